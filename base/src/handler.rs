@@ -29,8 +29,19 @@ impl Handler {
         commands.register(http, Arc::new(Mutex::new(qg_tictactoe::command()))).await?;
         commands.register(http, Arc::new(Mutex::new(qg_ulttictactoe::command()))).await?;
         commands.register(http, Arc::new(Mutex::new(qg_slidingpuzzle::command()))).await?;
+        commands.register(http, Arc::new(Mutex::new(qg_connectfour::command()))).await?;
+        commands.register(http, Arc::new(Mutex::new(qg_minesweeper::command()))).await?;
+        commands.register(http, Arc::new(Mutex::new(qg_wordle::command()))).await?;
         #[cfg(feature = "leaderboard")]
         commands.register(http, Arc::new(Mutex::new(qg_leaderboard::command()))).await?;
+        #[cfg(feature = "leaderboard")]
+        commands.register(http, Arc::new(Mutex::new(qg_profile::command()))).await?;
+        #[cfg(feature = "leaderboard")]
+        commands.register(http, Arc::new(Mutex::new(qg_tournament::command()))).await?;
+        #[cfg(feature = "leaderboard")]
+        commands.register(http, Arc::new(Mutex::new(qg_queue::command()))).await?;
+        #[cfg(feature = "leaderboard")]
+        commands.register(http, Arc::new(Mutex::new(qg_settings::command()))).await?;
         commands.finalize_registration(http).await?;
         Ok(())
     }
@@ -44,7 +55,23 @@ impl EventHandler for Handler {
         }
         log::info!("{} is connected!", ready.user.name);
     }
+    // only sent with the GUILD_MEMBERS intent, but it's free to keep the identity cache fresh when it is
+    async fn guild_member_update(&self, _ctx: Context, _old: Option<Member>, _new: Option<Member>, event: GuildMemberUpdateEvent) {
+        qg_shared::identity::observe(&event.user);
+    }
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        // anyone who interacts with us might end up on a leaderboard, so remember who they are while we have them
+        match &interaction {
+            Interaction::Command(cmd) => {
+                qg_shared::identity::observe(&cmd.user);
+                for user in cmd.data.resolved.users.values() {
+                    qg_shared::identity::observe(user);
+                }
+            }
+            Interaction::Component(cmp) => qg_shared::identity::observe(&cmp.user),
+            Interaction::Modal(mdl) => qg_shared::identity::observe(&mdl.user),
+            _ => {}
+        }
         match interaction {
             Interaction::Ping(p) => {
                 log::info!("Ping interaction {}", format!("{:?}", p).blue());
@@ -67,7 +94,9 @@ impl EventHandler for Handler {
                         #[cfg(not(feature = "leaderboard"))]
                         None::<sqlx::Transaction<'_, sqlx::Postgres>>
                     };
-                    if let Err(e) = command.lock().await.application_command(&ctx, &mut cmd, &mut tx).await {
+                    let ((result, unlocked), opening) =
+                        qg_shared::db::TournamentMatch::collect(qg_shared::db::Achievement::collect(command.lock().await.application_command(&ctx, &mut cmd, &mut tx))).await;
+                    if let Err(e) = result {
                         log::trace!("Error handling interaction for command {}: {}", name.blue(), e.to_string().red());
                         if cmd
                             .create_response(&ctx.http, {
@@ -102,12 +131,19 @@ impl EventHandler for Handler {
                             log::trace!("committing transaction");
                             if let Err(e) = tx.commit().await {
                                 log::error!("Error committing transaction: {}", e);
+                                // nothing was saved, so nothing was unlocked
+                                return;
                             } else {
                                 log::trace!("Commit transaction");
                             }
                         } else {
                             log::trace!("no transaction to commit");
                         }
+                        qg_shared::db::Achievement::notify(&ctx, unlocked).await;
+                        #[cfg(feature = "leaderboard")]
+                        qg_tournament::open_matches(&ctx, &self.pool, opening).await;
+                        #[cfg(not(feature = "leaderboard"))]
+                        let _ = opening;
                     }
                 } else {
                     log::warn!("Command {} not found", name.red());
@@ -146,7 +182,8 @@ impl EventHandler for Handler {
                         #[cfg(not(feature = "leaderboard"))]
                         None::<sqlx::Transaction<'_, sqlx::Postgres>>
                     };
-                    if let Err(e) = cmd.message_component(&ctx, &mut cmp, &mut tx).await {
+                    let ((result, unlocked), opening) = qg_shared::db::TournamentMatch::collect(qg_shared::db::Achievement::collect(cmd.message_component(&ctx, &mut cmp, &mut tx))).await;
+                    if let Err(e) = result {
                         log::trace!("Error handling interaction for command {}: {}", name.blue(), e.to_string().red());
                         if cmp
                             .create_response(&ctx.http, {
@@ -184,12 +221,19 @@ impl EventHandler for Handler {
                             log::trace!("committing transaction");
                             if let Err(e) = tx.commit().await {
                                 log::error!("Error committing transaction: {}", e);
+                                // nothing was saved, so nothing was unlocked
+                                return;
                             } else {
                                 log::trace!("Commit transaction");
                             }
                         } else {
                             log::trace!("no transaction to commit");
                         }
+                        qg_shared::db::Achievement::notify(&ctx, unlocked).await;
+                        #[cfg(feature = "leaderboard")]
+                        qg_tournament::open_matches(&ctx, &self.pool, opening).await;
+                        #[cfg(not(feature = "leaderboard"))]
+                        let _ = opening;
                     }
                 } else {
                     log::warn!("Command {} not found", name.red());
@@ -297,7 +341,8 @@ impl EventHandler for Handler {
                         #[cfg(not(feature = "leaderboard"))]
                         None::<sqlx::Transaction<'_, sqlx::Postgres>>
                     };
-                    if let Err(e) = command.lock().await.modal_submit(&ctx, &mut mdl, &mut tx).await {
+                    let (result, unlocked) = qg_shared::db::Achievement::collect(command.lock().await.modal_submit(&ctx, &mut mdl, &mut tx)).await;
+                    if let Err(e) = result {
                         log::trace!("Error handling interaction for command {}: {}", name.blue(), e.to_string().red());
                         if mdl
                             .create_response(
@@ -330,10 +375,13 @@ impl EventHandler for Handler {
                             log::trace!("committing transaction");
                             if let Err(e) = tx.commit().await {
                                 log::error!("Error committing transaction: {}", e);
+                                // nothing was saved, so nothing was unlocked
+                                return;
                             } else {
                                 log::trace!("Commit transaction");
                             }
                         }
+                        qg_shared::db::Achievement::notify(&ctx, unlocked).await;
                     }
                 } else {
                     log::warn!("Command {} not found", name.red());
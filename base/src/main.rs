@@ -37,6 +37,10 @@ async fn serenity(
         std::env::set_var("ALLOW_SELF_PLAY", b.to_string());
     }
 
+    if let Some(secret) = secret_store.get("STATE_SECRET") {
+        std::env::set_var("STATE_SECRET", secret);
+    }
+
     let dev_server = secret_store.get("DEV_SERVER").and_then(|f| f.parse::<serenity::model::id::GuildId>().ok());
 
     // replace with actually necessary intents eventually lol
@@ -0,0 +1,642 @@
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    serenity::all::*,
+    CycleVec, OptTrans,
+};
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// how long an open challenge waits for someone to accept, in seconds
+const OPEN_CHALLENGE_TIMEOUT: u64 = 60 * 10;
+
+const ROWS: usize = 6;
+const COLUMNS: usize = 7;
+const WIN_LENGTH: usize = 4;
+
+pub fn command() -> ConnectFour {
+    ConnectFour
+}
+
+pub struct ConnectFour;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for ConnectFour {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("connectfour"),
+            description: String::from("Play a game of Connect Four"),
+            options: vec![qg_shared::CommandOption {
+                name: String::from("opponent"),
+                description: String::from("The opponent to play against (leave empty for an open challenge anyone can accept)"),
+                option_type: qg_shared::CommandOptionType::User,
+                choices: qg_shared::UnorderedVec::from(vec![]),
+                required: false,
+            }]
+            .into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, _: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let mut players = vec![Player {
+            id: interaction.user.id,
+            piece: Piece::Red,
+        }];
+        // without an opponent anyone can claim the second seat
+        let mut invitee = None;
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "opponent") {
+            match option.value {
+                CommandDataOptionValue::User(user) => {
+                    let user = user.to_user(&ctx.http).await?;
+                    if user.bot {
+                        return Err(anyhow!("You cannot play against a bot"));
+                    }
+                    invitee = Some(user.id);
+                    players.push(Player { id: user.id, piece: Piece::Yellow });
+                }
+                _ => {
+                    return Err(anyhow!("Invalid opponent"));
+                }
+            }
+        }
+        if invitee == Some(interaction.user.id) && !qg_shared::allow_self_play() {
+            return Err(anyhow!("Playing with yourself is not pemitted"));
+        }
+
+        let game = Game {
+            players: CycleVec::new(players),
+            gamestate: State::AwaitingApproval(Awaiting {
+                inviter: interaction.user.id,
+                invitee,
+                expires_at: match invitee {
+                    Some(_) => 0,
+                    None => qg_shared::current_time()? + OPEN_CHALLENGE_TIMEOUT,
+                },
+            }),
+            last_time: qg_shared::current_time()?,
+        };
+
+        let (content, components) = game.message()?;
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components(components)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let action = match Action::from_custom_id(&interaction.data.custom_id) {
+            Some(action) => action,
+            None => return Err(anyhow!("Invalid action id")),
+        };
+
+        // get first line of message content, strip the ``` prefix and deserialize
+        let mut game = {
+            let mut lines = interaction.message.content.lines();
+            let game = lines.next().ok_or(anyhow!("No game data found"))?;
+            let game = game.strip_prefix("```").ok_or(anyhow!("No game data found"))?;
+            qg_shared::deserialize::<Game>(game)?
+        };
+
+        game.do_action(ctx, interaction, action, db).await?;
+
+        Ok(())
+    }
+}
+
+pub enum Action {
+    Accept,
+    Decline,
+    Cancel,
+    Rematch,
+    Resign,
+    Drop(usize), // the column
+}
+
+impl Action {
+    pub fn from_custom_id(custom_id: &str) -> Option<Self> {
+        let mut split = custom_id.split(':').skip(1);
+        let action = match split.next()? {
+            "Accept" => Self::Accept,
+            "Decline" => Self::Decline,
+            "Cancel" => Self::Cancel,
+            "Rematch" => Self::Rematch,
+            "Resign" => Self::Resign,
+            "Drop" => Self::Drop(split.next()?.parse().ok()?),
+            _ => return None,
+        };
+        if split.next().is_some() {
+            return None;
+        }
+        Some(action)
+    }
+    pub fn to_custom_id(&self) -> String {
+        format!("connectfour:{}", self.name())
+    }
+    pub fn name(&self) -> String {
+        match self {
+            Self::Accept => String::from("Accept"),
+            Self::Decline => String::from("Decline"),
+            Self::Cancel => String::from("Cancel"),
+            Self::Rematch => String::from("Rematch"),
+            Self::Resign => String::from("Resign"),
+            Self::Drop(column) => format!("Drop:{}", column),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Game {
+    players: CycleVec<Player>,
+    gamestate: State,
+    last_time: u64,
+}
+
+impl Game {
+    pub async fn do_action(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut OptTrans<'_>) -> Result<()> {
+        match self.gamestate {
+            State::AwaitingApproval(ref u) => {
+                if u.expired()? {
+                    self.gamestate = State::Cancelled("Nobody accepted in time".into());
+                    return self.render(ctx, interaction).await;
+                }
+                let (inviter, invitee) = (u.inviter, u.invitee);
+                match action {
+                    Action::Accept => {
+                        match invitee {
+                            Some(invitee) => {
+                                if interaction.user.id != invitee {
+                                    return Err(anyhow!("You are not the invitee"));
+                                }
+                            }
+                            None => {
+                                if interaction.user.id == inviter && !qg_shared::allow_self_play() {
+                                    return Err(anyhow!("You can't accept your own challenge"));
+                                }
+                                // first come first served, then shuffle so the challenger doesn't always go first
+                                self.players.push(Player {
+                                    id: interaction.user.id,
+                                    piece: Piece::Yellow,
+                                });
+                                self.players.shuffle();
+                            }
+                        }
+                        self.gamestate = State::InProgress(Board::new());
+                        self.notify_turn(ctx, interaction).await?;
+                    }
+                    Action::Decline => {
+                        if Some(interaction.user.id) != invitee {
+                            return Err(anyhow!("You are not the invitee"));
+                        }
+                        self.gamestate = State::Cancelled("Declined".into());
+                    }
+                    Action::Cancel => {
+                        if interaction.user.id != inviter {
+                            return Err(anyhow!("Only {} can cancel this challenge", inviter.mention()));
+                        }
+                        self.gamestate = State::Cancelled("Cancelled by the challenger".into());
+                    }
+                    _ => {
+                        return Err(anyhow!("Invalid action"));
+                    }
+                }
+            }
+            State::InProgress(ref mut board) => match action {
+                Action::Resign => {
+                    let player = *self.players.all().find(|p| p.id == interaction.user.id).ok_or(anyhow!("You are not playing in this game"))?;
+                    let winner = *self.players.all().find(|p| p.id != player.id).unwrap_or(&player);
+                    let board = board.clone();
+                    return self.finish(ctx, interaction, Outcome::Win(winner), board, Some(format!("{} resigned", player.id.mention())), db).await;
+                }
+                Action::Drop(column) => {
+                    let current = *self.players.current().ok_or(anyhow!("Player not found"))?;
+                    if current.id != interaction.user.id {
+                        return Err(anyhow!("It is not your turn"));
+                    }
+                    board.drop(column, current.piece)?;
+                    if let Some(outcome) = board.check_winner(&self.players) {
+                        let board = board.clone();
+                        return self.finish(ctx, interaction, outcome, board, None, db).await;
+                    }
+                    self.players.next_player();
+                    self.notify_turn(ctx, interaction).await?;
+                }
+                _ => {
+                    return Err(anyhow!("Invalid action: {}", action.name()));
+                }
+            },
+            State::Finished(ref mut finished) => match action {
+                Action::Rematch => {
+                    if !self.players.all().any(|p| p.id == interaction.user.id) {
+                        return Err(anyhow!("Only the players can start a rematch"));
+                    }
+                    if finished.rematched {
+                        return Err(anyhow!("A rematch has already been started"));
+                    }
+                    finished.rematched = true;
+                    // whoever went second goes first this time
+                    let rematch = Game {
+                        players: self.players.rotated(),
+                        gamestate: State::InProgress(Board::new()),
+                        last_time: qg_shared::current_time()?,
+                    };
+                    let (content, components) = rematch.message()?;
+                    interaction.channel_id.send_message(&ctx.http, CreateMessage::default().content(content).components(components)).await?;
+                }
+                _ => {
+                    return Err(anyhow!("Invalid action: {}", action.name()));
+                }
+            },
+            State::Cancelled(_) => {
+                return Err(anyhow!("Invalid action: {}", action.name()));
+            }
+        }
+
+        self.render(ctx, interaction).await
+    }
+
+    /// lets the next player know it's their turn, unless they've been playing quickly enough to not need it
+    async fn notify_turn(&mut self, ctx: &Context, interaction: &ComponentInteraction) -> Result<()> {
+        let pid = self.players.current().ok_or(anyhow!("Player not found"))?.id;
+        if pid == interaction.user.id {
+            return Ok(());
+        }
+        let now = qg_shared::current_time()?;
+        if now.saturating_sub(self.last_time) > 60 {
+            ctx.http
+                .get_user(pid)
+                .await?
+                .create_dm_channel(&ctx.http)
+                .await?
+                .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", interaction.message.link())))
+                .await?;
+        }
+        self.last_time = now;
+        Ok(())
+    }
+
+    async fn render(&self, ctx: &Context, interaction: &mut ComponentInteraction) -> Result<()> {
+        let (content, components) = self.message()?;
+        interaction.defer(&ctx.http).await?;
+        interaction.edit_response(&ctx.http, EditInteractionResponse::default().content(content).components(components)).await?;
+        Ok(())
+    }
+
+    fn message(&self) -> Result<(String, Vec<CreateActionRow>)> {
+        let mut content = format!("```{}\nConnect Four\n```", qg_shared::serialize(&self)?.replace('\n', ""));
+        let components = match self.gamestate {
+            State::AwaitingApproval(ref u) => {
+                content.push_str(&u.challenge_message());
+                u.components()
+            }
+            State::InProgress(ref board) => {
+                let current = self.players.current().ok_or(anyhow!("Player not found"))?;
+                content.push_str(&format!("It is {}'s turn [{}]\n", current.id.mention(), current.piece));
+                content.push_str(&board.to_string());
+                board.components()
+            }
+            State::Finished(ref finished) => {
+                content.push_str(&finished.win_message());
+                content.push('\n');
+                content.push_str(&finished.board.to_string());
+                vec![CreateActionRow::Buttons(vec![CreateButton::new(Action::Rematch.to_custom_id())
+                    .style(ButtonStyle::Primary)
+                    .label("Rematch")
+                    .disabled(finished.rematched)])]
+            }
+            State::Cancelled(ref reason) => {
+                content.push_str(&format!("Game cancelled: {}", reason));
+                vec![]
+            }
+        };
+        Ok((content, components))
+    }
+
+    /// ends the game, letting the players know and recording the result. `reason` explains an ending that didn't happen on the board
+    async fn finish(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, winner: Outcome, board: Board, reason: Option<String>, db: &mut OptTrans<'_>) -> Result<()> {
+        self.gamestate = State::Finished(Finished {
+            winner: winner.clone(),
+            board,
+            rematched: false,
+            reason,
+        });
+        // render early so the messages and sql stuff doesnt lag the update
+        self.render(ctx, interaction).await?;
+        for player in self.players.all() {
+            let content = match winner {
+                Outcome::Win(p) => format!("You {} in {}", if *player == p { "won" } else { "lost" }, interaction.message.link()),
+                Outcome::Tie => format!("You tied in {}", interaction.message.link()),
+            };
+            match ctx.http.get_user(player.id).await {
+                Ok(user) => match user.create_dm_channel(&ctx.http).await {
+                    Ok(dm) => {
+                        if let Err(e) = dm.send_message(&ctx.http, CreateMessage::default().content(content)).await {
+                            qg_shared::log::trace!("Failed to send message to user: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        qg_shared::log::trace!("Failed to create dm channel: {}", e);
+                    }
+                },
+                Err(e) => {
+                    qg_shared::log::trace!("Failed to get user: {}", e);
+                }
+            }
+        }
+        if let (Some(db), Some(winner)) = (db, winner.winner()) {
+            let mut players: HashMap<UserId, qg_shared::db::User> = HashMap::new();
+            for player in self.players.all() {
+                players.insert(player.id, qg_shared::db::User::get_or_create(ctx, &player.id, db).await?);
+            }
+            for player in self.players.all() {
+                let user = &players[&player.id];
+                let opponent = &players[&self.players.all().find(|p| p.id != player.id).unwrap_or(player).id];
+                if user.id != opponent.id {
+                    qg_shared::db::ConnectFour::create(user.id as i32, opponent.id as i32, player.id == winner.id, interaction.guild_id.map(|g| g.get() as i64), db).await?;
+                    let unlocked = qg_shared::db::Achievement::evaluate(user, &qg_shared::db::GameResult::ConnectFour { won: player.id == winner.id }, db).await?;
+                    qg_shared::db::Achievement::notify_later(player.id, unlocked);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Piece {
+    Red,
+    Yellow,
+    Empty,
+}
+
+impl std::fmt::Display for Piece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Piece::Red => write!(f, "🔴"),
+            Piece::Yellow => write!(f, "🟡"),
+            Piece::Empty => write!(f, "⚫"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum State {
+    AwaitingApproval(Awaiting),
+    InProgress(Board),
+    Finished(Finished),
+    Cancelled(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Awaiting {
+    inviter: UserId,
+    invitee: Option<UserId>, // None is an open challenge
+    expires_at: u64,         // 0 never expires
+}
+
+impl Awaiting {
+    fn challenge_message(&self) -> String {
+        match self.invitee {
+            Some(invitee) => format!("{} has challenged {} to a game of Connect Four", self.inviter.mention(), invitee.mention()),
+            None => format!(
+                "{} is looking for an opponent for a game of Connect Four, first to accept plays! (expires <t:{}:R>)",
+                self.inviter.mention(),
+                self.expires_at
+            ),
+        }
+    }
+    fn expired(&self) -> Result<bool> {
+        Ok(self.expires_at != 0 && qg_shared::current_time()? > self.expires_at)
+    }
+    fn components(&self) -> Vec<CreateActionRow> {
+        let mut buttons = vec![CreateButton::new(Action::Accept.to_custom_id()).style(ButtonStyle::Success).label("Accept")];
+        if self.invitee.is_some() {
+            buttons.push(CreateButton::new(Action::Decline.to_custom_id()).style(ButtonStyle::Danger).label("Decline"));
+        }
+        buttons.push(CreateButton::new(Action::Cancel.to_custom_id()).style(ButtonStyle::Secondary).label("Cancel"));
+        vec![CreateActionRow::Buttons(buttons)]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Finished {
+    winner: Outcome,
+    board: Board,
+    rematched: bool,
+    reason: Option<String>, // why the game ended off the board, a resignation
+}
+
+impl Finished {
+    fn win_message(&self) -> String {
+        let message = match self.winner {
+            Outcome::Win(player) => format!("{} [{}] has won!", player.id.mention(), player.piece),
+            Outcome::Tie => String::from("It's a tie!"),
+        };
+        match self.reason {
+            Some(ref reason) => format!("{} ({})", message, reason),
+            None => message,
+        }
+    }
+}
+
+/// the grid, top row first. too big for buttons, so it's drawn in the message and only the columns get buttons
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Board {
+    rows: Vec<Vec<Piece>>,
+}
+
+impl Board {
+    fn new() -> Self {
+        Self {
+            rows: vec![vec![Piece::Empty; COLUMNS]; ROWS],
+        }
+    }
+
+    fn column_full(&self, column: usize) -> bool {
+        self.rows[0][column] != Piece::Empty
+    }
+
+    /// the piece falls to the lowest empty space in the column
+    fn drop(&mut self, column: usize, piece: Piece) -> Result<()> {
+        if column >= COLUMNS {
+            return Err(anyhow!("Invalid move, out of bounds"));
+        }
+        let row = self.rows.iter().rposition(|row| row[column] == Piece::Empty).ok_or(anyhow!("Invalid move, that column is full"))?;
+        self.rows[row][column] = piece;
+        Ok(())
+    }
+
+    fn check_winner(&self, players: &CycleVec<Player>) -> Option<Outcome> {
+        for row in 0..ROWS as isize {
+            for column in 0..COLUMNS as isize {
+                let piece = self.rows[row as usize][column as usize];
+                if piece == Piece::Empty {
+                    continue;
+                }
+                // each line is found from its first space, so only looking forwards is enough
+                for (dr, dc) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                    let line = (0..WIN_LENGTH as isize).all(|i| {
+                        let (row, column) = (row + dr * i, column + dc * i);
+                        (0..ROWS as isize).contains(&row) && (0..COLUMNS as isize).contains(&column) && self.rows[row as usize][column as usize] == piece
+                    });
+                    if line {
+                        return Some(Outcome::Win(players.all().find(|p| p.piece == piece).copied()?));
+                    }
+                }
+            }
+        }
+        if (0..COLUMNS).all(|column| self.column_full(column)) {
+            return Some(Outcome::Tie);
+        }
+        None
+    }
+
+    /// a button per column over two rows, with resign tucked in after the last column
+    fn components(&self) -> Vec<CreateActionRow> {
+        let mut buttons = (0..COLUMNS)
+            .map(|column| {
+                CreateButton::new(Action::Drop(column).to_custom_id())
+                    .style(ButtonStyle::Secondary)
+                    .label((column + 1).to_string())
+                    .disabled(self.column_full(column))
+            })
+            .collect::<Vec<CreateButton>>();
+        let mut second = buttons.split_off(4);
+        second.push(CreateButton::new(Action::Resign.to_custom_id()).style(ButtonStyle::Danger).label("Resign"));
+        vec![CreateActionRow::Buttons(buttons), CreateActionRow::Buttons(second)]
+    }
+}
+
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows.iter() {
+            writeln!(f, "{}", row.iter().map(|piece| piece.to_string()).collect::<String>())?;
+        }
+        writeln!(f, "1️⃣2️⃣3️⃣4️⃣5️⃣6️⃣7️⃣")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Outcome {
+    Win(Player),
+    Tie,
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            Self::Win(player) => Some(*player),
+            Self::Tie => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Player {
+    id: UserId,
+    piece: Piece,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Player = Player {
+        id: UserId::new(1),
+        piece: Piece::Red,
+    };
+    const YELLOW: Player = Player {
+        id: UserId::new(2),
+        piece: Piece::Yellow,
+    };
+
+    /// a board from its rows top first, `R`, `Y` and `.` for empty
+    fn board(rows: [&str; ROWS]) -> Board {
+        Board {
+            rows: rows
+                .iter()
+                .map(|row| {
+                    row.chars()
+                        .map(|c| match c {
+                            'R' => Piece::Red,
+                            'Y' => Piece::Yellow,
+                            _ => Piece::Empty,
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    fn winner(rows: [&str; ROWS]) -> Option<Player> {
+        let players = CycleVec::new(vec![RED, YELLOW]);
+        match board(rows).check_winner(&players)? {
+            Outcome::Win(player) => Some(player),
+            Outcome::Tie => None,
+        }
+    }
+
+    #[test]
+    fn drop_falls_to_the_lowest_empty_space() {
+        let mut board = Board::new();
+        board.drop(3, Piece::Red).unwrap();
+        board.drop(3, Piece::Yellow).unwrap();
+        board.drop(0, Piece::Red).unwrap();
+        assert_eq!(board.rows[ROWS - 1][3], Piece::Red);
+        assert_eq!(board.rows[ROWS - 2][3], Piece::Yellow);
+        assert_eq!(board.rows[ROWS - 1][0], Piece::Red);
+        assert_eq!(board.rows[ROWS - 2][0], Piece::Empty);
+    }
+
+    #[test]
+    fn drop_rejects_full_columns_and_out_of_bounds() {
+        let mut board = Board::new();
+        for _ in 0..ROWS {
+            board.drop(6, Piece::Red).unwrap();
+        }
+        assert!(board.column_full(6));
+        assert!(board.drop(6, Piece::Yellow).is_err());
+        assert!(board.drop(COLUMNS, Piece::Yellow).is_err());
+        assert_eq!(board.rows.iter().filter(|row| row[6] == Piece::Red).count(), ROWS);
+    }
+
+    #[test]
+    fn lines_along_the_edges() {
+        assert_eq!(winner(["...RRRR", ".......", ".......", ".......", ".......", "YYY...."]), Some(RED));
+        assert_eq!(winner([".......", ".......", "Y......", "Y......", "Y......", "Y......"]), Some(YELLOW));
+        assert_eq!(winner(["......R", "......R", "......R", "......R", ".......", "......."]), Some(RED));
+        assert_eq!(winner([".......", ".......", ".......", ".......", ".......", "YYYRRRR"]), Some(RED));
+        assert_eq!(winner([".......", ".......", ".......", ".......", ".......", "RRRYYY."]), None);
+    }
+
+    #[test]
+    fn diagonals() {
+        assert_eq!(winner(["R......", ".R.....", "..R....", "...R...", ".......", "......."]), Some(RED));
+        assert_eq!(winner([".......", ".......", "...Y...", "....Y..", ".....Y.", "......Y"]), Some(YELLOW));
+        assert_eq!(winner(["R......", ".R.....", "..R....", ".......", ".......", "......."]), None);
+    }
+
+    #[test]
+    fn anti_diagonals() {
+        assert_eq!(winner(["......R", ".....R.", "....R..", "...R...", ".......", "......."]), Some(RED));
+        assert_eq!(winner([".......", ".......", "...Y...", "..Y....", ".Y.....", "Y......"]), Some(YELLOW));
+        assert_eq!(winner([".......", ".......", ".......", "..Y....", ".Y.....", "Y......"]), None);
+    }
+
+    #[test]
+    fn lines_dont_wrap_around() {
+        // the end of one row followed by the start of the next isn't a line
+        assert_eq!(winner([".......", ".......", ".......", ".......", ".....RR", "RR....."]), None);
+        assert_eq!(winner([".......", ".......", ".......", "......R", "R......", ".R....."]), None);
+    }
+
+    #[test]
+    fn full_board_without_a_line_is_a_tie() {
+        let players = CycleVec::new(vec![RED, YELLOW]);
+        let full = board(["RRYYRRY", "YYRRYYR", "RRYYRRY", "YYRRYYR", "RRYYRRY", "YYRRYYR"]);
+        assert!(matches!(full.check_winner(&players), Some(Outcome::Tie)));
+    }
+}
@@ -1,4 +1,5 @@
 use qg_shared::{serenity::all::*, UnorderedVec};
+use serde::{Deserialize, Serialize};
 
 pub fn command() -> LeaderboardCommand {
     LeaderboardCommand
@@ -8,63 +9,6 @@ pub struct LeaderboardCommand;
 
 #[qg_shared::async_trait]
 impl qg_shared::Command for LeaderboardCommand {
-    // fn register<'a>(&self, builder: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
-    //     let info = self.get_command_info();
-    //     builder.name(info.name).description(info.description);
-    //     builder
-    // }
-
-    fn register(&self) -> CreateCommand {
-        let info = self.get_command_info();
-        CreateCommand::new(info.name).description(info.description).set_options(vec![
-            CreateCommandOption::new(CommandOptionType::SubCommandGroup, "slidingpuzzle", "Sliding Puzzle Leaderboards")
-                .add_sub_option(
-                    CreateCommandOption::new(CommandOptionType::SubCommand, "3x3", "3x3 Sliding Puzzle Leaderboards")
-                        .add_sub_option(
-                            CreateCommandOption::new(CommandOptionType::String, "sort", "value to sort by when getting the leaderboard (defaults to score)")
-                                .add_string_choice("score", "score")
-                                .add_string_choice("time", "time"),
-                        )
-                        .add_sub_option(
-                            CreateCommandOption::new(CommandOptionType::String, "difficulty", "difficulty to filter by when getting the leaderboard (defaults to easy)")
-                                .add_string_choice("easy", "easy")
-                                .add_string_choice("medium", "medium")
-                                .add_string_choice("hard", "hard"),
-                        ),
-                )
-                .add_sub_option(
-                    CreateCommandOption::new(CommandOptionType::SubCommand, "4x4", "4x4 Sliding Puzzle Leaderboards")
-                        .add_sub_option(
-                            CreateCommandOption::new(CommandOptionType::String, "sort", "value to sort by when getting the leaderboard (defaults to score)")
-                                .add_string_choice("score", "score")
-                                .add_string_choice("time", "time"),
-                        )
-                        .add_sub_option(
-                            CreateCommandOption::new(CommandOptionType::String, "difficulty", "difficulty to filter by when getting the leaderboard (defaults to easy)")
-                                .add_string_choice("easy", "easy")
-                                .add_string_choice("medium", "medium")
-                                .add_string_choice("hard", "hard"),
-                        ),
-                )
-                .add_sub_option(
-                    CreateCommandOption::new(CommandOptionType::SubCommand, "5x5", "5x5 Sliding Puzzle Leaderboards")
-                        .add_sub_option(
-                            CreateCommandOption::new(CommandOptionType::String, "sort", "value to sort by when getting the leaderboard (defaults to score)")
-                                .add_string_choice("score", "score")
-                                .add_string_choice("time", "time"),
-                        )
-                        .add_sub_option(
-                            CreateCommandOption::new(CommandOptionType::String, "difficulty", "difficulty to filter by when getting the leaderboard (defaults to easy)")
-                                .add_string_choice("easy", "easy")
-                                .add_string_choice("medium", "medium")
-                                .add_string_choice("hard", "hard"),
-                        ),
-                ),
-            CreateCommandOption::new(CommandOptionType::SubCommand, "tictactoe", "Tic Tac Toe Leaderboards"),
-            CreateCommandOption::new(CommandOptionType::SubCommand, "ultimate_tictactoe", "Ultimate Tic Tac Toe Leaderboards"),
-        ])
-    }
-
     fn get_command_info(&self) -> qg_shared::CommandInfo {
         qg_shared::CommandInfo {
             name: String::from("leaderboard"),
@@ -75,135 +19,16 @@ impl qg_shared::Command for LeaderboardCommand {
                     description: String::from("Sliding Puzzle Leaderboards"),
                     choices: qg_shared::UnorderedVec::from(vec![]),
                     option_type: qg_shared::CommandOptionType::SubCommandGroup(UnorderedVec::from(vec![
+                        slidingpuzzle_subcommand("3x3"),
+                        slidingpuzzle_subcommand("4x4"),
+                        slidingpuzzle_subcommand("5x5"),
+                        slidingpuzzle_subcommand("6x6"),
+                        slidingpuzzle_subcommand("7x7"),
+                        slidingpuzzle_subcommand("8x8"),
                         qg_shared::CommandOption {
-                            name: String::from("3x3"),
-                            description: String::from("3x3 Sliding Puzzle Leaderboards"),
-                            option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
-                                qg_shared::CommandOption {
-                                    name: String::from("sort"),
-                                    description: String::from("value to sort by when getting the leaderboard (defaults to score)"),
-                                    option_type: qg_shared::CommandOptionType::String,
-                                    choices: UnorderedVec::from(vec![
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("score"),
-                                            value: String::from("score"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("time"),
-                                            value: String::from("time"),
-                                        },
-                                    ]),
-                                    required: false,
-                                },
-                                qg_shared::CommandOption {
-                                    name: String::from("difficulty"),
-                                    description: String::from("difficulty to filter by when getting the leaderboard (defaults to easy)"),
-                                    option_type: qg_shared::CommandOptionType::String,
-                                    choices: UnorderedVec::from(vec![
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("easy"),
-                                            value: String::from("easy"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("medium"),
-                                            value: String::from("medium"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("hard"),
-                                            value: String::from("hard"),
-                                        },
-                                    ]),
-                                    required: false,
-                                },
-                            ])),
-                            choices: qg_shared::UnorderedVec::from(vec![]),
-                            required: false,
-                        },
-                        qg_shared::CommandOption {
-                            name: String::from("4x4"),
-                            description: String::from("4x4 Sliding Puzzle Leaderboards"),
-                            option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
-                                qg_shared::CommandOption {
-                                    name: String::from("sort"),
-                                    description: String::from("value to sort by when getting the leaderboard (defaults to score)"),
-                                    option_type: qg_shared::CommandOptionType::String,
-                                    choices: UnorderedVec::from(vec![
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("score"),
-                                            value: String::from("score"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("time"),
-                                            value: String::from("time"),
-                                        },
-                                    ]),
-                                    required: false,
-                                },
-                                qg_shared::CommandOption {
-                                    name: String::from("difficulty"),
-                                    description: String::from("difficulty to filter by when getting the leaderboard (defaults to easy)"),
-                                    option_type: qg_shared::CommandOptionType::String,
-                                    choices: UnorderedVec::from(vec![
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("easy"),
-                                            value: String::from("easy"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("medium"),
-                                            value: String::from("medium"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("hard"),
-                                            value: String::from("hard"),
-                                        },
-                                    ]),
-                                    required: false,
-                                },
-                            ])),
-                            choices: qg_shared::UnorderedVec::from(vec![]),
-                            required: false,
-                        },
-                        qg_shared::CommandOption {
-                            name: String::from("5x5"),
-                            description: String::from("5x5 Sliding Puzzle Leaderboards"),
-                            option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
-                                qg_shared::CommandOption {
-                                    name: String::from("sort"),
-                                    description: String::from("value to sort by when getting the leaderboard (defaults to score)"),
-                                    option_type: qg_shared::CommandOptionType::String,
-                                    choices: UnorderedVec::from(vec![
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("score"),
-                                            value: String::from("score"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("time"),
-                                            value: String::from("time"),
-                                        },
-                                    ]),
-                                    required: false,
-                                },
-                                qg_shared::CommandOption {
-                                    name: String::from("difficulty"),
-                                    description: String::from("difficulty to filter by when getting the leaderboard (defaults to easy)"),
-                                    option_type: qg_shared::CommandOptionType::String,
-                                    choices: UnorderedVec::from(vec![
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("easy"),
-                                            value: String::from("easy"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("medium"),
-                                            value: String::from("medium"),
-                                        },
-                                        qg_shared::CommandOptionChoice {
-                                            name: String::from("hard"),
-                                            value: String::from("hard"),
-                                        },
-                                    ]),
-                                    required: false,
-                                },
-                            ])),
+                            name: String::from("race"),
+                            description: String::from("Sliding Puzzle Race Leaderboards"),
+                            option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![period_option(), scope_option()])),
                             choices: qg_shared::UnorderedVec::from(vec![]),
                             required: false,
                         },
@@ -213,14 +38,45 @@ impl qg_shared::Command for LeaderboardCommand {
                 qg_shared::CommandOption {
                     name: String::from("tictactoe"),
                     description: String::from("Tic Tac Toe Leaderboards"),
-                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![])),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
+                        string_option("size", "board size to get the leaderboard for (defaults to 3x3)", &[("3x3", "3"), ("4x4", "4"), ("5x5", "5")]),
+                        string_option(
+                            "win_length",
+                            "how many in a row the games were played to (defaults to 3)",
+                            &[("3 in a row", "3"), ("4 in a row", "4"), ("5 in a row", "5")],
+                        ),
+                        period_option(),
+                        scope_option(),
+                    ])),
                     choices: qg_shared::UnorderedVec::from(vec![]),
                     required: false,
                 },
                 qg_shared::CommandOption {
                     name: String::from("ultimate_tictactoe"),
                     description: String::from("Ultimate Tic Tac Toe Leaderboards"),
-                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![])),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![period_option(), scope_option()])),
+                    choices: qg_shared::UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("connectfour"),
+                    description: String::from("Connect Four Leaderboards"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![period_option(), scope_option()])),
+                    choices: qg_shared::UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("minesweeper"),
+                    description: String::from("Minesweeper Leaderboards"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
+                        string_option(
+                            "difficulty",
+                            "difficulty to filter by when getting the leaderboard (defaults to easy)",
+                            &[("easy", "easy"), ("medium", "medium"), ("hard", "hard")],
+                        ),
+                        period_option(),
+                        scope_option(),
+                    ])),
                     choices: qg_shared::UnorderedVec::from(vec![]),
                     required: false,
                 },
@@ -255,6 +111,110 @@ impl qg_shared::Command for LeaderboardCommand {
 
         Ok(())
     }
+
+    async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut qg_shared::OptTrans<'_>) -> qg_shared::anyhow::Result<()> {
+        let tx = match db {
+            Some(db) => db,
+            None => return Err(qg_shared::anyhow::anyhow!("No database connection")),
+        };
+
+        // get first line of message content, strip the ``` prefix and deserialize
+        let mut leaderboard = {
+            let mut lines = interaction.message.content.lines();
+            let leaderboard = lines.next().ok_or(qg_shared::anyhow::anyhow!("No leaderboard data found"))?;
+            let leaderboard = leaderboard.strip_prefix("```").ok_or(qg_shared::anyhow::anyhow!("No leaderboard data found"))?;
+            qg_shared::deserialize::<Leaderboard>(leaderboard)?
+        };
+
+        match Action::from_custom_id(&interaction.data.custom_id)? {
+            Action::Prev => leaderboard.previous_page(),
+            Action::Next => leaderboard.next_page(),
+        }
+
+        let (content, embed, components) = Self::render_page(&leaderboard, tx).await?;
+
+        interaction.defer(&ctx.http).await?;
+        interaction
+            .edit_response(&ctx.http, EditInteractionResponse::default().content(content).embeds(vec![embed]).components(components))
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn slidingpuzzle_subcommand(size: &str) -> qg_shared::CommandOption {
+    qg_shared::CommandOption {
+        name: size.to_string(),
+        description: format!("{} Sliding Puzzle Leaderboards", size),
+        option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
+            string_option("sort", "value to sort by when getting the leaderboard (defaults to score)", &[("score", "score"), ("time", "time")]),
+            string_option(
+                "difficulty",
+                "difficulty to filter by when getting the leaderboard (defaults to easy)",
+                &[("easy", "easy"), ("medium", "medium"), ("hard", "hard")],
+            ),
+            period_option(),
+            scope_option(),
+        ])),
+        choices: qg_shared::UnorderedVec::from(vec![]),
+        required: false,
+    }
+}
+
+fn period_option() -> qg_shared::CommandOption {
+    string_option(
+        "period",
+        "window of time to get the leaderboard for (defaults to all time)",
+        &[("daily", "daily"), ("weekly", "weekly"), ("monthly", "monthly"), ("all time", "alltime")],
+    )
+}
+
+fn scope_option() -> qg_shared::CommandOption {
+    string_option(
+        "scope",
+        "whether to only show games played in this server (defaults to global)",
+        &[("server", "server"), ("global", "global")],
+    )
+}
+
+fn string_option(name: &str, description: &str, choices: &[(&str, &str)]) -> qg_shared::CommandOption {
+    qg_shared::CommandOption {
+        name: name.to_string(),
+        description: description.to_string(),
+        option_type: qg_shared::CommandOptionType::String,
+        choices: UnorderedVec::from(
+            choices
+                .iter()
+                .map(|(name, value)| qg_shared::CommandOptionChoice {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+        ),
+        required: false,
+    }
+}
+
+/// the guild to filter by, None for the global leaderboard
+fn parse_scope(option: &CommandDataOption, guild_id: Option<GuildId>) -> qg_shared::anyhow::Result<Option<i64>> {
+    match option.value {
+        CommandDataOptionValue::String(ref value) => match value.as_str() {
+            "server" => match guild_id {
+                Some(guild_id) => Ok(Some(guild_id.get() as i64)),
+                None => Err(qg_shared::anyhow::anyhow!("Server leaderboards are only available in servers")),
+            },
+            "global" => Ok(None),
+            _ => Err(qg_shared::anyhow::anyhow!("Unhandled value `{}`", value)),
+        },
+        _ => Err(qg_shared::anyhow::anyhow!("Expected String, got {:?}", option.value)),
+    }
+}
+
+fn parse_period(option: &CommandDataOption) -> qg_shared::anyhow::Result<qg_shared::db::Period> {
+    match option.value {
+        CommandDataOptionValue::String(ref value) => qg_shared::db::Period::from_name(value).ok_or(qg_shared::anyhow::anyhow!("Unhandled value `{}`", value)),
+        _ => Err(qg_shared::anyhow::anyhow!("Expected String, got {:?}", option.value)),
+    }
 }
 
 impl LeaderboardCommand {
@@ -279,7 +239,7 @@ impl LeaderboardCommand {
                     return Err(qg_shared::anyhow::anyhow!("Expected SubCommandGroup, got {:?}", option.value));
                 }
             },
-            t if t == "3x3" || t == "4x4" || t == "5x5" => match option.value {
+            t if ["3x3", "4x4", "5x5", "6x6", "7x7", "8x8"].contains(&t) => match option.value {
                 CommandDataOptionValue::SubCommand(ref options) => {
                     let mut filters = qg_shared::db::SlidingPuzzleFilters::default();
 
@@ -294,6 +254,15 @@ impl LeaderboardCommand {
                         "5x5" => {
                             filters.fivebyfive();
                         }
+                        "6x6" => {
+                            filters.sixbysix();
+                        }
+                        "7x7" => {
+                            filters.sevenbyseven();
+                        }
+                        "8x8" => {
+                            filters.eightbyeight();
+                        }
                         _ => unreachable!(),
                     }
 
@@ -334,33 +303,122 @@ impl LeaderboardCommand {
                                     return Err(qg_shared::anyhow::anyhow!("Expected String, got {:?}", option.value));
                                 }
                             },
+                            "period" => {
+                                filters.period(parse_period(option)?);
+                            }
+                            "scope" => match parse_scope(option, interaction.guild_id)? {
+                                Some(guild_id) => {
+                                    filters.server(guild_id);
+                                }
+                                None => {
+                                    filters.global();
+                                }
+                            },
                             v => {
                                 return Err(qg_shared::anyhow::anyhow!("Unhandled option `{}`", v));
                             }
                         }
                     }
 
-                    let (leaderboard, more_available) = qg_shared::db::SlidingPuzzle::get_standings(filters, tx).await?;
-
-                    Self::send_slidingpuzzle_leaderboard(ctx, &leaderboard, interaction, t, more_available).await?;
+                    let leaderboard = Leaderboard::SlidingPuzzle { size: t.to_string(), filters };
+                    Self::send_leaderboard(ctx, &leaderboard, interaction, tx).await?;
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("Expected SubCommand, got {:?}", option.value));
+                }
+            },
+            "race" => match option.value {
+                CommandDataOptionValue::SubCommand(ref options) => {
+                    let leaderboard = Leaderboard::SlidingPuzzleRace(Self::rating_filters(options, interaction.guild_id)?);
+                    Self::send_leaderboard(ctx, &leaderboard, interaction, tx).await?;
                 }
                 _ => {
                     return Err(qg_shared::anyhow::anyhow!("Expected SubCommand, got {:?}", option.value));
                 }
             },
             "tictactoe" => match option.value {
-                CommandDataOptionValue::SubCommand(ref _options) => {
-                    let (mut standings, more) = qg_shared::db::TicTacToe::get_standings(tx).await?;
-                    Self::send_tictactoe_leaderboard(ctx, &mut standings, interaction, more).await?;
+                CommandDataOptionValue::SubCommand(ref options) => {
+                    let (mut size, mut win_length) = (3, 3);
+                    for option in options {
+                        match (option.name.as_str(), &option.value) {
+                            ("size", CommandDataOptionValue::String(value)) => size = value.parse()?,
+                            ("win_length", CommandDataOptionValue::String(value)) => win_length = value.parse()?,
+                            _ => {}
+                        }
+                    }
+                    let variant = qg_shared::db::TicTacToeVariant::new(size, win_length)?;
+                    // the rest are the usual win/loss filters
+                    let options = options.iter().filter(|option| option.name != "size" && option.name != "win_length").cloned().collect::<Vec<_>>();
+                    let leaderboard = Leaderboard::TicTacToe {
+                        variant,
+                        filters: Self::rating_filters(&options, interaction.guild_id)?,
+                    };
+                    Self::send_leaderboard(ctx, &leaderboard, interaction, tx).await?;
                 }
                 _ => {
                     return Err(qg_shared::anyhow::anyhow!("Expected SubCommand, got {:?}", option.value));
                 }
             },
             "ultimate_tictactoe" => match option.value {
-                CommandDataOptionValue::SubCommand(ref _options) => {
-                    let (mut standings, more) = qg_shared::db::UltimateTicTacToe::get_standings(tx).await?;
-                    Self::send_ultimate_tictactoe_leaderboard(ctx, &mut standings, interaction, more).await?;
+                CommandDataOptionValue::SubCommand(ref options) => {
+                    let leaderboard = Leaderboard::UltimateTicTacToe(Self::rating_filters(options, interaction.guild_id)?);
+                    Self::send_leaderboard(ctx, &leaderboard, interaction, tx).await?;
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("Expected SubCommand, got {:?}", option.value));
+                }
+            },
+            "connectfour" => match option.value {
+                CommandDataOptionValue::SubCommand(ref options) => {
+                    let leaderboard = Leaderboard::ConnectFour(Self::rating_filters(options, interaction.guild_id)?);
+                    Self::send_leaderboard(ctx, &leaderboard, interaction, tx).await?;
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("Expected SubCommand, got {:?}", option.value));
+                }
+            },
+            "minesweeper" => match option.value {
+                CommandDataOptionValue::SubCommand(ref options) => {
+                    let mut filters = qg_shared::db::MinesweeperFilters::default();
+                    for option in options {
+                        match option.name.as_str() {
+                            "difficulty" => match option.value {
+                                CommandDataOptionValue::String(ref value) => match value.as_str() {
+                                    "easy" => {
+                                        filters.easy();
+                                    }
+                                    "medium" => {
+                                        filters.medium();
+                                    }
+                                    "hard" => {
+                                        filters.hard();
+                                    }
+                                    _ => {
+                                        return Err(qg_shared::anyhow::anyhow!("Unhandled value `{}`", value));
+                                    }
+                                },
+                                _ => {
+                                    return Err(qg_shared::anyhow::anyhow!("Expected String, got {:?}", option.value));
+                                }
+                            },
+                            "period" => {
+                                filters.period(parse_period(option)?);
+                            }
+                            "scope" => match parse_scope(option, interaction.guild_id)? {
+                                Some(guild_id) => {
+                                    filters.server(guild_id);
+                                }
+                                None => {
+                                    filters.global();
+                                }
+                            },
+                            v => {
+                                return Err(qg_shared::anyhow::anyhow!("Unhandled option `{}`", v));
+                            }
+                        }
+                    }
+                    let leaderboard = Leaderboard::Minesweeper(filters);
+                    Self::send_leaderboard(ctx, &leaderboard, interaction, tx).await?;
                 }
                 _ => {
                     return Err(qg_shared::anyhow::anyhow!("Expected SubCommand, got {:?}", option.value));
@@ -374,164 +432,251 @@ impl LeaderboardCommand {
         Ok(())
     }
 
-    async fn send_tictactoe_leaderboard(ctx: &Context, leaderboard: &mut [qg_shared::db::TTTLeaderboardEntry], interaction: &mut CommandInteraction, _more_available: bool) -> Result<()> {
-        let mut message = CreateInteractionResponseMessage::new();
-
-        message = message.content("`Tic Tac Toe Leaderboard`");
-
-        leaderboard.sort_by(|a, b| {
-            b.rating.partial_cmp(&a.rating).unwrap_or({
-                // if the rating fails to compare, compare by wins
-                b.wins.cmp(&a.wins)
-            })
-        });
-
-        for (i, entry) in leaderboard.iter().enumerate() {
-            let mut embed = CreateEmbed::default();
-
-            embed = embed.fields(vec![
-                ("Wins", format!("{}", entry.wins), true),
-                // ("Losses", format!("{}", entry.losses), true),
-                ("Rating", format!("{}", entry.rating), true),
-            ]);
-
-            let fancyuser = Self::get_author(&entry.user, ctx, interaction).await;
-
-            let mut author = CreateEmbedAuthor::new(format!("#{}: {} ({})", i + 1, fancyuser.name, fancyuser.discord_id));
-
-            if let Some(img) = fancyuser.avatar {
-                author = author.icon_url(img);
-            }
-
-            embed = embed.author(author);
-
-            message = message.add_embed(embed);
-        }
-
-        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(message)).await?;
-
-        Ok(())
-    }
-
-    async fn send_ultimate_tictactoe_leaderboard(ctx: &Context, leaderboard: &mut [qg_shared::db::UTTTLeaderboardEntry], interaction: &mut CommandInteraction, _more_available: bool) -> Result<()> {
-        let mut message = CreateInteractionResponseMessage::new();
-
-        message = message.content("`Ultimate Tic Tac Toe Leaderboard`");
-
-        leaderboard.sort_by(|a, b| {
-            b.rating.partial_cmp(&a.rating).unwrap_or({
-                // if the rating fails to compare, compare by wins
-                b.wins.cmp(&a.wins)
-            })
-        });
-
-        for (i, entry) in leaderboard.iter().enumerate() {
-            let mut embed = CreateEmbed::default();
-
-            embed = embed.fields(vec![
-                ("Wins", format!("{}", entry.wins), true),
-                // ("Losses", format!("{}", entry.losses), true),
-                ("Rating", format!("{}", entry.rating), true),
-            ]);
-
-            let fancyuser = Self::get_author(&entry.user, ctx, interaction).await;
-
-            let mut author = CreateEmbedAuthor::new(format!("#{}: {} ({})", i + 1, fancyuser.name, fancyuser.discord_id));
-
-            if let Some(img) = fancyuser.avatar {
-                author = author.icon_url(img);
+    fn rating_filters(options: &[CommandDataOption], guild_id: Option<GuildId>) -> qg_shared::anyhow::Result<qg_shared::db::RatingFilters> {
+        let mut filters = qg_shared::db::RatingFilters::default();
+        for option in options {
+            match option.name.as_str() {
+                "period" => {
+                    filters.period(parse_period(option)?);
+                }
+                "scope" => match parse_scope(option, guild_id)? {
+                    Some(guild_id) => {
+                        filters.server(guild_id);
+                    }
+                    None => {
+                        filters.global();
+                    }
+                },
+                v => {
+                    return Err(qg_shared::anyhow::anyhow!("Unhandled option `{}`", v));
+                }
             }
-
-            embed = embed.author(author);
-
-            message = message.add_embed(embed);
         }
-
-        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(message)).await?;
-
-        Ok(())
+        Ok(filters)
     }
 
-    async fn send_slidingpuzzle_leaderboard(
+    async fn send_leaderboard(
         ctx: &Context,
-        leaderboard: &[qg_shared::db::SlidingPuzzleWithUser],
+        leaderboard: &Leaderboard,
         interaction: &mut CommandInteraction,
-        s: &str,
-        _more_available: bool,
+        tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>,
     ) -> qg_shared::anyhow::Result<()> {
-        let mut message = CreateInteractionResponseMessage::new();
+        let (content, embed, components) = Self::render_page(leaderboard, tx).await?;
 
-        message = message.content(format!("`Sliding Puzzle {} Leaderboard`", s));
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(content).embed(embed).components(components)),
+            )
+            .await?;
 
-        // {
-        //     let mut embed = CreateEmbed::default();
-
-        //     embed = embed.title(format!("{} Leaderboard", s)).fields(leaderboard.iter().enumerate().map(|(i, holder)| {
-        //         (
-        //             format!("{}. {} ({})", i + 1, holder.user.name, holder.user.discord_id),
-        //             format!("Score: {}\nTime: {}", holder.puzzle.score, holder.puzzle.time),
-        //             false,
-        //         )
-        //     }));
+        Ok(())
+    }
 
-        //     message = message.add_embed(embed);
-        // }
+    /// renders a single page as one compact embed, discord only allows 10 embeds per message
+    async fn render_page(leaderboard: &Leaderboard, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> qg_shared::anyhow::Result<(String, CreateEmbed, Vec<CreateActionRow>)> {
+        let (entries, more_available): (Vec<(qg_shared::db::User, String)>, bool) = match leaderboard {
+            Leaderboard::SlidingPuzzle { filters, .. } => {
+                let (standings, more) = qg_shared::db::SlidingPuzzle::get_standings(*filters, tx).await?;
+                (
+                    standings
+                        .into_iter()
+                        .map(|entry| {
+                            let mut stats = format!("Score: {} · Time: {}", entry.puzzle.score, qg_shared::format_duration_ms(entry.puzzle.time as u64));
+                            if let Some(efficiency) = entry.puzzle.efficiency {
+                                stats.push_str(&format!(" · Efficiency: {:.0}%", efficiency * 100.0));
+                            }
+                            if entry.puzzle.hinted {
+                                stats.push_str(" · Hinted");
+                            }
+                            (entry.user, stats)
+                        })
+                        .collect(),
+                    more,
+                )
+            }
+            Leaderboard::TicTacToe { variant, filters } => {
+                let (standings, more) = qg_shared::db::TicTacToe::get_standings(*variant, *filters, tx).await?;
+                (
+                    standings.into_iter().map(|entry| (entry.user, format!("Wins: {} · Rating: {}", entry.wins, entry.rating))).collect(),
+                    more,
+                )
+            }
+            Leaderboard::UltimateTicTacToe(filters) => {
+                let (standings, more) = qg_shared::db::UltimateTicTacToe::get_standings(*filters, tx).await?;
+                (
+                    standings.into_iter().map(|entry| (entry.user, format!("Wins: {} · Rating: {}", entry.wins, entry.rating))).collect(),
+                    more,
+                )
+            }
+            Leaderboard::ConnectFour(filters) => {
+                let (standings, more) = qg_shared::db::ConnectFour::get_standings(*filters, tx).await?;
+                (
+                    standings.into_iter().map(|entry| (entry.user, format!("Wins: {} · Rating: {}", entry.wins, entry.rating))).collect(),
+                    more,
+                )
+            }
+            Leaderboard::Minesweeper(filters) => {
+                let (standings, more) = qg_shared::db::Minesweeper::get_standings(*filters, tx).await?;
+                (
+                    standings
+                        .into_iter()
+                        .map(|entry| (entry.user, format!("Time: {}", qg_shared::format_duration_ms(entry.game.time as u64))))
+                        .collect(),
+                    more,
+                )
+            }
+            Leaderboard::SlidingPuzzleRace(filters) => {
+                let (standings, more) = qg_shared::db::SlidingPuzzleRace::get_standings(*filters, tx).await?;
+                (
+                    standings
+                        .into_iter()
+                        .map(|entry| (entry.user, format!("Wins: {} · Losses: {} · Rating: {}", entry.wins, entry.losses, entry.rating)))
+                        .collect(),
+                    more,
+                )
+            }
+        };
 
-        for (i, entry) in leaderboard.iter().enumerate() {
-            let mut embed = CreateEmbed::default();
+        let mut description = String::new();
+        let mut thumbnail = None;
 
-            embed = embed.fields(vec![("Score", format!("{}", entry.puzzle.score), true), ("Time", format!("{}", entry.puzzle.time), true)]);
+        for (i, (user, stats)) in entries.iter().enumerate() {
+            let fancyuser = Self::get_author(user);
+            let rank = leaderboard.paginated_offset() + i as i64 + 1;
 
-            let fancyuser = Self::get_author(&entry.user, ctx, interaction).await;
+            if rank == 1 {
+                thumbnail = fancyuser.avatar;
+            }
 
-            let mut author = CreateEmbedAuthor::new(format!("#{}: {} ({})", i + 1, fancyuser.name, fancyuser.discord_id));
+            description.push_str(&format!("**#{}** {} ({})\n{}\n", rank, fancyuser.name, fancyuser.discord_id, stats));
+        }
 
-            if let Some(img) = fancyuser.avatar {
-                author = author.icon_url(img);
-            }
+        if description.is_empty() {
+            description.push_str("Nobody here yet!");
+        }
 
-            embed = embed.author(author);
+        let mut embed = CreateEmbed::default()
+            .description(description)
+            .footer(CreateEmbedFooter::new(format!("Page {}", leaderboard.page() + 1)));
 
-            message = message.add_embed(embed);
+        if let Some(thumbnail) = thumbnail {
+            embed = embed.thumbnail(thumbnail);
         }
 
-        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(message)).await?;
+        let components = vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(Action::Prev.to_custom_id())
+                .label("Prev")
+                .style(ButtonStyle::Secondary)
+                .disabled(leaderboard.page() == 0),
+            CreateButton::new(Action::Next.to_custom_id()).label("Next").style(ButtonStyle::Secondary).disabled(!more_available),
+        ])];
 
-        Ok(())
+        // the leaderboard is kept in the message so the buttons know what page they're on
+        let content = format!("```{}\n{}\n```", qg_shared::serialize(leaderboard)?, leaderboard.title());
+
+        Ok((content, embed, components))
     }
 
-    async fn get_author(user: &qg_shared::db::User, ctx: &Context, interaction: &mut CommandInteraction) -> FancyUser {
-        let mut author = FancyUser {
-            name: user.name.clone(),
+    /// names and avatars come from what we've stored (or seen recently), so rendering a page doesn't hit discord once per entry
+    fn get_author(user: &qg_shared::db::User) -> FancyUser {
+        let identity = user.identity();
+        FancyUser {
+            name: identity.name,
             discord_id: user.discord_id as u64,
-            avatar: None,
-        };
+            avatar: identity.avatar,
+        }
+    }
+}
 
-        // first, attempt to get the member from the discord api
-        if let Some(guild) = interaction.guild_id.as_ref() {
-            // then attempt to get the member from the discord api
-            if let Ok(member) = guild.member(&ctx.http, UserId::from(author.discord_id)).await {
-                // if we got the member, we can get the avatar url, nickname, etc
-                if let Some(avatar) = member.avatar_url() {
-                    author.avatar = Some(avatar);
-                }
-                if let Some(nick) = member.nick {
-                    author.name = nick;
-                }
+/// what the message is showing, carried in the message content so the page buttons can re-run the same query
+#[derive(Debug, Serialize, Deserialize)]
+enum Leaderboard {
+    SlidingPuzzle {
+        size: String,
+        filters: qg_shared::db::SlidingPuzzleFilters,
+    },
+    TicTacToe {
+        variant: qg_shared::db::TicTacToeVariant,
+        filters: qg_shared::db::RatingFilters,
+    },
+    UltimateTicTacToe(qg_shared::db::RatingFilters),
+    SlidingPuzzleRace(qg_shared::db::RatingFilters),
+    ConnectFour(qg_shared::db::RatingFilters),
+    Minesweeper(qg_shared::db::MinesweeperFilters),
+}
+
+impl Leaderboard {
+    fn title(&self) -> String {
+        let (game, period, global) = match self {
+            Self::SlidingPuzzle { size, filters } => (format!("Sliding Puzzle {}", size), filters.get_period(), filters.is_global()),
+            Self::TicTacToe { variant, filters } if variant.is_classic() => (String::from("Tic Tac Toe"), filters.get_period(), filters.is_global()),
+            Self::TicTacToe { variant, filters } => (format!("Tic Tac Toe {}", variant.name()), filters.get_period(), filters.is_global()),
+            Self::UltimateTicTacToe(filters) => (String::from("Ultimate Tic Tac Toe"), filters.get_period(), filters.is_global()),
+            Self::SlidingPuzzleRace(filters) => (String::from("Sliding Puzzle Race"), filters.get_period(), filters.is_global()),
+            Self::ConnectFour(filters) => (String::from("Connect Four"), filters.get_period(), filters.is_global()),
+            Self::Minesweeper(filters) => (format!("Minesweeper {}", filters.difficulty_name()), filters.get_period(), filters.is_global()),
+        };
+        format!("{} {}{} Leaderboard", game, period.name(), if global { "" } else { " Server" })
+    }
+    fn page(&self) -> i64 {
+        match self {
+            Self::SlidingPuzzle { filters, .. } => filters.get_offset(),
+            Self::Minesweeper(filters) => filters.get_offset(),
+            Self::TicTacToe { filters, .. } | Self::UltimateTicTacToe(filters) | Self::SlidingPuzzleRace(filters) | Self::ConnectFour(filters) => filters.get_offset(),
+        }
+    }
+    fn paginated_offset(&self) -> i64 {
+        match self {
+            Self::SlidingPuzzle { filters, .. } => filters.paginated_offset(),
+            Self::Minesweeper(filters) => filters.paginated_offset(),
+            Self::TicTacToe { filters, .. } | Self::UltimateTicTacToe(filters) | Self::SlidingPuzzleRace(filters) | Self::ConnectFour(filters) => filters.paginated_offset(),
+        }
+    }
+    fn next_page(&mut self) {
+        match self {
+            Self::SlidingPuzzle { filters, .. } => {
+                filters.increment_offset();
+            }
+            Self::Minesweeper(filters) => {
+                filters.increment_offset();
+            }
+            Self::TicTacToe { filters, .. } | Self::UltimateTicTacToe(filters) | Self::SlidingPuzzleRace(filters) | Self::ConnectFour(filters) => {
+                filters.increment_offset();
             }
         }
-        // fallback to the regular avatar url
-        if author.avatar.is_none() {
-            // then attempt to get the user from the discord api
-            if let Ok(user) = ctx.http.get_user(UserId::from(author.discord_id)).await {
-                // if we got the user, we can get the avatar url
-                if let Some(avatar) = user.avatar_url() {
-                    author.avatar = Some(avatar);
-                }
+    }
+    fn previous_page(&mut self) {
+        match self {
+            Self::SlidingPuzzle { filters, .. } => {
+                filters.decrement_offset();
+            }
+            Self::Minesweeper(filters) => {
+                filters.decrement_offset();
+            }
+            Self::TicTacToe { filters, .. } | Self::UltimateTicTacToe(filters) | Self::SlidingPuzzleRace(filters) | Self::ConnectFour(filters) => {
+                filters.decrement_offset();
             }
         }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    Prev,
+    Next,
+}
 
-        author
+impl Action {
+    fn to_custom_id(self) -> String {
+        format!("leaderboard:{:?}", self)
+    }
+    fn from_custom_id(custom_id: &str) -> qg_shared::anyhow::Result<Self> {
+        match custom_id {
+            "leaderboard:Prev" => Ok(Self::Prev),
+            "leaderboard:Next" => Ok(Self::Next),
+            _ => Err(qg_shared::anyhow::anyhow!("Invalid custom_id")),
+        }
     }
 }
 
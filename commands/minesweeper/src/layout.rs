@@ -0,0 +1,107 @@
+//! where the mines are. the message is public, so they're never stored in it. instead they're worked out each time from
+//! the game's nonce and the server's secret, which nobody reading the message has
+//! if the secret changes, say it was never set and the bot restarted, the same nonce gives different mines. so each game
+//! also keeps a fingerprint of the secret it was started with, and games from another secret are turned away
+
+use qg_shared::rand::{rngs::StdRng, seq::SliceRandom as _, SeedableRng as _};
+use sha2::{Digest as _, Sha256};
+
+use super::Difficulty;
+
+pub struct Layout {
+    mines: Vec<Vec<bool>>,
+}
+
+impl Layout {
+    /// the layout is drawn after the first reveal so it can be kept clear of it, the first click always opens up some of the board
+    pub fn new(difficulty: Difficulty, nonce: u64, first: (usize, usize)) -> Self {
+        let (rows, columns) = difficulty.dimensions();
+
+        let mut hasher = Sha256::new();
+        hasher.update(qg_shared::state_secret());
+        hasher.update(nonce.to_le_bytes());
+        hasher.update((first.0 as u64).to_le_bytes());
+        hasher.update((first.1 as u64).to_le_bytes());
+        let digest = hasher.finalize();
+        let mut seed = [0; 32];
+        seed.copy_from_slice(&digest);
+        let mut rng = StdRng::from_seed(seed);
+
+        let mut spaces = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                if row.abs_diff(first.0) > 1 || column.abs_diff(first.1) > 1 {
+                    spaces.push((row, column));
+                }
+            }
+        }
+
+        let mut mines = vec![vec![false; columns]; rows];
+        for (row, column) in spaces.choose_multiple(&mut rng, difficulty.mines()) {
+            mines[*row][*column] = true;
+        }
+        Self { mines }
+    }
+
+    /// a layout with the mines put in by hand
+    #[cfg(test)]
+    pub fn from_mines(mines: Vec<Vec<bool>>) -> Self {
+        Self { mines }
+    }
+
+    pub fn is_mine(&self, row: usize, column: usize) -> bool {
+        self.mines[row][column]
+    }
+
+    /// how many of the surrounding spaces have mines
+    pub fn count(&self, row: usize, column: usize) -> u8 {
+        neighbours(row, column, self.mines.len(), self.mines[0].len()).filter(|(row, column)| self.mines[*row][*column]).count() as u8
+    }
+}
+
+/// a short hash of the server's secret. it says nothing about the secret, only whether it's the same one
+pub fn fingerprint() -> u32 {
+    let mut hasher = Sha256::new();
+    hasher.update(qg_shared::state_secret());
+    hasher.update(b"minesweeper fingerprint");
+    let digest = hasher.finalize();
+    let mut fingerprint = [0; 4];
+    fingerprint.copy_from_slice(&digest[..4]);
+    u32::from_le_bytes(fingerprint)
+}
+
+/// the up to eight spaces around a space that are on the board
+pub fn neighbours(row: usize, column: usize, rows: usize, columns: usize) -> impl Iterator<Item = (usize, usize)> {
+    (row.saturating_sub(1)..=(row + 1).min(rows - 1))
+        .flat_map(move |r| (column.saturating_sub(1)..=(column + 1).min(columns - 1)).map(move |c| (r, c)))
+        .filter(move |(r, c)| (*r, *c) != (row, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_click_is_never_next_to_a_mine() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let (rows, columns) = difficulty.dimensions();
+            for nonce in 0..50 {
+                // corners, edges and the middle, the space kept clear is smaller at the edges
+                for first in [(0, 0), (0, columns - 1), (rows - 1, 0), (rows - 1, columns - 1), (0, columns / 2), (rows / 2, columns / 2)] {
+                    let layout = Layout::new(difficulty, nonce, first);
+                    assert!(!layout.is_mine(first.0, first.1));
+                    assert_eq!(layout.count(first.0, first.1), 0, "{:?} nonce {} first {:?}", difficulty, nonce, first);
+                    let mines = layout.mines.iter().flatten().filter(|mine| **mine).count();
+                    assert_eq!(mines, difficulty.mines());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_game_same_mines() {
+        let a = Layout::new(Difficulty::Medium, 7, (3, 3));
+        let b = Layout::new(Difficulty::Medium, 7, (3, 3));
+        assert_eq!(a.mines, b.mines);
+    }
+}
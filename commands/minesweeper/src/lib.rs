@@ -0,0 +1,576 @@
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    colored::Colorize,
+    serenity::all::*,
+    OptTrans,
+};
+
+use serde::{Deserialize, Serialize};
+
+mod layout;
+
+use layout::Layout;
+
+/// the part of the board shown as buttons. discord allows five rows, one of them is the controls
+const VIEW_ROWS: usize = 4;
+const VIEW_COLUMNS: usize = 5;
+
+pub fn command() -> Minesweeper {
+    Minesweeper
+}
+
+pub struct Minesweeper;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for Minesweeper {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("minesweeper"),
+            description: String::from("Play a game of Minesweeper"),
+            options: vec![qg_shared::CommandOption {
+                name: String::from("difficulty"),
+                description: String::from("How big the board is and how many mines are hidden in it (defaults to easy)"),
+                option_type: qg_shared::CommandOptionType::String,
+                choices: qg_shared::UnorderedVec::from(
+                    [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+                        .iter()
+                        .map(|difficulty| qg_shared::CommandOptionChoice {
+                            name: difficulty.name().to_string(),
+                            value: difficulty.name().to_string(),
+                        })
+                        .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                ),
+                required: false,
+            }]
+            .into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, _: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let mut difficulty = Difficulty::Easy;
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "difficulty") {
+            match option.value {
+                CommandDataOptionValue::String(ref value) => {
+                    difficulty = Difficulty::from_name(value).ok_or(anyhow!("Unhandled value `{}`", value))?;
+                }
+                _ => {
+                    return Err(anyhow!("Invalid difficulty"));
+                }
+            }
+        }
+
+        let (rows, columns) = difficulty.dimensions();
+        let game = Game {
+            player: interaction.user.id,
+            difficulty,
+            nonce: qg_shared::rand::random(),
+            key: layout::fingerprint(),
+            first: None,
+            spaces: vec![vec![Space::Hidden; columns]; rows],
+            mode: Mode::Reveal,
+            view: (0, 0),
+            start_time: None,
+            state: State::Playing,
+        };
+
+        let (content, components) = game.message()?;
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components(components)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let action = match Action::from_custom_id(&interaction.data.custom_id) {
+            Some(action) => action,
+            None => return Err(anyhow!("Invalid action id")),
+        };
+
+        // get first line of message content, strip the ``` prefix and deserialize
+        let mut game = {
+            let mut lines = interaction.message.content.lines();
+            let game = lines.next().ok_or(anyhow!("No game data found"))?;
+            let game = game.strip_prefix("```").ok_or(anyhow!("No game data found"))?;
+            qg_shared::deserialize::<Game>(game)?
+        };
+
+        game.do_action(ctx, interaction, action, db).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Hard => "Hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Easy" => Some(Self::Easy),
+            "Medium" => Some(Self::Medium),
+            "Hard" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name_with_ansi(&self) -> String {
+        match self {
+            Self::Easy => "Easy".green().to_string(),
+            Self::Medium => "Medium".blue().to_string(),
+            Self::Hard => "Hard".red().to_string(),
+        }
+    }
+
+    /// (rows, columns)
+    fn dimensions(&self) -> (usize, usize) {
+        match self {
+            Self::Easy => (9, 9),
+            Self::Medium => (12, 12),
+            Self::Hard => (16, 16),
+        }
+    }
+
+    fn mines(&self) -> usize {
+        match self {
+            Self::Easy => 10,
+            Self::Medium => 24,
+            Self::Hard => 40,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Up => "Up",
+            Self::Down => "Down",
+            Self::Left => "Left",
+            Self::Right => "Right",
+        }
+    }
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Up" => Some(Self::Up),
+            "Down" => Some(Self::Down),
+            "Left" => Some(Self::Left),
+            "Right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+    fn emoji(&self) -> &'static str {
+        match self {
+            Self::Up => "⬆️",
+            Self::Down => "⬇️",
+            Self::Left => "⬅️",
+            Self::Right => "➡️",
+        }
+    }
+}
+
+pub enum Action {
+    Click(usize, usize), // (row, column) on the whole board, what it does depends on the mode
+    Pan(Direction),
+    ToggleMode,
+}
+
+impl Action {
+    pub fn from_custom_id(custom_id: &str) -> Option<Self> {
+        let mut split = custom_id.split(':').skip(1);
+        let action = match split.next()? {
+            "Click" => Self::Click(split.next()?.parse().ok()?, split.next()?.parse().ok()?),
+            "Pan" => Self::Pan(Direction::from_name(split.next()?)?),
+            "ToggleMode" => Self::ToggleMode,
+            _ => return None,
+        };
+        if split.next().is_some() {
+            return None;
+        }
+        Some(action)
+    }
+    pub fn to_custom_id(&self) -> String {
+        format!("minesweeper:{}", self.name())
+    }
+    pub fn name(&self) -> String {
+        match self {
+            Self::Click(row, column) => format!("Click:{}:{}", row, column),
+            Self::Pan(direction) => format!("Pan:{}", direction.name()),
+            Self::ToggleMode => String::from("ToggleMode"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Reveal,
+    Flag,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Space {
+    Hidden,
+    Flagged,
+    Revealed(u8), // how many mines are next to it
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Playing,
+    Won { time: u64 },                  // milliseconds
+    Lost { row: usize, column: usize }, // the mine that went off
+}
+
+/// everything here is public, the mines are never in the message. see `layout`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Game {
+    player: UserId,
+    difficulty: Difficulty,
+    nonce: u64, // the mines are drawn from this and the server's secret
+    #[serde(default)]
+    key: u32, // `layout::fingerprint` when the game started, the mines are only the same under the same secret
+    first: Option<(usize, usize)>, // the first reveal, the mines are laid out around it
+    spaces: Vec<Vec<Space>>,
+    mode: Mode,
+    view: (usize, usize),    // the top left of the part of the board that's shown
+    start_time: Option<u64>, // milliseconds since the epoch, set by the first reveal
+    state: State,
+}
+
+impl Game {
+    async fn do_action(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut OptTrans<'_>) -> Result<()> {
+        if interaction.user.id != self.player {
+            return Err(anyhow!("You are not the player"));
+        }
+        // the board can still be looked around once it's over
+        if self.state != State::Playing && !matches!(action, Action::Pan(_)) {
+            return Err(anyhow!("The game is over"));
+        }
+        if !matches!(action, Action::Pan(_)) && self.key != layout::fingerprint() {
+            return Err(anyhow!("This game's mines were lost when the bot restarted, start a new one"));
+        }
+        // the message is only the board as it was sent, an older copy of it can't be played on
+        if let (false, Some(db)) = (matches!(action, Action::Pan(_)), db.as_mut()) {
+            if let Some(saved) = qg_shared::db::MinesweeperGame::get(self.nonce as i64, db).await? {
+                if saved.won.is_some() {
+                    return Err(anyhow!("The game is over"));
+                }
+                if saved.revealed as usize != self.revealed() {
+                    return Err(anyhow!("This board is out of date, play on the newest one"));
+                }
+            }
+        }
+        let now = qg_shared::snowflake_millis(interaction.id.get());
+        match action {
+            Action::ToggleMode => {
+                self.mode = match self.mode {
+                    Mode::Reveal => Mode::Flag,
+                    Mode::Flag => Mode::Reveal,
+                };
+            }
+            Action::Pan(direction) => self.pan(direction),
+            Action::Click(row, column) => {
+                let (rows, columns) = self.difficulty.dimensions();
+                if row >= rows || column >= columns {
+                    return Err(anyhow!("Invalid move, out of bounds"));
+                }
+                match (self.mode, self.spaces[row][column]) {
+                    (_, Space::Revealed(_)) => {
+                        return Err(anyhow!("That space has already been revealed"));
+                    }
+                    (Mode::Flag, Space::Hidden) => {
+                        self.spaces[row][column] = Space::Flagged;
+                    }
+                    (Mode::Flag, Space::Flagged) => {
+                        self.spaces[row][column] = Space::Hidden;
+                    }
+                    (Mode::Reveal, Space::Flagged) => {
+                        return Err(anyhow!("Unflag the space before revealing it"));
+                    }
+                    (Mode::Reveal, Space::Hidden) => {
+                        let first = *self.first.get_or_insert((row, column));
+                        let start_time = *self.start_time.get_or_insert(now);
+                        let layout = Layout::new(self.difficulty, self.nonce, first);
+                        if layout.is_mine(row, column) {
+                            self.state = State::Lost { row, column };
+                            self.save(db).await?;
+                            return self.render(ctx, interaction).await;
+                        }
+                        self.reveal(&layout, row, column);
+                        if self.cleared() {
+                            let time = now.saturating_sub(start_time);
+                            self.state = State::Won { time };
+                            self.save(db).await?;
+                            self.render(ctx, interaction).await?;
+                            return self.record(ctx, interaction, time, db).await;
+                        }
+                        self.save(db).await?;
+                    }
+                }
+            }
+        }
+        self.render(ctx, interaction).await
+    }
+
+    /// reveals the space, opening up everything around it when none of its neighbours are mines
+    fn reveal(&mut self, layout: &Layout, row: usize, column: usize) {
+        let (rows, columns) = self.difficulty.dimensions();
+        let mut queue = vec![(row, column)];
+        while let Some((row, column)) = queue.pop() {
+            if self.spaces[row][column] != Space::Hidden {
+                continue;
+            }
+            let count = layout.count(row, column);
+            self.spaces[row][column] = Space::Revealed(count);
+            if count == 0 {
+                queue.extend(layout::neighbours(row, column, rows, columns));
+            }
+        }
+    }
+
+    fn revealed(&self) -> usize {
+        self.spaces.iter().flatten().filter(|space| matches!(space, Space::Revealed(_))).count()
+    }
+
+    /// every space without a mine has been revealed
+    fn cleared(&self) -> bool {
+        let (rows, columns) = self.difficulty.dimensions();
+        self.revealed() == rows * columns - self.difficulty.mines()
+    }
+
+    /// keeps how far the game has got, see `MinesweeperGame`
+    async fn save(&self, db: &mut OptTrans<'_>) -> Result<()> {
+        if let Some(db) = db {
+            let won = match self.state {
+                State::Playing => None,
+                State::Won { .. } => Some(true),
+                State::Lost { .. } => Some(false),
+            };
+            qg_shared::db::MinesweeperGame::save(self.nonce as i64, self.revealed() as i32, won, db).await?;
+        }
+        Ok(())
+    }
+
+    /// moves the view a whole screen over, stopping at the edge of the board
+    fn pan(&mut self, direction: Direction) {
+        let (rows, columns) = self.difficulty.dimensions();
+        let (row, column) = self.view;
+        self.view = match direction {
+            Direction::Up => (row.saturating_sub(VIEW_ROWS), column),
+            Direction::Down => ((row + VIEW_ROWS).min(rows - VIEW_ROWS), column),
+            Direction::Left => (row, column.saturating_sub(VIEW_COLUMNS)),
+            Direction::Right => (row, (column + VIEW_COLUMNS).min(columns - VIEW_COLUMNS)),
+        };
+    }
+
+    fn can_pan(&self, direction: Direction) -> bool {
+        let (rows, columns) = self.difficulty.dimensions();
+        let (row, column) = self.view;
+        match direction {
+            Direction::Up => row > 0,
+            Direction::Down => row + VIEW_ROWS < rows,
+            Direction::Left => column > 0,
+            Direction::Right => column + VIEW_COLUMNS < columns,
+        }
+    }
+
+    async fn record(&self, ctx: &Context, interaction: &ComponentInteraction, time: u64, db: &mut OptTrans<'_>) -> Result<()> {
+        if let Some(db) = db {
+            let user = qg_shared::db::User::get_or_create(ctx, &self.player, db).await?;
+            qg_shared::db::Minesweeper::create(user.id as i32, self.difficulty as i32, time as i32, interaction.guild_id.map(|g| g.get() as i64), db).await?;
+            let result = qg_shared::db::GameResult::Minesweeper {
+                difficulty: self.difficulty as i32,
+                time: time as i32,
+            };
+            let unlocked = qg_shared::db::Achievement::evaluate(&user, &result, db).await?;
+            qg_shared::db::Achievement::notify_later(self.player, unlocked);
+        }
+        Ok(())
+    }
+
+    async fn render(&self, ctx: &Context, interaction: &mut ComponentInteraction) -> Result<()> {
+        let (content, components) = self.message()?;
+        interaction.defer(&ctx.http).await?;
+        interaction.edit_response(&ctx.http, EditInteractionResponse::default().content(content).components(components)).await?;
+        Ok(())
+    }
+
+    fn message(&self) -> Result<(String, Vec<CreateActionRow>)> {
+        let (rows, columns) = self.difficulty.dimensions();
+        // once it's over there's nothing left to hide, so the mines are shown
+        let layout = match (self.state, self.first) {
+            (State::Playing, _) | (_, None) => None,
+            (_, Some(first)) => Some(Layout::new(self.difficulty, self.nonce, first)),
+        };
+        let flags = self.spaces.iter().flatten().filter(|space| **space == Space::Flagged).count();
+        let mut content = format!("```{}\nMinesweeper\n```", qg_shared::serialize(&self)?.replace('\n', ""));
+        match self.state {
+            State::Playing => content.push_str(&format!("{}'s game, the clock starts on the first reveal", self.player.mention())),
+            State::Won { .. } => content.push_str(&format!("{} cleared the board!", self.player.mention())),
+            State::Lost { .. } => content.push_str(&format!("{} hit a mine!", self.player.mention())),
+        }
+        content.push_str(&format!(
+            "```ansi\nDifficulty: {}\nMines: {}\nFlags: {}\nShowing: rows {}-{} of {}, columns {}-{} of {}\n",
+            self.difficulty.name_with_ansi(),
+            self.difficulty.mines().to_string().red(),
+            flags.to_string().yellow(),
+            self.view.0 + 1,
+            self.view.0 + VIEW_ROWS,
+            rows,
+            self.view.1 + 1,
+            self.view.1 + VIEW_COLUMNS,
+            columns,
+        ));
+        if let State::Won { time } = self.state {
+            content.push_str(&format!("Time: {}\n", qg_shared::format_duration_ms(time).blue()));
+        }
+        content.push_str("```");
+
+        let over = self.state != State::Playing;
+        let mut components = vec![];
+        for row in self.view.0..self.view.0 + VIEW_ROWS {
+            let mut buttons = vec![];
+            for column in self.view.1..self.view.1 + VIEW_COLUMNS {
+                let mut button = CreateButton::new(Action::Click(row, column).to_custom_id());
+                button = match (self.spaces[row][column], &layout) {
+                    (_, Some(layout)) if layout.is_mine(row, column) => {
+                        let exploded = self.state == State::Lost { row, column };
+                        button.label("💣").style(if exploded { ButtonStyle::Danger } else { ButtonStyle::Secondary })
+                    }
+                    (Space::Flagged, _) => button.label("🚩").style(ButtonStyle::Danger),
+                    (Space::Hidden, _) => button.label("?").style(ButtonStyle::Primary),
+                    (Space::Revealed(0), _) => button.label("·").style(ButtonStyle::Secondary).disabled(true),
+                    (Space::Revealed(count), _) => button.label(count.to_string()).style(ButtonStyle::Secondary).disabled(true),
+                };
+                buttons.push(if over { button.disabled(true) } else { button });
+            }
+            components.push(CreateActionRow::Buttons(buttons));
+        }
+        let mut controls = vec![];
+        for direction in [Direction::Left, Direction::Up, Direction::Down, Direction::Right] {
+            controls.push(
+                CreateButton::new(Action::Pan(direction).to_custom_id())
+                    .label(direction.emoji())
+                    .style(ButtonStyle::Secondary)
+                    .disabled(!self.can_pan(direction)),
+            );
+        }
+        if !over {
+            controls.push(
+                CreateButton::new(Action::ToggleMode.to_custom_id())
+                    .label(match self.mode {
+                        Mode::Reveal => "⛏️ Reveal",
+                        Mode::Flag => "🚩 Flag",
+                    })
+                    .style(ButtonStyle::Success),
+            );
+        }
+        components.push(CreateActionRow::Buttons(controls));
+        Ok((content, components))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> Game {
+        let (rows, columns) = Difficulty::Easy.dimensions();
+        Game {
+            player: UserId::new(1),
+            difficulty: Difficulty::Easy,
+            nonce: 0,
+            key: 0,
+            first: None,
+            spaces: vec![vec![Space::Hidden; columns]; rows],
+            mode: Mode::Reveal,
+            view: (0, 0),
+            start_time: None,
+            state: State::Playing,
+        }
+    }
+
+    /// a wall of mines down column 4, and one more in the bottom right
+    fn walled() -> Layout {
+        let mut mines = vec![vec![false; 9]; 9];
+        for row in mines.iter_mut() {
+            row[4] = true;
+        }
+        mines[8][8] = true;
+        Layout::from_mines(mines)
+    }
+
+    #[test]
+    fn reveal_floods_up_to_the_numbers() {
+        let mut game = game();
+        game.reveal(&walled(), 0, 0);
+        for row in 0..9 {
+            for column in 0..9 {
+                let expected = match column {
+                    0..=2 => Space::Revealed(0),
+                    3 if row == 0 || row == 8 => Space::Revealed(2),
+                    3 => Space::Revealed(3),
+                    _ => Space::Hidden,
+                };
+                assert_eq!(game.spaces[row][column], expected, "row {} column {}", row, column);
+            }
+        }
+        assert_eq!(game.revealed(), 36);
+        assert!(!game.cleared());
+    }
+
+    #[test]
+    fn reveal_stops_at_a_number() {
+        let mut game = game();
+        game.reveal(&walled(), 4, 5);
+        assert_eq!(game.spaces[4][5], Space::Revealed(3));
+        assert_eq!(game.revealed(), 1);
+    }
+
+    #[test]
+    fn reveal_leaves_flags_alone() {
+        let mut game = game();
+        game.spaces[2][1] = Space::Flagged;
+        game.reveal(&walled(), 0, 0);
+        assert_eq!(game.spaces[2][1], Space::Flagged);
+        // the flood goes around it
+        assert_eq!(game.spaces[4][0], Space::Revealed(0));
+        assert_eq!(game.revealed(), 35);
+    }
+
+    #[test]
+    fn clearing_every_safe_space_wins() {
+        let mut game = game();
+        let layout = walled();
+        game.reveal(&layout, 0, 0);
+        for row in 0..9 {
+            for column in 5..9 {
+                if !layout.is_mine(row, column) {
+                    game.reveal(&layout, row, column);
+                }
+            }
+        }
+        assert!(game.cleared());
+    }
+}
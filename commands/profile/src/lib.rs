@@ -0,0 +1,92 @@
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    serenity::all::*,
+};
+
+pub fn command() -> ProfileCommand {
+    ProfileCommand
+}
+
+pub struct ProfileCommand;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for ProfileCommand {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("profile"),
+            description: String::from("Check your stats and achievements!"),
+            options: vec![qg_shared::CommandOption {
+                name: String::from("user"),
+                description: String::from("The user to check (defaults to you)"),
+                option_type: qg_shared::CommandOptionType::User,
+                choices: qg_shared::UnorderedVec::from(vec![]),
+                required: false,
+            }]
+            .into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let tx = match db {
+            Some(db) => db,
+            None => return Err(anyhow!("No database connection")),
+        };
+
+        let target = match interaction.data.options.first().map(|option| &option.value) {
+            Some(CommandDataOptionValue::User(user)) => *user,
+            _ => interaction.user.id,
+        };
+
+        let user = match qg_shared::db::User::get_by_discord_id(&target, tx).await? {
+            Some(user) => user,
+            None => return Err(anyhow!("{} hasn't finished any games yet", target.mention())),
+        };
+
+        let (tictactoe_wins, tictactoe_losses) = qg_shared::db::TicTacToe::get_record(&user, tx).await?;
+        let (ultimate_wins, ultimate_losses) = qg_shared::db::UltimateTicTacToe::get_record(&user, tx).await?;
+        let puzzles = qg_shared::db::SlidingPuzzle::count_for_user(&user, tx).await?;
+        let achievements = qg_shared::db::Achievement::get_unlocked(&user, tx).await?;
+        let total_achievements = qg_shared::db::Achievement::count(tx).await?;
+
+        let mut embed = CreateEmbed::default().title(format!("{}'s Profile", user.name)).fields(vec![
+            ("Tic Tac Toe", format!("{} wins / {} losses", tictactoe_wins, tictactoe_losses), true),
+            ("Ultimate Tic Tac Toe", format!("{} wins / {} losses", ultimate_wins, ultimate_losses), true),
+            ("Sliding Puzzle", format!("{} solved", puzzles), true),
+        ]);
+
+        embed = embed.field(
+            format!("Achievements ({}/{})", achievements.len(), total_achievements),
+            {
+                let mut value = String::new();
+                for achievement in achievements.iter() {
+                    let line = format!("🏆 **{}** - {} (<t:{}:d>)\n", achievement.name, achievement.description, achievement.unlocked_at.and_utc().timestamp());
+                    // embed field values are capped at 1024 characters
+                    if value.len() + line.len() > 1024 {
+                        break;
+                    }
+                    value.push_str(&line);
+                }
+                if value.is_empty() {
+                    value.push_str("None yet!");
+                }
+                value
+            },
+            false,
+        );
+
+        let avatar = if target == interaction.user.id {
+            interaction.user.avatar_url()
+        } else {
+            interaction.data.resolved.users.get(&target).and_then(|user| user.avatar_url())
+        };
+        if let Some(avatar) = avatar {
+            embed = embed.thumbnail(avatar);
+        }
+
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().embed(embed)))
+            .await?;
+
+        Ok(())
+    }
+}
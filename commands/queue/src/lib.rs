@@ -0,0 +1,217 @@
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    db::{GameKind, GuildSettings, QueueEntry},
+    serenity::all::*,
+    UnorderedVec,
+};
+
+pub fn command() -> QueueCommand {
+    QueueCommand
+}
+
+pub struct QueueCommand;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for QueueCommand {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("queue"),
+            description: String::from("Find a random opponent"),
+            options: vec![
+                qg_shared::CommandOption {
+                    name: String::from("join"),
+                    description: String::from("Wait for an opponent, you'll be matched with someone close to your rating"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
+                        choice_option("game", "The game to play", &[("Tic Tac Toe", "tictactoe"), ("Ultimate Tic Tac Toe", "ultimatetictactoe")], true),
+                        choice_option(
+                            "scope",
+                            "Only match with people in this server, or anyone and play in dms (defaults to server)",
+                            &[("server", "server"), ("global", "global")],
+                            false,
+                        ),
+                    ])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("leave"),
+                    description: String::from("Stop waiting for an opponent"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+            ]
+            .into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let tx = match db {
+            Some(db) => db,
+            None => return Err(anyhow!("No database connection")),
+        };
+
+        let option = interaction.data.options.first().ok_or(anyhow!("No options found"))?.clone();
+        let options = match option.value {
+            CommandDataOptionValue::SubCommand(options) => options,
+            _ => return Err(anyhow!("Expected SubCommand, got {:?}", option.value)),
+        };
+
+        let user = qg_shared::db::User::get_or_create(ctx, &interaction.user.id, tx).await?;
+
+        match option.name.as_str() {
+            "join" => {
+                let mut game = None;
+                // the server queue by default, dms can only use the global one
+                let mut guild_id = interaction.guild_id;
+
+                for option in options.iter() {
+                    match (option.name.as_str(), &option.value) {
+                        ("game", CommandDataOptionValue::String(value)) => {
+                            game = match GameKind::from_name(value) {
+                                Some(GameKind::SlidingPuzzle | GameKind::ConnectFour | GameKind::Minesweeper | GameKind::Wordle) | None => return Err(anyhow!("Unhandled value `{}`", value)),
+                                kind => kind,
+                            };
+                        }
+                        ("scope", CommandDataOptionValue::String(value)) => match value.as_str() {
+                            "server" => {
+                                guild_id = Some(interaction.guild_id.ok_or(anyhow!("Server queues are only available in servers"))?);
+                            }
+                            "global" => {
+                                guild_id = None;
+                            }
+                            _ => return Err(anyhow!("Unhandled value `{}`", value)),
+                        },
+                        (v, _) => {
+                            return Err(anyhow!("Unhandled option `{}`", v));
+                        }
+                    }
+                }
+
+                let game = game.ok_or(anyhow!("No game specified"))?;
+
+                match QueueEntry::join(&user, game, guild_id.map(|g| g.get() as i64), interaction.channel_id.get() as i64, tx).await? {
+                    Some(opponent) => {
+                        let opponent = qg_shared::db::User::get_by_id(opponent.user_id, tx).await?.ok_or(anyhow!("User not found"))?;
+                        let opponent_id = UserId::new(opponent.discord_id as u64);
+                        let players = (interaction.user.id, opponent_id);
+
+                        let link = match guild_id {
+                            Some(guild_id) => {
+                                // the server's queue channel if it has one, otherwise right here
+                                let channel_id = GuildSettings::get(guild_id.get() as i64, tx)
+                                    .await?
+                                    .queue_channel_id
+                                    .map(|c| ChannelId::new(c as u64))
+                                    .unwrap_or(interaction.channel_id);
+
+                                let message = match game {
+                                    GameKind::TicTacToe => qg_tictactoe::open_game(ctx, channel_id, players, None).await?,
+                                    GameKind::UltimateTicTacToe => qg_ulttictactoe::open_game(ctx, channel_id, players, None).await?,
+                                    GameKind::SlidingPuzzle | GameKind::ConnectFour | GameKind::Minesweeper | GameKind::Wordle => unreachable!(),
+                                };
+
+                                // the person who was waiting might not be looking at this channel anymore
+                                for player in [interaction.user.id, opponent_id] {
+                                    notify(ctx, player, format!("Found you a {} opponent! {}", game.display_name(), message.link())).await;
+                                }
+
+                                message.link()
+                            }
+                            None => {
+                                // the global queue can pair people without a server in common, so each of them gets the game in their dms
+                                let dm = match game {
+                                    GameKind::TicTacToe => qg_tictactoe::open_dm_game(ctx, players).await?,
+                                    GameKind::UltimateTicTacToe => qg_ulttictactoe::open_dm_game(ctx, players).await?,
+                                    GameKind::SlidingPuzzle | GameKind::ConnectFour | GameKind::Minesweeper | GameKind::Wordle => unreachable!(),
+                                };
+                                dm.link(interaction.user.id).ok_or(anyhow!("Player not found"))?
+                            }
+                        };
+
+                        interaction
+                            .create_response(
+                                &ctx.http,
+                                CreateInteractionResponse::Message(
+                                    CreateInteractionResponseMessage::new()
+                                        .content(format!("Matched with {}! {}", opponent_id.mention(), link))
+                                        .ephemeral(true),
+                                ),
+                            )
+                            .await?;
+                    }
+                    None => {
+                        let expires_at = qg_shared::current_time()? + QUEUE_TIMEOUT_SECONDS;
+                        interaction
+                            .create_response(
+                                &ctx.http,
+                                CreateInteractionResponse::Message(
+                                    CreateInteractionResponseMessage::new()
+                                        .content(format!(
+                                            "You're in the {} {} queue, you'll get a dm when an opponent is found. Your spot expires <t:{}:R>",
+                                            if guild_id.is_some() { "server" } else { "global" },
+                                            game.display_name(),
+                                            expires_at
+                                        ))
+                                        .ephemeral(true),
+                                ),
+                            )
+                            .await?;
+                    }
+                }
+            }
+            "leave" => {
+                let left = QueueEntry::leave(&user, tx).await?;
+                interaction
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content(if left > 0 { "You've left the queue" } else { "You weren't in the queue" })
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await?;
+            }
+            v => {
+                return Err(anyhow!("Unhandled option `{}`", v));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const QUEUE_TIMEOUT_SECONDS: u64 = qg_shared::db::QUEUE_TIMEOUT_MINUTES as u64 * 60;
+
+fn choice_option(name: &str, description: &str, choices: &[(&str, &str)], required: bool) -> qg_shared::CommandOption {
+    qg_shared::CommandOption {
+        name: name.to_string(),
+        description: description.to_string(),
+        option_type: qg_shared::CommandOptionType::String,
+        choices: UnorderedVec::from(
+            choices
+                .iter()
+                .map(|(name, value)| qg_shared::CommandOptionChoice {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+        ),
+        required,
+    }
+}
+
+/// failing to dm someone shouldn't undo the match, so errors are only logged
+async fn notify(ctx: &Context, player: UserId, content: String) {
+    match player.create_dm_channel(&ctx.http).await {
+        Ok(dm) => {
+            if let Err(e) = dm.send_message(&ctx.http, CreateMessage::default().content(content)).await {
+                qg_shared::log::trace!("Failed to send message to user: {}", e);
+            }
+        }
+        Err(e) => {
+            qg_shared::log::trace!("Failed to create dm channel: {}", e);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    db::{GuildSettings, OfficialPuzzle},
+    serenity::all::*,
+    UnorderedVec,
+};
+
+pub fn command() -> SettingsCommand {
+    SettingsCommand
+}
+
+pub struct SettingsCommand;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for SettingsCommand {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("settings"),
+            description: String::from("Configure the bot for this server"),
+            options: vec![
+                qg_shared::CommandOption {
+                    name: String::from("queue_channel"),
+                    description: String::from("Where games from /queue are posted"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![qg_shared::CommandOption {
+                        name: String::from("channel"),
+                        description: String::from("The channel to post in (leave empty to post wherever the match was found)"),
+                        option_type: qg_shared::CommandOptionType::Channel,
+                        choices: UnorderedVec::from(vec![]),
+                        required: false,
+                    }])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("game_threads"),
+                    description: String::from("Play accepted challenges in their own thread, the result is posted back to the channel"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![qg_shared::CommandOption {
+                        name: String::from("enabled"),
+                        description: String::from("Whether games get their own thread"),
+                        option_type: qg_shared::CommandOptionType::Boolean,
+                        choices: UnorderedVec::from(vec![]),
+                        required: true,
+                    }])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("official_puzzle"),
+                    description: String::from("Let sliding puzzles with this picture count for the leaderboards"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
+                        qg_shared::CommandOption {
+                            name: String::from("image"),
+                            description: String::from("The same file players will upload"),
+                            option_type: qg_shared::CommandOptionType::Attachment,
+                            choices: UnorderedVec::from(vec![]),
+                            required: true,
+                        },
+                        qg_shared::CommandOption {
+                            name: String::from("official"),
+                            description: String::from("Whether the picture is official (defaults to true)"),
+                            option_type: qg_shared::CommandOptionType::Boolean,
+                            choices: UnorderedVec::from(vec![]),
+                            required: false,
+                        },
+                    ])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+            ]
+            .into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let tx = match db {
+            Some(db) => db,
+            None => return Err(anyhow!("No database connection")),
+        };
+
+        let guild_id = interaction.guild_id.ok_or(anyhow!("Settings can only be changed in servers"))?;
+
+        if !interaction.member.as_ref().and_then(|member| member.permissions).map(|p| p.manage_guild()).unwrap_or(false) {
+            return Err(anyhow!("You need the Manage Server permission to change settings"));
+        }
+
+        let option = interaction.data.options.first().ok_or(anyhow!("No options found"))?;
+        let options = match option.value {
+            CommandDataOptionValue::SubCommand(ref options) => options,
+            _ => return Err(anyhow!("Expected SubCommand, got {:?}", option.value)),
+        };
+
+        let content = match option.name.as_str() {
+            "queue_channel" => {
+                let channel = match options.first().map(|option| &option.value) {
+                    Some(CommandDataOptionValue::Channel(channel)) => Some(*channel),
+                    Some(value) => return Err(anyhow!("Expected Channel, got {:?}", value)),
+                    None => None,
+                };
+                GuildSettings::set_queue_channel(guild_id.get() as i64, channel.map(|c| c.get() as i64), tx).await?;
+                match channel {
+                    Some(channel) => format!("Matched games will be posted in {}", channel.mention()),
+                    None => String::from("Matched games will be posted wherever the match was found"),
+                }
+            }
+            "game_threads" => {
+                let enabled = match options.first().map(|option| &option.value) {
+                    Some(CommandDataOptionValue::Boolean(enabled)) => *enabled,
+                    Some(value) => return Err(anyhow!("Expected Boolean, got {:?}", value)),
+                    None => return Err(anyhow!("No value specified")),
+                };
+                GuildSettings::set_game_threads(guild_id.get() as i64, enabled, tx).await?;
+                if enabled {
+                    String::from("Accepted challenges will be played in their own thread")
+                } else {
+                    String::from("Games will be played right in the channel")
+                }
+            }
+            "official_puzzle" => {
+                let mut image = None;
+                let mut official = true;
+                for option in options.iter() {
+                    match (option.name.as_str(), &option.value) {
+                        ("image", CommandDataOptionValue::Attachment(id)) => {
+                            image = Some(interaction.data.resolved.attachments.get(id).ok_or(anyhow!("Attachment not found"))?.clone());
+                        }
+                        ("official", CommandDataOptionValue::Boolean(value)) => {
+                            official = *value;
+                        }
+                        (v, _) => return Err(anyhow!("Unhandled option `{}`", v)),
+                    }
+                }
+                let image = image.ok_or(anyhow!("No image specified"))?;
+                let hash = OfficialPuzzle::hash(&image.download().await?);
+                OfficialPuzzle::set(guild_id.get() as i64, hash, official, tx).await?;
+                if official {
+                    format!("Sliding puzzles using `{}` will count for the leaderboards", image.filename)
+                } else {
+                    format!("Sliding puzzles using `{}` are unranked again", image.filename)
+                }
+            }
+            v => return Err(anyhow!("Unhandled option `{}`", v)),
+        };
+
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(content).ephemeral(true)))
+            .await?;
+
+        Ok(())
+    }
+}
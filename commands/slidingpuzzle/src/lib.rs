@@ -1,13 +1,18 @@
 use qg_shared::{
     anyhow::{anyhow, Result},
     colored::Colorize,
-    rand::Rng as _,
+    rand::{rngs::StdRng, seq::SliceRandom as _, Rng as _, SeedableRng as _},
     serenity::all::*,
 };
 
 use serde::{Deserialize, Serialize};
 
+mod picture;
+mod race;
+mod solver;
+
 pub fn command() -> SlidingPuzzle {
+    solver::prepare();
     SlidingPuzzle
 }
 
@@ -24,25 +29,89 @@ impl qg_shared::Command for SlidingPuzzle {
         qg_shared::CommandInfo {
             name: String::from("slidingpuzzle"),
             description: String::from("Play a game of sliding puzzle!"),
-            options: vec![].into(),
+            options: vec![
+                qg_shared::CommandOption {
+                    name: String::from("play"),
+                    description: String::from("Solve a sliding puzzle on your own"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(qg_shared::UnorderedVec::from(vec![qg_shared::CommandOption {
+                        name: String::from("image"),
+                        description: String::from("Play with a picture instead of numbers, unranked unless the server has made it official"),
+                        option_type: qg_shared::CommandOptionType::Attachment,
+                        choices: qg_shared::UnorderedVec::from(vec![]),
+                        required: false,
+                    }])),
+                    choices: qg_shared::UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("race"),
+                    description: String::from("Race someone to solve the same scramble"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(qg_shared::UnorderedVec::from(vec![
+                        qg_shared::CommandOption {
+                            name: String::from("opponent"),
+                            description: String::from("Who to race against"),
+                            option_type: qg_shared::CommandOptionType::User,
+                            choices: qg_shared::UnorderedVec::from(vec![]),
+                            required: true,
+                        },
+                        qg_shared::CommandOption {
+                            name: String::from("size"),
+                            description: String::from("The size of the board (defaults to 3x3)"),
+                            option_type: qg_shared::CommandOptionType::String,
+                            choices: qg_shared::UnorderedVec::from(
+                                [Size::Three, Size::Four, Size::Five, Size::Six, Size::Seven, Size::Eight]
+                                    .iter()
+                                    .map(|size| qg_shared::CommandOptionChoice {
+                                        name: size.name().to_string(),
+                                        value: size.name().to_string(),
+                                    })
+                                    .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                            ),
+                            required: false,
+                        },
+                        qg_shared::CommandOption {
+                            name: String::from("difficulty"),
+                            description: String::from("How scrambled the board is (defaults to easy)"),
+                            option_type: qg_shared::CommandOptionType::String,
+                            choices: qg_shared::UnorderedVec::from(
+                                [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+                                    .iter()
+                                    .map(|difficulty| qg_shared::CommandOptionChoice {
+                                        name: difficulty.name().to_string(),
+                                        value: difficulty.name().to_string(),
+                                    })
+                                    .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                            ),
+                            required: false,
+                        },
+                    ])),
+                    choices: qg_shared::UnorderedVec::from(vec![]),
+                    required: false,
+                },
+            ]
+            .into(),
         }
     }
 
-    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, _: &mut qg_shared::OptTrans<'_>) -> Result<()> {
-        let game = Game {
-            player: Player { id: interaction.user.id },
-            gamestate: State::AwaitingApproval(Awaiting { inviter: interaction.user.id }),
-            start_time: None,
-            moves: 0,
-            difficulty: Difficulty::Easy,
-            size: Size::Three,
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let option = interaction.data.options.first().ok_or(anyhow!("No options found"))?.clone();
+        let options = match option.value {
+            CommandDataOptionValue::SubCommand(ref options) => options,
+            _ => return Err(anyhow!("Expected SubCommand, got {:?}", option.value)),
         };
-
-        game.send(ctx, interaction).await?;
-        Ok(())
+        match option.name.as_str() {
+            "play" => Self::play(ctx, interaction, options, db).await,
+            "race" => race::challenge(ctx, interaction, options).await,
+            v => Err(anyhow!("Unhandled option `{}`", v)),
+        }
     }
 
     async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        // the race scoreboard has its own buttons and state
+        if let Some(action) = race::Action::from_custom_id(&interaction.data.custom_id) {
+            return race::do_action(ctx, interaction, action, db).await;
+        }
+
         let action = match Action::from_custom_id(&interaction.data.custom_id) {
             Some(action) => action,
             None => return Err(qg_shared::anyhow::anyhow!("Invalid action id")),
@@ -62,12 +131,66 @@ impl qg_shared::Command for SlidingPuzzle {
     }
 }
 
+impl SlidingPuzzle {
+    /// `/slidingpuzzle play`
+    async fn play(ctx: &Context, interaction: &mut CommandInteraction, options: &[CommandDataOption], db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let mut picture = None;
+        let mut source = None;
+        let mut deferred = false;
+        if let Some(option) = options.iter().find(|option| option.name == "image") {
+            let attachment = match option.value {
+                CommandDataOptionValue::Attachment(id) => interaction.data.resolved.attachments.get(&id).ok_or(anyhow!("Attachment not found"))?,
+                _ => return Err(anyhow!("Expected Attachment, got {:?}", option.value)),
+            };
+            if !attachment.content_type.as_deref().unwrap_or_default().starts_with("image/") {
+                return Err(anyhow!("That attachment isn't an image"));
+            }
+            if attachment.size > picture::MAX_UPLOAD_BYTES {
+                return Err(anyhow!("That image is too big, try one under {}MB", picture::MAX_UPLOAD_BYTES / 1024 / 1024));
+            }
+            // downloading and drawing a big picture can take longer than discord waits for a response
+            interaction.defer(&ctx.http).await?;
+            deferred = true;
+            let bytes = attachment.download().await?;
+            let hash = qg_shared::db::OfficialPuzzle::hash(&bytes);
+            let official = match (db, interaction.guild_id) {
+                (Some(db), Some(guild_id)) => qg_shared::db::OfficialPuzzle::is_official(guild_id.get() as i64, hash, db).await?,
+                _ => false,
+            };
+            source = Some(tokio::task::spawn_blocking(move || picture::prepare(&bytes)).await??);
+            picture = Some(Picture { official, numbers: false });
+        }
+
+        let game = Game {
+            player: Player { id: interaction.user.id },
+            gamestate: State::AwaitingApproval(Awaiting { inviter: interaction.user.id }),
+            start_time: None,
+            moves: 0,
+            difficulty: Difficulty::Easy,
+            size: Size::Three,
+            picture,
+            elapsed: 0,
+            seed: 0,
+            race: None,
+            deferred,
+        };
+
+        game.send(ctx, interaction, source).await?;
+        Ok(())
+    }
+}
+
 pub enum Action {
     SetDifficulty(Difficulty),
     SetSize(Size),
     Start,
     MoveTile(usize, usize),
     InvalidMove(usize),
+    Hint,
+    Slide(Direction), // the direction the tile moves, so the empty space goes the other way
+    ToggleNumbers,
+    Pause,
+    Resume,
 }
 
 impl Action {
@@ -78,6 +201,11 @@ impl Action {
             Self::Start => "Start",
             Self::MoveTile(_, _) => "MoveTile",
             Self::InvalidMove(_) => "InvalidMove",
+            Self::Hint => "Hint",
+            Self::Slide(_) => "Slide",
+            Self::ToggleNumbers => "ToggleNumbers",
+            Self::Pause => "Pause",
+            Self::Resume => "Resume",
         }
     }
 }
@@ -98,6 +226,15 @@ impl Difficulty {
         }
     }
 
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Easy" => Some(Self::Easy),
+            "Medium" => Some(Self::Medium),
+            "Hard" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+
     pub fn name_with_ansi(&self) -> String {
         match self {
             Self::Easy => "Easy".green().to_string(),
@@ -113,6 +250,31 @@ impl Difficulty {
             Self::Hard => ButtonStyle::Danger,
         }
     }
+
+    /// how many moves a scramble can be from solved, each band is roughly a third of all the boards of that size.
+    /// 3x3 uses the exact solution length, the bigger sizes use the solver's estimate since solving them takes too long
+    fn band(&self, size: Size) -> std::ops::RangeInclusive<u32> {
+        match (size, self) {
+            (Size::Three, Self::Easy) => 1..=20,
+            (Size::Three, Self::Medium) => 21..=23,
+            (Size::Three, Self::Hard) => 24..=u32::MAX,
+            (Size::Four, Self::Easy) => 1..=36,
+            (Size::Four, Self::Medium) => 37..=41,
+            (Size::Four, Self::Hard) => 42..=u32::MAX,
+            (Size::Five, Self::Easy) => 1..=75,
+            (Size::Five, Self::Medium) => 76..=82,
+            (Size::Five, Self::Hard) => 83..=u32::MAX,
+            (Size::Six, Self::Easy) => 1..=135,
+            (Size::Six, Self::Medium) => 136..=143,
+            (Size::Six, Self::Hard) => 144..=u32::MAX,
+            (Size::Seven, Self::Easy) => 1..=218,
+            (Size::Seven, Self::Medium) => 219..=230,
+            (Size::Seven, Self::Hard) => 231..=u32::MAX,
+            (Size::Eight, Self::Easy) => 1..=328,
+            (Size::Eight, Self::Medium) => 329..=343,
+            (Size::Eight, Self::Hard) => 344..=u32::MAX,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -132,6 +294,34 @@ impl Direction {
             Self::Right => "Right",
         }
     }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Up" => Some(Self::Up),
+            "Down" => Some(Self::Down),
+            "Left" => Some(Self::Left),
+            "Right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    fn arrow(&self) -> &'static str {
+        match self {
+            Self::Up => "⬆️",
+            Self::Down => "⬇️",
+            Self::Left => "⬅️",
+            Self::Right => "➡️",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -139,6 +329,9 @@ pub enum Size {
     Three,
     Four,
     Five,
+    Six,
+    Seven,
+    Eight,
 }
 
 impl Size {
@@ -147,6 +340,21 @@ impl Size {
             Self::Three => "3x3",
             Self::Four => "4x4",
             Self::Five => "5x5",
+            Self::Six => "6x6",
+            Self::Seven => "7x7",
+            Self::Eight => "8x8",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "3x3" => Some(Self::Three),
+            "4x4" => Some(Self::Four),
+            "5x5" => Some(Self::Five),
+            "6x6" => Some(Self::Six),
+            "7x7" => Some(Self::Seven),
+            "8x8" => Some(Self::Eight),
+            _ => None,
         }
     }
 
@@ -155,6 +363,9 @@ impl Size {
             Self::Three => "3x3".green().to_string(),
             Self::Four => "4x4".blue().to_string(),
             Self::Five => "5x5".red().to_string(),
+            Self::Six => "6x6".magenta().to_string(),
+            Self::Seven => "7x7".magenta().to_string(),
+            Self::Eight => "8x8".magenta().to_string(),
         }
     }
 
@@ -163,8 +374,16 @@ impl Size {
             Self::Three => 3,
             Self::Four => 4,
             Self::Five => 5,
+            Self::Six => 6,
+            Self::Seven => 7,
+            Self::Eight => 8,
         }
     }
+
+    /// discord allows 5 rows of 5 buttons, anything bigger is drawn as text and played with a direction pad
+    fn uses_dpad(&self) -> bool {
+        self.numeral() > 5
+    }
 }
 
 impl Action {
@@ -172,24 +391,8 @@ impl Action {
         let mut split = custom_id.split(':').skip(1);
         let action = split.next()?;
         match action {
-            "SetDifficulty" => {
-                let difficulty = split.next()?;
-                Some(Self::SetDifficulty(match difficulty {
-                    "Easy" => Difficulty::Easy,
-                    "Medium" => Difficulty::Medium,
-                    "Hard" => Difficulty::Hard,
-                    _ => return None,
-                }))
-            }
-            "SetSize" => {
-                let size = split.next()?;
-                Some(Self::SetSize(match size {
-                    "3x3" => Size::Three,
-                    "4x4" => Size::Four,
-                    "5x5" => Size::Five,
-                    _ => return None,
-                }))
-            }
+            "SetDifficulty" => Some(Self::SetDifficulty(Difficulty::from_name(split.next()?)?)),
+            "SetSize" => Some(Self::SetSize(Size::from_name(split.next()?)?)),
             "Start" => Some(Self::Start),
             "MoveTile" => Some(Self::MoveTile(
                 {
@@ -205,6 +408,11 @@ impl Action {
                 let s = split.next()?;
                 s.parse::<usize>().ok()?
             })),
+            "Hint" => Some(Self::Hint),
+            "Slide" => Some(Self::Slide(Direction::from_name(split.next()?)?)),
+            "ToggleNumbers" => Some(Self::ToggleNumbers),
+            "Pause" => Some(Self::Pause),
+            "Resume" => Some(Self::Resume),
             _ => None,
         }
     }
@@ -215,6 +423,11 @@ impl Action {
             Self::Start => format!("{}:Start", command),
             Self::MoveTile(s, f) => format!("{}:MoveTile:{}:{}", command, s, f),
             Self::InvalidMove(i) => format!("{}:InvalidMove:{}", command, i),
+            Self::Hint => format!("{}:Hint", command),
+            Self::Slide(direction) => format!("{}:Slide:{}", command, direction.name()),
+            Self::ToggleNumbers => format!("{}:ToggleNumbers", command),
+            Self::Pause => format!("{}:Pause", command),
+            Self::Resume => format!("{}:Resume", command),
         }
     }
 }
@@ -223,16 +436,83 @@ impl Action {
 pub struct Game {
     player: Player,
     gamestate: State,
-    start_time: Option<u64>,
+    start_time: Option<u64>, // when the clock was last started in milliseconds, `None` while it's stopped
     moves: u64,
     difficulty: Difficulty,
     size: Size,
+    #[serde(default)]
+    picture: Option<Picture>, // set when playing with an uploaded picture instead of numbers
+    #[serde(default)]
+    elapsed: u64, // milliseconds on the clock before `start_time`, from before the last pause
+    #[serde(default)]
+    seed: u64, // the scramble is drawn from this, so a finished game can be replayed from the start
+    #[serde(default)]
+    race: Option<race::RaceLink>, // set on each player's board in a race
+    #[serde(skip)]
+    deferred: bool, // the interaction has already been acknowledged, so rendering only edits the response
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Picture {
+    official: bool, // the server has approved the picture, so the game counts for the leaderboards like a number puzzle
+    numbers: bool,  // whether each tile's number is drawn over the picture
 }
 
 impl Game {
+    /// milliseconds on the clock at `now`, time spent paused isn't counted
+    fn elapsed_at(&self, now: u64) -> u64 {
+        self.elapsed + self.start_time.map(|start_time| now.saturating_sub(start_time)).unwrap_or(0)
+    }
+
+    /// replays the logged moves from `board`, the scramble the seed makes, returning the tiles moved if they really solve it
+    /// in the moves and time the game says they did
+    fn verify(&self, mut board: Board, log: &[qg_shared::db::SlidingPuzzleMove], elapsed: u64) -> Result<Vec<i32>> {
+        if log.len() as u64 != self.moves {
+            return Err(anyhow!("{} moves were logged for {} played", log.len(), self.moves));
+        }
+        let mut last = 0;
+        for (i, entry) in log.iter().enumerate() {
+            if entry.number as usize != i + 1 {
+                return Err(anyhow!("Move {} was logged as move {}", i + 1, entry.number));
+            }
+            if (entry.elapsed as u64) < last {
+                return Err(anyhow!("Move {} was made before the one before it", entry.number));
+            }
+            last = entry.elapsed as u64;
+            let tile = board
+                .spaces
+                .iter()
+                .position(|space| *space == Space::Value(entry.tile as u8))
+                .ok_or(anyhow!("There's no tile {}", entry.tile))?;
+            let empty = board.spaces.iter().position(|space| *space == Space::Empty).ok_or(anyhow!("No empty space"))?;
+            board.swap_checked(tile, empty)?;
+        }
+        if !board.check_winner() {
+            return Err(anyhow!("The moves don't solve the scramble"));
+        }
+        if last != elapsed {
+            return Err(anyhow!("The last move was logged at {}ms, the game finished at {}ms", last, elapsed));
+        }
+        Ok(log.iter().map(|entry| entry.tile).collect())
+    }
+
+    /// the logged moves, checked against the scramble with `verify`
+    async fn replayed(&self, interaction: &ComponentInteraction, now: u64, db: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<Vec<i32>> {
+        let log = qg_shared::db::SlidingPuzzleMove::for_game(interaction.message.id.get() as i64, db).await?;
+        // scrambling runs the solver, so like the first time it's kept off the async workers
+        let (size, difficulty, seed) = (self.size, self.difficulty, self.seed);
+        let scramble = tokio::task::spawn_blocking(move || Board::new(size, difficulty, seed)).await?;
+        self.verify(scramble, &log, self.elapsed_at(now)).map_err(|e| {
+            qg_shared::log::warn!("Sliding puzzle {} failed its replay: {}", interaction.message.id, e);
+            anyhow!("This solve couldn't be verified, so it wasn't recorded")
+        })
+    }
+
     pub async fn do_action(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
-        let shitstarted = qg_shared::current_time()?;
-        let mut updatetime = false;
+        // timed from when the button was clicked, so the time it takes us to respond doesn't count against the player
+        let now = qg_shared::snowflake_millis(interaction.id.get());
+        // pausing and resuming don't change it, so it holds for the whole click
+        let elapsed = self.elapsed_at(now);
         match self.gamestate {
             State::AwaitingApproval(ref u) => {
                 if interaction.user.id != u.inviter {
@@ -240,9 +520,26 @@ impl Game {
                 }
                 match action {
                     Action::Start => {
-                        self.gamestate = State::InProgress(InProgress {
-                            board: Board::new(self.size, self.difficulty),
-                        });
+                        // a race has already picked the scramble both players get
+                        self.seed = match self.race {
+                            Some(race) => race::seed(race, db).await?,
+                            None => qg_shared::rand::thread_rng().gen(),
+                        };
+                        // scrambling and solving can take a couple of seconds, longer than discord waits for an answer
+                        interaction.defer(&ctx.http).await?;
+                        self.deferred = true;
+                        let (size, difficulty, seed) = (self.size, self.difficulty, self.seed);
+                        let (board, optimal) = tokio::task::spawn_blocking(move || {
+                            let board = Board::new(size, difficulty, seed);
+                            let optimal = solver::solve(&board).map(|solution| solution.len() as u64);
+                            (board, optimal)
+                        })
+                        .await?;
+                        self.gamestate = State::InProgress(InProgress { board, optimal, hint: None, hints: 0 });
+                        self.start_time = Some(now);
+                    }
+                    Action::SetDifficulty(_) | Action::SetSize(_) if self.race.is_some() => {
+                        return Err(anyhow!("The race decides the size and difficulty"));
                     }
                     Action::SetDifficulty(difficulty) => {
                         self.difficulty = difficulty;
@@ -259,28 +556,59 @@ impl Game {
                 if interaction.user.id != self.player.id {
                     return Err(anyhow!("You are not the player"));
                 }
+                let paused = self.start_time.is_none();
                 match action {
-                    Action::MoveTile(s, f) => {
-                        match self.start_time {
-                            None => {
-                                self.start_time = Some(qg_shared::current_time()?);
-                            }
-                            Some(_) => {}
-                        };
-                        game.board.swap_checked(s, f)?;
+                    Action::Resume if paused => {
+                        self.start_time = Some(now);
+                    }
+                    _ if paused => {
+                        return Err(anyhow!("The game is paused"));
+                    }
+                    Action::Pause => {
+                        self.elapsed = elapsed;
+                        self.start_time = None;
+                    }
+                    Action::MoveTile(_, _) | Action::Slide(_) => {
+                        let empty = game.board.spaces.iter().position(|space| *space == Space::Empty).ok_or(anyhow!("No empty space"))?;
+                        match action {
+                            Action::MoveTile(s, f) => game.board.swap_checked(s, f)?,
+                            Action::Slide(direction) => game.board.move_empty_tile(direction.opposite())?,
+                            _ => unreachable!(),
+                        }
+                        game.hint = None;
                         self.moves += 1;
+                        if let (Some(db), Space::Value(tile)) = (db.as_mut(), game.board.spaces[empty]) {
+                            qg_shared::db::SlidingPuzzleMove::log(interaction.message.id.get() as i64, self.moves as i32, tile as i32, elapsed as i32, db).await?;
+                        }
                         if game.board.check_winner() {
+                            let (optimal, hints, board) = (game.optimal, game.hints, game.board.clone());
                             self.gamestate = State::Finished(WonGame {
                                 winner: Outcome {
-                                    elapsed: qg_shared::current_time()? - self.start_time.unwrap_or(qg_shared::current_time()?),
+                                    elapsed,
                                     moves: self.moves,
                                     player: self.player,
                                 },
-                                board: game.board.clone(),
+                                board: board.clone(),
+                                optimal,
+                                hints,
                             });
                             // early render so the player isnt waiting on the sql query to finish
-                            self.render(ctx, interaction, self.start_time).await?;
+                            self.render(ctx, interaction, now).await?;
+                            // a race is only won by a solve that replays, the same as a leaderboard entry
+                            if let Some(race) = self.race {
+                                if let Some(db) = db.as_mut() {
+                                    self.replayed(interaction, now, db).await?;
+                                }
+                                race::finish(ctx, interaction, race, &board, elapsed, self.moves, db).await?;
+                                return Ok(());
+                            }
+                            // pictures the server hasn't approved are unranked, so there's nothing to record
+                            if self.picture.map(|picture| !picture.official).unwrap_or(false) {
+                                return Ok(());
+                            }
                             if let Some(db) = db {
+                                // the moves have been logged as they were played, they have to solve the scramble before the result is saved
+                                let solution = self.replayed(interaction, now, db).await?;
                                 // ensure the user is in the database first
                                 let user = qg_shared::db::User::get_or_create(ctx, &self.player.id, db).await?;
                                 // create an entry for the user in the slidingpuzzle table
@@ -289,21 +617,51 @@ impl Game {
                                     self.difficulty as i32,
                                     self.size as i32,
                                     self.moves as i32,
-                                    (qg_shared::current_time()? - self.start_time.unwrap_or(qg_shared::current_time()?)) as i32,
+                                    elapsed as i32,
+                                    optimal.map(|optimal| optimal as i32),
+                                    hints > 0,
+                                    interaction.guild_id.map(|g| g.get() as i64),
+                                    Some(interaction.message.id.get() as i64),
+                                    self.seed as i64,
+                                    &solution,
                                     db,
                                 )
                                 .await?;
 
                                 println!("Created SlidingPuzzle entry: {:?}", s);
+
+                                let result = qg_shared::db::GameResult::SlidingPuzzle {
+                                    size: s.size,
+                                    difficulty: s.difficulty,
+                                    moves: s.score,
+                                    time: s.time,
+                                };
+                                let unlocked = qg_shared::db::Achievement::evaluate(&user, &result, db).await?;
+                                qg_shared::db::Achievement::notify_later(self.player.id, unlocked);
                             }
                             return Ok(()); // we dont want to render again
-                        } else {
-                            updatetime = true;
                         }
                     }
                     Action::InvalidMove(_) => {
                         // do nothing
                     }
+                    Action::ToggleNumbers => {
+                        let picture = self.picture.as_mut().ok_or(anyhow!("This puzzle doesn't have a picture"))?;
+                        picture.numbers = !picture.numbers;
+                    }
+                    Action::Hint if self.race.is_some() => {
+                        return Err(anyhow!("There are no hints in a race"));
+                    }
+                    Action::Hint => {
+                        interaction.defer(&ctx.http).await?;
+                        self.deferred = true;
+                        let board = game.board.clone();
+                        let solution = tokio::task::spawn_blocking(move || solver::solve(&board))
+                            .await?
+                            .ok_or(anyhow!("Couldn't find a hint in time, try again after a few more moves"))?;
+                        game.hint = solution.first().copied();
+                        game.hints += 1;
+                    }
                     _ => {
                         return Err(anyhow!("Invalid action"));
                     }
@@ -314,54 +672,42 @@ impl Game {
             }
         }
 
-        if updatetime {
-            // add the difference between now and when the function started to the start time
-            let mut now = qg_shared::current_time()?;
-            now -= shitstarted;
-            if let Some(start_time) = self.start_time {
-                self.start_time = Some(start_time + now);
-            }
-        }
-
-        self.render(ctx, interaction, self.start_time).await.map_err(|e| {
+        self.render(ctx, interaction, now).await.map_err(|e| {
             qg_shared::log::error!("Error rendering game: {}", e);
             e
         })?;
 
-        if updatetime {
-            // add the difference between now and when the function started to the start time
-            let mut now = qg_shared::current_time()?;
-            now -= shitstarted;
-            if let Some(start_time) = self.start_time {
-                self.start_time = Some(start_time + now);
-            }
+        if let (Some(race), State::InProgress(game)) = (self.race, &self.gamestate) {
+            race::report(ctx, race, &game.board, self.moves).await?;
         }
 
         Ok(())
     }
 
-    async fn render(&self, ctx: &Context, interaction: &mut ComponentInteraction, start_time: Option<u64>) -> Result<()> {
+    /// acknowledges the click, unless starting or a hint already did before running the solver
+    async fn defer(&self, ctx: &Context, interaction: &mut ComponentInteraction) -> Result<()> {
+        if !self.deferred {
+            interaction.defer(&ctx.http).await?;
+        }
+        Ok(())
+    }
+    /// `now` is when the interaction being answered was made, in milliseconds
+    async fn render(&self, ctx: &Context, interaction: &mut ComponentInteraction, now: u64) -> Result<()> {
         match &self.gamestate {
             State::AwaitingApproval(ref u) => {
                 let mut content = self.title_card()?;
                 content.push_str(u.challenge_message().as_str());
-                interaction.defer(&ctx.http).await?;
+                if self.picture.map(|picture| !picture.official).unwrap_or(false) {
+                    content.push_str("\nThis picture isn't an official puzzle here, so the game is unranked");
+                }
+                self.defer(ctx, interaction).await?;
                 interaction
                     .edit_response(&ctx.http, {
                         EditInteractionResponse::default().content(content).components({
                             vec![
-                                CreateActionRow::Buttons({
-                                    let mut buttons = Vec::new();
-                                    for size in &[Size::Three, Size::Four, Size::Five] {
-                                        buttons.push(
-                                            CreateButton::new(Action::SetSize(*size).to_custom_id("slidingpuzzle"))
-                                                .style(if *size == self.size { ButtonStyle::Primary } else { ButtonStyle::Secondary })
-                                                .label(size.name())
-                                                .disabled(*size == self.size),
-                                        );
-                                    }
-                                    buttons
-                                }),
+                                // five buttons fit in a row, so the sizes are split over two
+                                self.size_row(&[Size::Three, Size::Four, Size::Five]),
+                                self.size_row(&[Size::Six, Size::Seven, Size::Eight]),
                                 CreateActionRow::Buttons({
                                     let mut buttons = Vec::new();
                                     for difficulty in &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
@@ -409,44 +755,115 @@ impl Game {
                     })
                     .await?;
             }
+            State::InProgress(_) if self.start_time.is_none() => {
+                // the board is hidden while paused so it can't be studied off the clock
+                let mut content = self.title_card()?;
+                content.push_str(
+                    format!(
+                        "```ansi\nTime: {} (paused)\nMoves: {}\n```",
+                        qg_shared::format_duration_ms(self.elapsed).yellow(),
+                        self.moves.to_string().red(),
+                    )
+                    .as_str(),
+                );
+                self.defer(ctx, interaction).await?;
+                interaction
+                    .edit_response(&ctx.http, {
+                        let response =
+                            EditInteractionResponse::default()
+                                .content(content)
+                                .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(Action::Resume.to_custom_id("slidingpuzzle"))
+                                    .style(ButtonStyle::Success)
+                                    .label("Resume")])]);
+                        // a picture puzzle keeps the solved picture but not the board
+                        interaction
+                            .message
+                            .attachments
+                            .iter()
+                            .filter(|attachment| attachment.filename == picture::SOURCE_NAME)
+                            .fold(response, |response, attachment| response.keep_existing_attachment(attachment.id))
+                    })
+                    .await?;
+            }
             State::InProgress(game) => {
                 let mut content = self.title_card()?;
+                if self.size.uses_dpad() && self.picture.is_none() {
+                    content.push_str(format!("```ansi\n{}```", game.board.text()).as_str());
+                }
                 content.push_str(
                     format!(
-                        "```ansi\nTime: {}\nMoves: {}\n```",
-                        match start_time {
-                            None => "0s (paused)".to_string().red(),
-                            Some(start_time) => qg_shared::format_duration(qg_shared::current_time()? - start_time).blue(),
-                        },
+                        "```ansi\nTime: {}\nMoves: {}\n{}```",
+                        qg_shared::format_duration_ms(self.elapsed_at(now)).blue(),
                         self.moves.to_string().red(),
+                        match game.hint.map(|i| game.board.spaces[i]) {
+                            Some(Space::Value(v)) => format!("Hint: slide {}\n", v.to_string().yellow()),
+                            _ => String::new(),
+                        },
                     )
                     .as_str(),
                 );
-                interaction.defer(&ctx.http).await?;
+                self.defer(ctx, interaction).await?;
+                let drawn = match self.picture {
+                    Some(picture) => {
+                        let (id, source) = Self::picture_source(interaction).await?;
+                        let board = game.board.clone();
+                        Some((id, tokio::task::spawn_blocking(move || picture::render(&source, &board, picture.numbers)).await??))
+                    }
+                    None => None,
+                };
                 interaction
                     .edit_response(&ctx.http, {
-                        EditInteractionResponse::default().content(content).components({
-                            let mut rows = Vec::new();
-                            for x in 0..self.size.numeral() {
-                                rows.push(CreateActionRow::Buttons({
-                                    let mut buttons = Vec::new();
-                                    for y in 0..self.size.numeral() {
-                                        buttons.push(game.board.button_for(y, x));
+                        let response = EditInteractionResponse::default().content(content).components({
+                            if let Some(picture) = self.picture {
+                                // the picture replaces the tile buttons, so it's always played with the arrows
+                                let mut rows = game.board.dpad();
+                                let mut buttons = vec![CreateButton::new(Action::ToggleNumbers.to_custom_id("slidingpuzzle"))
+                                    .style(ButtonStyle::Secondary)
+                                    .label(if picture.numbers { "Hide numbers" } else { "Show numbers" })];
+                                if !self.size.uses_dpad() {
+                                    buttons.push(CreateButton::new(Action::Hint.to_custom_id("slidingpuzzle")).style(ButtonStyle::Secondary).label("Hint"));
+                                }
+                                rows.push(CreateActionRow::Buttons(buttons));
+                                rows
+                            } else if self.size.uses_dpad() {
+                                game.board.dpad()
+                            } else {
+                                let mut rows = Vec::new();
+                                for x in 0..self.size.numeral() {
+                                    rows.push(CreateActionRow::Buttons({
+                                        let mut buttons = Vec::new();
+                                        for y in 0..self.size.numeral() {
+                                            buttons.push(game.board.button_for(y, x));
+                                        }
+                                        buttons
+                                    }))
+                                    // c.create_action_row(|a| {
+                                    //     for y in 0..self.size.numeral() {
+                                    //         a.create_button(|b| {
+                                    //             game.board.button_for(y, x, b);
+                                    //             b
+                                    //         });
+                                    //     }
+                                    //     a
+                                    // });
+                                }
+                                // a 5x5 board uses up all five rows discord allows, its empty space is the pause button instead
+                                if rows.len() < 5 {
+                                    let mut buttons = vec![];
+                                    // races are played without hints
+                                    if self.race.is_none() {
+                                        buttons.push(CreateButton::new(Action::Hint.to_custom_id("slidingpuzzle")).style(ButtonStyle::Secondary).label("Hint"));
                                     }
-                                    buttons
-                                }))
-                                // c.create_action_row(|a| {
-                                //     for y in 0..self.size.numeral() {
-                                //         a.create_button(|b| {
-                                //             game.board.button_for(y, x, b);
-                                //             b
-                                //         });
-                                //     }
-                                //     a
-                                // });
+                                    buttons.push(CreateButton::new(Action::Pause.to_custom_id("slidingpuzzle")).style(ButtonStyle::Secondary).label("Pause"));
+                                    rows.push(CreateActionRow::Buttons(buttons));
+                                }
+                                rows
                             }
-                            rows
-                        })
+                        });
+                        match drawn {
+                            Some((source, board)) => response.keep_existing_attachment(source).new_attachment(CreateAttachment::bytes(board, picture::BOARD_NAME)),
+                            None => response,
+                        }
                     })
                     .await?;
             }
@@ -454,19 +871,43 @@ impl Game {
                 let mut content = self.title_card()?;
                 content.push_str(
                     format!(
-                        "{} has won!\n```ansi\nSize: {}\nDifficulty: {}\nTime: {}\nMoves: {}\n```",
+                        "{} {}\n```ansi\nSize: {}\nDifficulty: {}\nTime: {}\nMoves: {}{}\n{}```",
                         won_game.winner.player.id.mention(),
+                        // whether it won the race is on the scoreboard
+                        if self.race.is_some() { "solved it!" } else { "has won!" },
                         self.size.name_with_ansi(),
                         self.difficulty.name_with_ansi(),
-                        qg_shared::format_duration(won_game.winner.elapsed).blue(),
+                        qg_shared::format_duration_ms(won_game.winner.elapsed).blue(),
                         won_game.winner.moves.to_string().red(),
+                        match won_game.optimal {
+                            Some(optimal) => format!(
+                                " (optimal {}, {:.0}% efficient)",
+                                optimal.to_string().green(),
+                                optimal as f64 / won_game.winner.moves.max(1) as f64 * 100.0
+                            ),
+                            None => String::new(),
+                        },
+                        if won_game.hints > 0 {
+                            format!("Hints: {}\n", won_game.hints.to_string().yellow())
+                        } else {
+                            String::new()
+                        },
                     )
                     .as_str(),
                 );
-                interaction.defer(&ctx.http).await?;
+                self.defer(ctx, interaction).await?;
+                // the finished picture is shown in full, without the spoiler
+                let solved = match self.picture {
+                    Some(_) => Some(Self::picture_source(interaction).await?.1),
+                    None => None,
+                };
                 interaction
                     .edit_response(&ctx.http, {
-                        EditInteractionResponse::default().content(content).components(vec![])
+                        let response = EditInteractionResponse::default().content(content).components(vec![]);
+                        match solved {
+                            Some(solved) => response.new_attachment(CreateAttachment::bytes(solved, "puzzle.png")),
+                            None => response,
+                        }
                         // d.content(content).components(|c| {
                         //     // no components, its a sliding puzzle lol.
                         //     c
@@ -477,73 +918,99 @@ impl Game {
         }
         Ok(())
     }
-    async fn send(&self, ctx: &Context, interaction: &mut CommandInteraction) -> Result<()> {
+    /// the prepared picture that was sent with the puzzle, every render is cut from it
+    async fn picture_source(interaction: &ComponentInteraction) -> Result<(AttachmentId, Vec<u8>)> {
+        let source = interaction
+            .message
+            .attachments
+            .iter()
+            .find(|attachment| attachment.filename == picture::SOURCE_NAME)
+            .ok_or(anyhow!("The picture for this puzzle is missing"))?;
+        Ok((source.id, source.download().await?))
+    }
+    async fn send(&self, ctx: &Context, interaction: &mut CommandInteraction, source: Option<Vec<u8>>) -> Result<()> {
         match self.gamestate {
             State::AwaitingApproval(ref u) => {
                 let mut content = self.title_card()?;
                 content.push_str(u.challenge_message().as_str());
-                interaction
-                    .create_response(&ctx.http, {
-                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components({
-                            vec![
-                                CreateActionRow::Buttons({
-                                    let mut buttons = Vec::new();
-                                    for size in &[Size::Three, Size::Four, Size::Five] {
-                                        buttons.push(
-                                            CreateButton::new(Action::SetSize(*size).to_custom_id("slidingpuzzle"))
-                                                .style(if *size == self.size { ButtonStyle::Primary } else { ButtonStyle::Secondary })
-                                                .label(size.name())
-                                                .disabled(*size == self.size),
-                                        );
-                                    }
-                                    buttons
-                                }),
-                                CreateActionRow::Buttons({
-                                    let mut buttons = Vec::new();
-                                    for difficulty in &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
-                                        buttons.push(
-                                            CreateButton::new(Action::SetDifficulty(*difficulty).to_custom_id("slidingpuzzle"))
-                                                .style(if *difficulty == self.difficulty { difficulty.button_style() } else { ButtonStyle::Secondary })
-                                                .label(difficulty.name())
-                                                .disabled(*difficulty == self.difficulty),
-                                        );
-                                    }
-                                    buttons
-                                }),
-                                CreateActionRow::Buttons(vec![CreateButton::new(Action::Start.to_custom_id("slidingpuzzle")).style(ButtonStyle::Success).label("Start")]),
-                            ]
-                            // c.create_action_row(|a| {
-                            //     // Size Buttons, the selected one is disabled and Primary, the others are Secondary
-                            //     for size in &[Size::Three, Size::Four, Size::Five] {
-                            //         a.create_button(|b| {
-                            //             b.style(if *size == self.size { ButtonStyle::Primary } else { ButtonStyle::Secondary })
-                            //                 .label(size.name())
-                            //                 .custom_id(Action::SetSize(*size).to_custom_id("slidingpuzzle"))
-                            //                 .disabled(*size == self.size)
-                            //         });
-                            //     }
-                            //     a
-                            // })
-                            // .create_action_row(|a| {
-                            //     // Difficulty Buttons, the selected one is disabled and depends on which difficulty, the others are Secondary
-                            //     for difficulty in &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
-                            //         a.create_button(|b| {
-                            //             b.style(if *difficulty == self.difficulty { difficulty.button_style() } else { ButtonStyle::Secondary })
-                            //                 .label(difficulty.name())
-                            //                 .custom_id(Action::SetDifficulty(*difficulty).to_custom_id("slidingpuzzle"))
-                            //                 .disabled(*difficulty == self.difficulty)
-                            //         });
-                            //     }
-                            //     a
-                            // })
-                            // .create_action_row(|a| {
-                            //     // Start Button
-                            //     a.create_button(|b| b.style(ButtonStyle::Success).label("Start").custom_id(Action::Start.to_custom_id("slidingpuzzle")));
-                            //     a
-                            // })
-                        }))
-                    })
-                    .await?;
+                if self.picture.map(|picture| !picture.official).unwrap_or(false) {
+                    content.push_str("\nThis picture isn't an official puzzle here, so the game is unranked");
+                }
+                let components = {
+                    vec![
+                        // five buttons fit in a row, so the sizes are split over two
+                        self.size_row(&[Size::Three, Size::Four, Size::Five]),
+                        self.size_row(&[Size::Six, Size::Seven, Size::Eight]),
+                        CreateActionRow::Buttons({
+                            let mut buttons = Vec::new();
+                            for difficulty in &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                                buttons.push(
+                                    CreateButton::new(Action::SetDifficulty(*difficulty).to_custom_id("slidingpuzzle"))
+                                        .style(if *difficulty == self.difficulty { difficulty.button_style() } else { ButtonStyle::Secondary })
+                                        .label(difficulty.name())
+                                        .disabled(*difficulty == self.difficulty),
+                                );
+                            }
+                            buttons
+                        }),
+                        CreateActionRow::Buttons(vec![CreateButton::new(Action::Start.to_custom_id("slidingpuzzle")).style(ButtonStyle::Success).label("Start")]),
+                    ]
+                    // c.create_action_row(|a| {
+                    //     // Size Buttons, the selected one is disabled and Primary, the others are Secondary
+                    //     for size in &[Size::Three, Size::Four, Size::Five] {
+                    //         a.create_button(|b| {
+                    //             b.style(if *size == self.size { ButtonStyle::Primary } else { ButtonStyle::Secondary })
+                    //                 .label(size.name())
+                    //                 .custom_id(Action::SetSize(*size).to_custom_id("slidingpuzzle"))
+                    //                 .disabled(*size == self.size)
+                    //         });
+                    //     }
+                    //     a
+                    // })
+                    // .create_action_row(|a| {
+                    //     // Difficulty Buttons, the selected one is disabled and depends on which difficulty, the others are Secondary
+                    //     for difficulty in &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                    //         a.create_button(|b| {
+                    //             b.style(if *difficulty == self.difficulty { difficulty.button_style() } else { ButtonStyle::Secondary })
+                    //                 .label(difficulty.name())
+                    //                 .custom_id(Action::SetDifficulty(*difficulty).to_custom_id("slidingpuzzle"))
+                    //                 .disabled(*difficulty == self.difficulty)
+                    //         });
+                    //     }
+                    //     a
+                    // })
+                    // .create_action_row(|a| {
+                    //     // Start Button
+                    //     a.create_button(|b| b.style(ButtonStyle::Success).label("Start").custom_id(Action::Start.to_custom_id("slidingpuzzle")));
+                    //     a
+                    // })
+                };
+                match source {
+                    // a picture has been downloaded and drawn, the interaction was deferred for it
+                    Some(source) if self.deferred => {
+                        interaction
+                            .edit_response(
+                                &ctx.http,
+                                EditInteractionResponse::default()
+                                    .content(content)
+                                    .new_attachment(CreateAttachment::bytes(source, picture::SOURCE_NAME))
+                                    .components(components),
+                            )
+                            .await?;
+                    }
+                    source => {
+                        interaction
+                            .create_response(&ctx.http, {
+                                CreateInteractionResponse::Message(
+                                    CreateInteractionResponseMessage::default()
+                                        .content(content)
+                                        .files(source.map(|source| CreateAttachment::bytes(source, picture::SOURCE_NAME)))
+                                        .components(components),
+                                )
+                            })
+                            .await?;
+                    }
+                }
             }
             _ => {
                 return Err(qg_shared::anyhow::anyhow!("Invalid game state"));
@@ -551,6 +1018,19 @@ impl Game {
         }
         Ok(())
     }
+    fn size_row(&self, sizes: &[Size]) -> CreateActionRow {
+        CreateActionRow::Buttons(
+            sizes
+                .iter()
+                .map(|size| {
+                    CreateButton::new(Action::SetSize(*size).to_custom_id("slidingpuzzle"))
+                        .style(if *size == self.size { ButtonStyle::Primary } else { ButtonStyle::Secondary })
+                        .label(size.name())
+                        .disabled(*size == self.size)
+                })
+                .collect(),
+        )
+    }
     fn title_card(&self) -> Result<String> {
         Ok(format!("```{}\nSliding Puzzle\n```", qg_shared::serialize(&self)?.replace('\n', "")))
     }
@@ -639,6 +1119,12 @@ impl Awaiting {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InProgress {
     board: Board,
+    #[serde(default)]
+    optimal: Option<u64>, // the fewest moves the scramble could be solved in, `None` if the solver gave up
+    #[serde(default)]
+    hint: Option<usize>, // the tile the last hint said to move, cleared on the next move
+    #[serde(default)]
+    hints: u64,
 }
 
 impl InProgress {
@@ -658,6 +1144,10 @@ impl InProgress {
 pub struct WonGame {
     winner: Outcome,
     board: Board,
+    #[serde(default)]
+    optimal: Option<u64>,
+    #[serde(default)]
+    hints: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -667,62 +1157,130 @@ pub struct Board {
 }
 
 impl Board {
-    pub fn new(size: Size, difficulty: Difficulty) -> Self {
-        let mut board = match size {
-            Size::Three => Self { spaces: vec![Space::Empty; 9], size },
-            Size::Four => Self { spaces: vec![Space::Empty; 16], size },
-            Size::Five => Self { spaces: vec![Space::Empty; 25], size },
-        };
-
+    /// the same seed always makes the same board, so finished games can be checked by replaying them.
+    /// StdRng can change between versions of rand, which would only break replays of games in progress across the upgrade
+    pub fn new(size: Size, difficulty: Difficulty, seed: u64) -> Self {
         // random number between 0 and size * size - 1
-        let mut rng = qg_shared::rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
         let emptyspace = rng.gen_range(0..((size.numeral() * size.numeral()) - 1));
 
-        for (i, s) in board.spaces.iter_mut().enumerate() {
-            if i == emptyspace {
-                *s = Space::Empty;
-            } else {
-                *s = Space::Value((i + 1) as u8);
+        // every solvable board is equally likely, we just keep drawing until one lands in the difficulty's band
+        let band = difficulty.band(size);
+        loop {
+            let board = Self::shuffled(size, emptyspace, &mut rng);
+            if band.contains(&board.distance()) {
+                return board;
             }
         }
+    }
 
-        let multiplier = match size {
-            Size::Three => 9,
-            Size::Four => 16,
-            Size::Five => 25,
+    /// a uniformly random board that can still be solved back to the empty space ending up at `emptyspace`
+    fn shuffled(size: Size, emptyspace: usize, rng: &mut impl qg_shared::rand::Rng) -> Self {
+        let width = size.numeral();
+        let mut spaces = (0..width * width)
+            .map(|i| if i == emptyspace { Space::Empty } else { Space::Value((i + 1) as u8) })
+            .collect::<Vec<Space>>();
+        spaces.shuffle(rng);
+
+        // a board is only solvable when the parity of its permutation matches how far the empty space is from home.
+        // swapping two tiles flips the parity, and pairs every unsolvable board with exactly one solvable one
+        let home = |space: &Space| match space {
+            Space::Empty => emptyspace,
+            Space::Value(v) => *v as usize - 1,
         };
-        // we need to shuffle the board in a way where we know it's solvable. so depending on the selected difficulty, we'll swap pieces a certain number of times
-        let number_of_moves = match difficulty {
-            Difficulty::Easy => 2 * multiplier,
-            Difficulty::Medium => 4 * multiplier,
-            Difficulty::Hard => 8 * multiplier,
-        };
-        let mut lastmove = None;
-        for _ in 0..number_of_moves {
-            let thismove = loop {
-                let thismove = match rng.gen_range(0..4) {
-                    0 => Direction::Up,
-                    1 => Direction::Down,
-                    2 => Direction::Left,
-                    3 => Direction::Right,
-                    _ => unreachable!(),
-                };
-                match (thismove, lastmove) {
-                    (Direction::Up, Some(Direction::Down)) => continue,
-                    (Direction::Down, Some(Direction::Up)) => continue,
-                    (Direction::Left, Some(Direction::Right)) => continue,
-                    (Direction::Right, Some(Direction::Left)) => continue,
-                    _ => {
-                        lastmove = Some(thismove);
-                        break thismove;
-                    }
-                }
-            };
+        let mut seen = vec![false; spaces.len()];
+        let mut swaps = 0;
+        for start in 0..spaces.len() {
+            // a cycle of n tiles takes n - 1 swaps to put right
+            let mut i = start;
+            let mut length: usize = 0;
+            while !seen[i] {
+                seen[i] = true;
+                i = home(&spaces[i]);
+                length += 1;
+            }
+            swaps += length.saturating_sub(1);
+        }
+        let empty = spaces.iter().position(|s| *s == Space::Empty).unwrap_or(emptyspace);
+        let distance = (empty % width).abs_diff(emptyspace % width) + (empty / width).abs_diff(emptyspace / width);
+        if swaps % 2 != distance % 2 {
+            let tiles = (0..spaces.len()).filter(|i| spaces[*i] != Space::Empty).take(2).collect::<Vec<usize>>();
+            spaces.swap(tiles[0], tiles[1]);
+        }
+
+        Self { spaces, size }
+    }
 
-            board.move_empty_tile_raw(thismove);
+    /// how many moves the board is from solved, exact for 3x3 and a lower bound for the rest
+    fn distance(&self) -> u32 {
+        match self.size {
+            Size::Three => solver::solve(self).map(|solution| solution.len() as u32),
+            _ => None,
         }
+        .or_else(|| solver::estimate(self))
+        .unwrap_or(0)
+    }
 
-        board
+    /// moves the empty space one step, unlike `move_empty_tile_raw` this refuses to go off the edge instead of picking another direction
+    fn move_empty_tile(&mut self, direction: Direction) -> Result<()> {
+        if !self.can_move_empty_tile(direction) {
+            return Err(anyhow!("Invalid move, there's no tile to slide that way"));
+        }
+        self.move_empty_tile_raw(direction);
+        Ok(())
+    }
+
+    fn can_move_empty_tile(&self, direction: Direction) -> bool {
+        let size = self.size.numeral();
+        let empty_tile = self.spaces.iter().enumerate().find_map(|(i, s)| if *s == Space::Empty { Some(i) } else { None }).unwrap();
+        let (x, y) = (empty_tile % size, empty_tile / size);
+        match direction {
+            Direction::Up => y > 0,
+            Direction::Down => y < size - 1,
+            Direction::Left => x > 0,
+            Direction::Right => x < size - 1,
+        }
+    }
+
+    /// the board as a grid of numbers, tiles already in place are green
+    fn text(&self) -> String {
+        let size = self.size.numeral();
+        let mut text = String::new();
+        for (i, space) in self.spaces.iter().enumerate() {
+            match space {
+                Space::Empty => text.push_str("  ."),
+                Space::Value(v) if *v == (i + 1) as u8 => text.push_str(&format!("{:>3}", v).green().to_string()),
+                Space::Value(v) => text.push_str(&format!("{:>3}", v)),
+            }
+            if i % size == size - 1 {
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// the arrows for boards too big for a button per tile, the blanks either side of up are just there to centre it
+    fn dpad(&self) -> Vec<CreateActionRow> {
+        let arrow = |direction: Direction| {
+            CreateButton::new(Action::Slide(direction).to_custom_id("slidingpuzzle"))
+                .style(ButtonStyle::Primary)
+                .label(direction.arrow())
+                .disabled(!self.can_move_empty_tile(direction.opposite()))
+        };
+        let blank = |i: usize| {
+            CreateButton::new(Action::InvalidMove(i).to_custom_id("slidingpuzzle"))
+                .style(ButtonStyle::Secondary)
+                .label("⬛")
+                .disabled(true)
+        };
+        vec![
+            CreateActionRow::Buttons(vec![
+                CreateButton::new(Action::Pause.to_custom_id("slidingpuzzle")).style(ButtonStyle::Secondary).label("⏸️"),
+                arrow(Direction::Up),
+                blank(1),
+            ]),
+            CreateActionRow::Buttons(vec![arrow(Direction::Left), arrow(Direction::Down), arrow(Direction::Right)]),
+        ]
     }
 
     fn move_empty_tile_raw(&mut self, mut thismove: Direction) {
@@ -773,13 +1331,11 @@ impl Board {
         let direction = self.direction_towards_empty_tile(x as isize, y as isize);
         let empty_tile_index = self.spaces.iter().enumerate().find_map(|(i, s)| if *s == Space::Empty { Some(i) } else { None }).unwrap();
 
-        let over_nine = match self.size {
-            Size::Three => false,
-            Size::Four => true,
-            Size::Five => true,
-        };
+        let over_nine = !matches!(self.size, Size::Three);
 
         match (p, direction) {
+            // 5x5 has no row left for a pause button, so the empty space is one
+            (Space::Empty, _) if self.size == Size::Five => CreateButton::new(Action::Pause.to_custom_id("slidingpuzzle")).style(ButtonStyle::Secondary).label("⏸️"),
             // Empty space, disabled, Secondary style
             (Space::Empty, _) => {
                 let b = CreateButton::new(Action::InvalidMove(i).to_custom_id("slidingpuzzle"));
@@ -852,7 +1408,7 @@ impl Board {
         // we need s and f to be adjacent
         let (sx, sy) = (s % size, s / size);
         let (fx, fy) = (f % size, f / size);
-        if sx != fx && sy != fy {
+        if sx.abs_diff(fx) + sy.abs_diff(fy) != 1 {
             return Err(anyhow!("Invalid move, tiles are not adjacent"));
         }
         // we need s and f to be in the same row or column
@@ -864,6 +1420,11 @@ impl Board {
         Ok(())
     }
 
+    /// how many tiles are already where they belong
+    fn correct(&self) -> usize {
+        self.spaces.iter().enumerate().filter(|(i, s)| **s == Space::Value((i + 1) as u8)).count()
+    }
+
     fn check_winner(&self) -> bool {
         // check that every space except Empty is in the correct position
         for (i, s) in self.spaces.iter().enumerate() {
@@ -935,3 +1496,78 @@ pub struct Outcome {
 pub struct Player {
     id: UserId,
 }
+
+#[cfg(test)]
+mod tests {
+    use qg_shared::db::SlidingPuzzleMove;
+
+    use super::*;
+
+    /// a 3x3 two moves from solved: 8 slid right, then 5 slid down, so playing 5 then 8 solves it
+    fn scramble() -> Board {
+        let mut spaces: Vec<Space> = (1..=8).map(Space::Value).collect();
+        spaces.push(Space::Empty);
+        let mut board = Board { spaces, size: Size::Three };
+        board.swap_checked(7, 8).unwrap();
+        board.swap_checked(4, 7).unwrap();
+        board
+    }
+
+    fn game(moves: u64) -> Game {
+        Game {
+            player: Player { id: UserId::new(1) },
+            gamestate: State::AwaitingApproval(Awaiting { inviter: UserId::new(1) }),
+            start_time: None,
+            moves,
+            difficulty: Difficulty::Easy,
+            size: Size::Three,
+            picture: None,
+            elapsed: 0,
+            seed: 0,
+            race: None,
+            deferred: false,
+        }
+    }
+
+    /// `(number, tile, elapsed)` for each move
+    fn log(moves: &[(i32, i32, i32)]) -> Vec<SlidingPuzzleMove> {
+        moves.iter().map(|&(number, tile, elapsed)| SlidingPuzzleMove::new(1, number, tile, elapsed)).collect()
+    }
+
+    #[test]
+    fn verify_accepts_a_solving_replay() {
+        let solution = game(2).verify(scramble(), &log(&[(1, 5, 100), (2, 8, 250)]), 250).unwrap();
+        assert_eq!(solution, vec![5, 8]);
+    }
+
+    #[test]
+    fn verify_rejects_a_wrong_move_count() {
+        assert!(game(3).verify(scramble(), &log(&[(1, 5, 100), (2, 8, 250)]), 250).is_err());
+        assert!(game(1).verify(scramble(), &log(&[(1, 5, 100), (2, 8, 250)]), 250).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_duplicate_and_out_of_order_numbers() {
+        assert!(game(2).verify(scramble(), &log(&[(1, 5, 100), (1, 8, 250)]), 250).is_err());
+        assert!(game(2).verify(scramble(), &log(&[(2, 5, 100), (1, 8, 250)]), 250).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_time_going_backwards() {
+        assert!(game(2).verify(scramble(), &log(&[(1, 5, 300), (2, 8, 250)]), 250).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_moves_that_dont_solve() {
+        // 5 back up and down again is legal but leaves the board where it started
+        assert!(game(2).verify(scramble(), &log(&[(1, 5, 100), (2, 5, 250)]), 250).is_err());
+        // 8 isn't next to the empty space at the start
+        assert!(game(2).verify(scramble(), &log(&[(1, 8, 100), (2, 5, 250)]), 250).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_a_finish_time_that_isnt_the_last_move() {
+        assert!(game(2).verify(scramble(), &log(&[(1, 5, 100), (2, 8, 250)]), 400).is_err());
+        assert!(game(2).verify(scramble(), &log(&[(1, 5, 100), (2, 8, 250)]), 200).is_err());
+    }
+}
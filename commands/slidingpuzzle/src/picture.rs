@@ -0,0 +1,128 @@
+//! picture puzzles, the upload is cropped to a square and cut into tiles which are drawn wherever the board has them.
+//! decoding and drawing are slow enough to hold up the async workers, so callers run these on a blocking thread
+
+use std::io::Cursor;
+
+use image::{
+    imageops,
+    io::{Limits, Reader},
+    DynamicImage, ImageFormat, Rgba, RgbaImage,
+};
+use qg_shared::anyhow::{anyhow, Result};
+
+use super::{Board, Space};
+
+/// the solved picture, sent as a spoiler so it can be peeked at. every render is cut from this, so it stays on the message
+pub const SOURCE_NAME: &str = "SPOILER_puzzle.png";
+/// the scrambled picture, replaced on every move
+pub const BOARD_NAME: &str = "board.png";
+
+/// the biggest upload we'll try to decode
+pub const MAX_UPLOAD_BYTES: u32 = 8 * 1024 * 1024;
+/// a small file can still claim to be huge once decoded, so the size it says it is gets checked before any pixels are
+const MAX_UPLOAD_SIDE: u32 = 8192;
+const MAX_DECODED_BYTES: u64 = 128 * 1024 * 1024;
+
+/// how wide the picture is drawn, it's shrunk a little to fit a whole number of tiles
+const SIDE: u32 = 480;
+
+const EMPTY: Rgba<u8> = Rgba([32, 34, 37, 255]);
+const LABEL_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 170]);
+const LABEL_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// crops the middle square out of the upload and scales it to size, returned as a png
+pub fn prepare(bytes: &[u8]) -> Result<Vec<u8>> {
+    let picture = decode(bytes, MAX_UPLOAD_SIDE).map_err(|e| anyhow!("Couldn't read that image: {}", e))?;
+    let side = picture.width().min(picture.height());
+    if side < 32 {
+        return Err(anyhow!("That image is too small to cut into tiles"));
+    }
+    let square = picture.crop_imm((picture.width() - side) / 2, (picture.height() - side) / 2, side, side);
+    encode(&square.resize_exact(SIDE, SIDE, imageops::FilterType::Triangle).to_rgba8())
+}
+
+/// draws the board using tiles cut from the prepared source picture, optionally with each tile's number in the corner
+pub fn render(source: &[u8], board: &Board, numbers: bool) -> Result<Vec<u8>> {
+    let source = decode(source, SIDE)?.to_rgba8();
+    let width = board.size.numeral() as u32;
+    let tile = source.width().min(source.height()) / width;
+
+    let mut canvas = RgbaImage::from_pixel(tile * width, tile * width, EMPTY);
+    for (i, space) in board.spaces.iter().enumerate() {
+        let (x, y) = ((i as u32 % width) * tile, (i as u32 / width) * tile);
+        if let Space::Value(v) = space {
+            let home = *v as u32 - 1;
+            let piece = imageops::crop_imm(&source, (home % width) * tile, (home / width) * tile, tile, tile).to_image();
+            imageops::replace(&mut canvas, &piece, x as i64, y as i64);
+            if numbers {
+                label(&mut canvas, x, y, tile, *v);
+            }
+        }
+    }
+    encode(&canvas)
+}
+
+fn decode(bytes: &[u8], max_side: u32) -> Result<DynamicImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_side);
+    limits.max_image_height = Some(max_side);
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+    let mut reader = Reader::new(Cursor::new(bytes)).with_guessed_format()?;
+    reader.limits(limits);
+    Ok(reader.decode()?)
+}
+
+fn encode(picture: &RgbaImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(picture.clone()).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// the digits 0-9 as 3x5 pixel glyphs, one row per byte
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// writes the tile's number in its top left corner on a dark box, there's no font to hand so the digits are scaled up pixel glyphs
+fn label(canvas: &mut RgbaImage, x: u32, y: u32, tile: u32, value: u8) {
+    let scale = (tile / 24).max(1);
+    let digits = value.to_string().bytes().map(|d| (d - b'0') as usize).collect::<Vec<usize>>();
+    let (width, height) = ((digits.len() as u32 * 4 + 1) * scale, 7 * scale);
+
+    for py in y..y + height {
+        for px in x..x + width {
+            let under = canvas.get_pixel(px, py).0;
+            canvas.put_pixel(px, py, Rgba(blend(under, LABEL_BACKGROUND.0)));
+        }
+    }
+    for (n, digit) in digits.iter().enumerate() {
+        for (row, bits) in DIGITS[*digit].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                let (gx, gy) = (x + (1 + n as u32 * 4 + column) * scale, y + (1 + row as u32) * scale);
+                for py in gy..gy + scale {
+                    for px in gx..gx + scale {
+                        canvas.put_pixel(px, py, LABEL_TEXT);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn blend(under: [u8; 4], over: [u8; 4]) -> [u8; 4] {
+    let alpha = over[3] as u32;
+    let mix = |u: u8, o: u8| ((o as u32 * alpha + u as u32 * (255 - alpha)) / 255) as u8;
+    [mix(under[0], over[0]), mix(under[1], over[1]), mix(under[2], over[2]), 255]
+}
@@ -0,0 +1,341 @@
+//! head to head races. both players get the same scramble on a board only they can see,
+//! and the public challenge message becomes a scoreboard that follows along until someone solves it
+
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    colored::Colorize,
+    rand::Rng as _,
+    serenity::all::*,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Awaiting, Board, Difficulty, Game, Player, Size, State};
+
+/// which race a player's board belongs to, so each move can be reported to the scoreboard
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct RaceLink {
+    channel: ChannelId,
+    message: MessageId,
+    seat: usize, // the player's index in `Race::players`
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Race {
+    players: [UserId; 2], // the challenger, then the opponent
+    size: Size,
+    difficulty: Difficulty,
+    state: RaceState,
+    progress: [Progress; 2],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum RaceState {
+    Awaiting,
+    Racing,
+    Finished { winner: usize, time: u64, moves: u64 },
+    Cancelled(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+struct Progress {
+    joined: bool, // the player has opened their board, they only get one
+    correct: usize,
+    moves: u64,
+}
+
+pub enum Action {
+    Accept,
+    Decline,
+    Cancel,
+    Board,
+}
+
+impl Action {
+    pub fn from_custom_id(custom_id: &str) -> Option<Self> {
+        let mut split = custom_id.split(':').skip(1);
+        if split.next()? != "Race" {
+            return None;
+        }
+        match split.next()? {
+            "Accept" => Some(Self::Accept),
+            "Decline" => Some(Self::Decline),
+            "Cancel" => Some(Self::Cancel),
+            "Board" => Some(Self::Board),
+            _ => None,
+        }
+    }
+    fn to_custom_id(&self) -> String {
+        format!("slidingpuzzle:Race:{}", self.name())
+    }
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Accept => "Accept",
+            Self::Decline => "Decline",
+            Self::Cancel => "Cancel",
+            Self::Board => "Board",
+        }
+    }
+}
+
+/// `/slidingpuzzle race`, posts the challenge that becomes the scoreboard
+pub async fn challenge(ctx: &Context, interaction: &mut CommandInteraction, options: &[CommandDataOption]) -> Result<()> {
+    let mut opponent = None;
+    let mut size = Size::Three;
+    let mut difficulty = Difficulty::Easy;
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            ("opponent", CommandDataOptionValue::User(user)) => {
+                opponent = Some(*user);
+            }
+            ("size", CommandDataOptionValue::String(value)) => {
+                size = Size::from_name(value).ok_or(anyhow!("Unhandled value `{}`", value))?;
+            }
+            ("difficulty", CommandDataOptionValue::String(value)) => {
+                difficulty = Difficulty::from_name(value).ok_or(anyhow!("Unhandled value `{}`", value))?;
+            }
+            (v, _) => return Err(anyhow!("Unhandled option `{}`", v)),
+        }
+    }
+    let opponent = opponent.ok_or(anyhow!("No opponent specified"))?.to_user(&ctx.http).await?;
+    if opponent.bot {
+        return Err(anyhow!("You cannot race against a bot"));
+    }
+    if opponent.id == interaction.user.id && !qg_shared::allow_self_play() {
+        return Err(anyhow!("Racing yourself is not permitted"));
+    }
+
+    let race = Race {
+        players: [interaction.user.id, opponent.id],
+        size,
+        difficulty,
+        state: RaceState::Awaiting,
+        progress: Default::default(),
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(race.content()?).components(race.components())),
+        )
+        .await?;
+    Ok(())
+}
+
+/// a button on the scoreboard
+pub async fn do_action(ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+    let mut race = Race::from_message(&interaction.message)?;
+    let [challenger, opponent] = race.players;
+    match (&race.state, action) {
+        (RaceState::Awaiting, Action::Accept) => {
+            if interaction.user.id != opponent {
+                return Err(anyhow!("You are not the invitee"));
+            }
+            // both boards are drawn from the seed, it's only handed to each board when its player presses Start
+            let db = db.as_mut().ok_or(anyhow!("Races need a database connection"))?;
+            let seed = qg_shared::rand::thread_rng().gen();
+            qg_shared::db::SlidingPuzzleRaceSeed::create(interaction.message.id.get() as i64, seed, db).await?;
+            race.state = RaceState::Racing;
+        }
+        (RaceState::Awaiting, Action::Decline) => {
+            if interaction.user.id != opponent {
+                return Err(anyhow!("You are not the invitee"));
+            }
+            race.state = RaceState::Cancelled("Declined".into());
+        }
+        (RaceState::Awaiting, Action::Cancel) => {
+            if interaction.user.id != challenger {
+                return Err(anyhow!("Only {} can cancel this race", challenger.mention()));
+            }
+            race.state = RaceState::Cancelled("Cancelled by the challenger".into());
+        }
+        (RaceState::Racing, Action::Board) => {
+            // racing yourself fills the seats in order
+            let seat = (0..2)
+                .find(|seat| race.players[*seat] == interaction.user.id && !race.progress[*seat].joined)
+                .ok_or(match race.players.contains(&interaction.user.id) {
+                    true => anyhow!("You already have your board, it's the message only you can see"),
+                    false => anyhow!("You are not in this race"),
+                })?;
+            race.progress[seat].joined = true;
+
+            let game = Game {
+                player: Player { id: interaction.user.id },
+                gamestate: State::AwaitingApproval(Awaiting { inviter: interaction.user.id }),
+                start_time: None,
+                moves: 0,
+                difficulty: race.difficulty,
+                size: race.size,
+                picture: None,
+                elapsed: 0,
+                seed: 0, // see `seed`
+                race: Some(RaceLink {
+                    channel: interaction.channel_id,
+                    message: interaction.message.id,
+                    seat,
+                }),
+                deferred: false,
+            };
+            let mut content = game.title_card()?;
+            content.push_str(&format!(
+                "Your board for the race against {}, the clock starts when you press Start!\n```ansi\nSize: {}\nDifficulty: {}\n```",
+                race.players[1 - seat].mention(),
+                race.size.name_with_ansi(),
+                race.difficulty.name_with_ansi(),
+            ));
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::default()
+                            .ephemeral(true)
+                            .content(content)
+                            .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(super::Action::Start.to_custom_id("slidingpuzzle"))
+                                .style(ButtonStyle::Success)
+                                .label("Start")])]),
+                    ),
+                )
+                .await?;
+            // the board has been answered with, so the scoreboard is edited directly
+            interaction.message.edit(&ctx.http, EditMessage::new().content(race.content()?).components(race.components())).await?;
+            return Ok(());
+        }
+        _ => {
+            return Err(anyhow!("Invalid action"));
+        }
+    }
+
+    interaction.defer(&ctx.http).await?;
+    interaction
+        .edit_response(&ctx.http, EditInteractionResponse::default().content(race.content()?).components(race.components()))
+        .await?;
+    Ok(())
+}
+
+/// the scramble both players get, kept in the database until the player starts their board
+pub async fn seed(link: RaceLink, db: &mut qg_shared::OptTrans<'_>) -> Result<u64> {
+    let db = db.as_mut().ok_or(anyhow!("Races need a database connection"))?;
+    let race_seed = qg_shared::db::SlidingPuzzleRaceSeed::get(link.message.get() as i64, db)
+        .await?
+        .ok_or(anyhow!("This race's scramble is missing"))?;
+    Ok(race_seed.seed as u64)
+}
+
+/// updates the player's line on the scoreboard after a move.
+/// the scoreboard is read and written back without a lock, so two moves landing together can briefly show one player's
+/// old progress until their next move
+pub async fn report(ctx: &Context, link: RaceLink, board: &Board, moves: u64) -> Result<()> {
+    let mut message = link.channel.message(&ctx.http, link.message).await?;
+    let mut race = Race::from_message(&message)?;
+    if !matches!(race.state, RaceState::Racing) {
+        return Ok(());
+    }
+    race.progress[link.seat].correct = board.correct();
+    race.progress[link.seat].moves = moves;
+    message.edit(&ctx.http, EditMessage::new().content(race.content()?)).await?;
+    Ok(())
+}
+
+/// the player solved their board, the first solve to be recorded wins. returns whether it was this one
+pub async fn finish(ctx: &Context, interaction: &ComponentInteraction, link: RaceLink, board: &Board, time: u64, moves: u64, db: &mut qg_shared::OptTrans<'_>) -> Result<bool> {
+    let mut message = link.channel.message(&ctx.http, link.message).await?;
+    let mut race = Race::from_message(&message)?;
+    if !matches!(race.state, RaceState::Racing) {
+        return Ok(false);
+    }
+    // the scoreboard alone can't settle two solves landing together, the database can
+    if let Some(db) = db {
+        let seed = qg_shared::db::SlidingPuzzleRaceSeed::get(link.message.get() as i64, db)
+            .await?
+            .ok_or(anyhow!("This race's scramble is missing"))?
+            .seed;
+        let winner = qg_shared::db::User::get_or_create(ctx, &race.players[link.seat], db).await?;
+        let loser = qg_shared::db::User::get_or_create(ctx, &race.players[1 - link.seat], db).await?;
+        let claimed = qg_shared::db::SlidingPuzzleRace::record(
+            link.message.get() as i64,
+            &winner,
+            &loser,
+            race.size as i32,
+            race.difficulty as i32,
+            seed,
+            time as i32,
+            moves as i32,
+            interaction.guild_id.map(|g| g.get() as i64),
+            db,
+        )
+        .await?;
+        if !claimed {
+            return Ok(false);
+        }
+    }
+    race.progress[link.seat].correct = board.correct();
+    race.progress[link.seat].moves = moves;
+    race.state = RaceState::Finished { winner: link.seat, time, moves };
+    message.edit(&ctx.http, EditMessage::new().content(race.content()?).components(race.components())).await?;
+    Ok(true)
+}
+
+impl Race {
+    fn from_message(message: &Message) -> Result<Self> {
+        // get first line of message content, strip the ``` prefix and deserialize
+        let mut lines = message.content.lines();
+        let race = lines.next().ok_or(anyhow!("No race data found"))?;
+        let race = race.strip_prefix("```").ok_or(anyhow!("No race data found"))?;
+        qg_shared::deserialize::<Self>(race)
+    }
+
+    fn content(&self) -> Result<String> {
+        let mut content = format!("```{}\nSliding Puzzle Race\n```", qg_shared::serialize(&self)?.replace('\n', ""));
+        let [challenger, opponent] = self.players;
+        match self.state {
+            RaceState::Awaiting => {
+                content.push_str(&format!("{} has challenged {} to a sliding puzzle race!", challenger.mention(), opponent.mention()));
+                content.push_str(&format!("```ansi\nSize: {}\nDifficulty: {}\n```", self.size.name_with_ansi(), self.difficulty.name_with_ansi()));
+            }
+            RaceState::Racing => {
+                content.push_str("Open your board below, the first to solve it wins!\n");
+                content.push_str(&self.standings());
+            }
+            RaceState::Finished { winner, time, moves } => {
+                content.push_str(&format!(
+                    "{} won the race!```ansi\nTime: {}\nMoves: {}\n```",
+                    self.players[winner].mention(),
+                    qg_shared::format_duration_ms(time).blue(),
+                    moves.to_string().red(),
+                ));
+                content.push_str(&self.standings());
+            }
+            RaceState::Cancelled(ref reason) => {
+                content.push_str(&format!("The race between {} and {} was called off: {}", challenger.mention(), opponent.mention(), reason));
+            }
+        }
+        Ok(content)
+    }
+
+    /// a line per player, kept out of a code block so the mentions work
+    fn standings(&self) -> String {
+        let tiles = self.size.numeral() * self.size.numeral() - 1;
+        self.players
+            .iter()
+            .zip(self.progress.iter())
+            .map(|(player, progress)| match progress.joined {
+                true => format!("{}: {}/{} tiles in place, {} moves\n", player.mention(), progress.correct, tiles, progress.moves),
+                false => format!("{}: hasn't opened their board yet\n", player.mention()),
+            })
+            .collect()
+    }
+
+    fn components(&self) -> Vec<CreateActionRow> {
+        match self.state {
+            RaceState::Awaiting => vec![CreateActionRow::Buttons(vec![
+                CreateButton::new(Action::Accept.to_custom_id()).style(ButtonStyle::Success).label("Accept"),
+                CreateButton::new(Action::Decline.to_custom_id()).style(ButtonStyle::Danger).label("Decline"),
+                CreateButton::new(Action::Cancel.to_custom_id()).style(ButtonStyle::Secondary).label("Cancel"),
+            ])],
+            RaceState::Racing => vec![CreateActionRow::Buttons(vec![CreateButton::new(Action::Board.to_custom_id())
+                .style(ButtonStyle::Primary)
+                .label("Open my board")])],
+            RaceState::Finished { .. } | RaceState::Cancelled(_) => vec![],
+        }
+    }
+}
@@ -0,0 +1,478 @@
+//! finds the fewest moves that solve a board, for hints and to score a solve against the best possible one.
+//! IDA* guided by an additive pattern database on 4x4, and by manhattan distance plus linear conflicts on the other sizes.
+//! the search gives up after a time budget so it can't hold up an interaction, most 5x5 scrambles are too far out to finish.
+//! it's still far too slow to run on an async worker, callers run it with `spawn_blocking`
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use super::{Board, Space};
+
+/// the widest board the solver has room for
+const MAX_WIDTH: usize = 8;
+const MAX_TILES: usize = MAX_WIDTH * MAX_WIDTH;
+
+/// the optimal solution as the index of each tile to slide into the empty space, `None` if the search ran out of budget
+pub fn solve(board: &Board) -> Option<Vec<usize>> {
+    // past 5x5 even the easiest scrambles are far more than the search could finish, they only get estimates
+    if board.size.numeral() > 5 {
+        return None;
+    }
+    let mut search = Search::new(board)?;
+    search.run()
+}
+
+/// how long the search can take before giving up, the player is waiting on it
+fn budget(width: usize) -> Duration {
+    match width {
+        3 => Duration::MAX, // every 3x3 board is solved in a few milliseconds
+        4 => Duration::from_millis(2_000),
+        _ => Duration::from_millis(500),
+    }
+}
+
+enum Step {
+    Found,
+    Next(u32), // the smallest estimate that went over the bound
+    OutOfBudget,
+}
+
+struct Search {
+    tiles: [u8; MAX_TILES],         // 0 is the empty space, tile `v` belongs at index `v - 1`
+    positions: [u8; MAX_TILES + 1], // where each tile is, the reverse of `tiles`
+    empty: usize,
+    width: usize,
+    patterns: Option<Vec<Pattern>>, // only for 4x4 once `prepare` has built them, otherwise manhattan distance
+    path: Vec<usize>,
+    nodes: u64,
+    deadline: Option<Instant>,
+}
+
+impl Search {
+    fn new(board: &Board) -> Option<Self> {
+        let width = board.size.numeral();
+        if width * width > MAX_TILES {
+            return None;
+        }
+        let mut search = Self {
+            tiles: [0; MAX_TILES],
+            positions: [0; MAX_TILES + 1],
+            empty: 0,
+            width,
+            patterns: None,
+            path: vec![],
+            nodes: 0,
+            deadline: Instant::now().checked_add(budget(width)),
+        };
+        for (i, space) in board.spaces.iter().enumerate() {
+            match space {
+                Space::Empty => search.empty = i,
+                Space::Value(v) => {
+                    search.tiles[i] = *v;
+                    search.positions[*v as usize] = i as u8;
+                }
+            }
+        }
+        if width == 4 {
+            let tiles = search.tiles[..16].iter().copied().filter(|t| *t != 0).collect::<Vec<u8>>();
+            search.patterns = patterns(&tiles, false);
+        }
+        Some(search)
+    }
+
+    fn run(&mut self) -> Option<Vec<usize>> {
+        let mut bound = self.estimate();
+        loop {
+            match self.dfs(0, bound, usize::MAX) {
+                Step::Found => return Some(self.path.clone()),
+                Step::Next(next) => bound = next,
+                Step::OutOfBudget => return None,
+            }
+        }
+    }
+
+    fn dfs(&mut self, moves: u32, bound: u32, previous: usize) -> Step {
+        let estimate = self.estimate();
+        if estimate == 0 {
+            return Step::Found;
+        }
+        let total = moves + estimate;
+        if total > bound {
+            return Step::Next(total);
+        }
+        self.nodes += 1;
+        // checking the clock every node would cost more than the node does
+        if self.nodes & 0xFFF == 0 && self.deadline.map(|deadline| Instant::now() > deadline).unwrap_or(false) {
+            return Step::OutOfBudget;
+        }
+
+        let mut next = u32::MAX;
+        let empty = self.empty;
+        for tile in self.neighbours(empty) {
+            // sliding the tile straight back is never part of a shortest solution
+            if tile == usize::MAX || tile == previous {
+                continue;
+            }
+            self.slide(tile);
+            self.path.push(tile);
+            match self.dfs(moves + 1, bound, empty) {
+                Step::Found => return Step::Found,
+                Step::Next(total) => next = next.min(total),
+                Step::OutOfBudget => return Step::OutOfBudget,
+            }
+            self.path.pop();
+            self.slide(empty);
+        }
+        Step::Next(next)
+    }
+
+    /// the spaces next to `index`, `usize::MAX` past the edge of the board
+    fn neighbours(&self, index: usize) -> [usize; 4] {
+        let (x, y) = (index % self.width, index / self.width);
+        [
+            if y > 0 { index - self.width } else { usize::MAX },
+            if y + 1 < self.width { index + self.width } else { usize::MAX },
+            if x > 0 { index - 1 } else { usize::MAX },
+            if x + 1 < self.width { index + 1 } else { usize::MAX },
+        ]
+    }
+
+    /// moves the tile at `index` into the empty space
+    fn slide(&mut self, index: usize) {
+        let tile = self.tiles[index];
+        self.tiles[self.empty] = tile;
+        self.tiles[index] = 0;
+        self.positions[tile as usize] = self.empty as u8;
+        self.empty = index;
+    }
+
+    /// a lower bound on the moves left, never more than the real number so the first solution found is a shortest one
+    fn estimate(&self) -> u32 {
+        match self.patterns {
+            Some(ref patterns) => patterns.iter().map(|pattern| pattern.lookup(&self.positions)).sum(),
+            None => self.quick_estimate(),
+        }
+    }
+
+    fn quick_estimate(&self) -> u32 {
+        self.manhattan() + self.linear_conflicts()
+    }
+
+    fn manhattan(&self) -> u32 {
+        let mut distance = 0;
+        for (i, tile) in self.tiles[..self.width * self.width].iter().enumerate() {
+            if *tile == 0 {
+                continue;
+            }
+            let goal = *tile as usize - 1;
+            distance += (i % self.width).abs_diff(goal % self.width) + (i / self.width).abs_diff(goal / self.width);
+        }
+        distance as u32
+    }
+
+    /// two tiles in their goal row (or column) but in the wrong order have to step around each other, which costs two more moves
+    fn linear_conflicts(&self) -> u32 {
+        let mut extra = 0;
+        let mut line = [0; MAX_WIDTH];
+        for row in 0..self.width {
+            let mut len = 0;
+            for column in 0..self.width {
+                let tile = self.tiles[row * self.width + column];
+                if tile != 0 && (tile as usize - 1) / self.width == row {
+                    line[len] = (tile as usize - 1) % self.width;
+                    len += 1;
+                }
+            }
+            extra += conflicts(&line[..len]);
+        }
+        for column in 0..self.width {
+            let mut len = 0;
+            for row in 0..self.width {
+                let tile = self.tiles[row * self.width + column];
+                if tile != 0 && (tile as usize - 1) % self.width == column {
+                    line[len] = (tile as usize - 1) / self.width;
+                    len += 1;
+                }
+            }
+            extra += conflicts(&line[..len]);
+        }
+        extra
+    }
+}
+
+/// the extra moves to untangle one row or column, given where each tile in it belongs in the order they sit.
+/// every tile outside the longest run that's already in order has to leave the line and come back
+fn conflicts(line: &[usize]) -> u32 {
+    let mut longest = [1; MAX_WIDTH];
+    for i in 0..line.len() {
+        for j in 0..i {
+            if line[j] < line[i] {
+                longest[i] = longest[i].max(longest[j] + 1);
+            }
+        }
+    }
+    let in_order = longest[..line.len()].iter().max().copied().unwrap_or(0);
+    2 * (line.len() - in_order) as u32
+}
+
+/// the exact moves needed to get a handful of tiles home on a 4x4, ignoring every other tile.
+/// the groups don't share tiles so the moves they count can be added together
+struct Pattern {
+    tiles: Vec<u8>,
+    distances: Arc<Vec<u8>>, // indexed by the tiles' positions, 4 bits each
+}
+
+impl Pattern {
+    fn lookup(&self, positions: &[u8]) -> u32 {
+        let index = self.tiles.iter().fold(0, |index, tile| index << 4 | positions[*tile as usize] as usize);
+        self.distances[index] as u32
+    }
+}
+
+/// the distances for each group of tiles, keyed by the tiles in the group
+type Tables = Mutex<HashMap<Vec<u8>, Arc<Vec<u8>>>>;
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+/// builds the 4x4 tables on a background thread, a few seconds of work that would otherwise land on whoever plays first.
+/// searches that start before it's done use manhattan distance, which finds the same solutions more slowly
+pub fn prepare() {
+    std::thread::spawn(|| {
+        for home in 0..16 {
+            let tiles = (1..=16).filter(|tile| *tile != home + 1).collect::<Vec<u8>>();
+            patterns(&tiles, true);
+        }
+    });
+}
+
+/// splits the tiles into groups of five, `None` unless every group has been built or `build` is set to build the missing ones.
+/// where the empty space goes changes which tiles there are, so there's a set of groups for each empty space
+fn patterns(tiles: &[u8], build: bool) -> Option<Vec<Pattern>> {
+    let mut sorted = tiles.to_vec();
+    sorted.sort_unstable();
+    sorted
+        .chunks(5)
+        .map(|group| {
+            let tables = TABLES.get_or_init(Default::default);
+            let built = tables.lock().unwrap_or_else(|e| e.into_inner()).get(group).cloned();
+            let distances = match built {
+                Some(distances) => distances,
+                // built without holding the lock, so searches can keep checking for their tables meanwhile
+                None if build => {
+                    let distances = Arc::new(build_pattern(group));
+                    tables.lock().unwrap_or_else(|e| e.into_inner()).insert(group.to_vec(), distances.clone());
+                    distances
+                }
+                None => return None,
+            };
+            Some(Pattern { tiles: group.to_vec(), distances })
+        })
+        .collect()
+}
+
+/// a breadth first search out from the solved positions, moving any tile in the group into any space the group isn't using
+fn build_pattern(tiles: &[u8]) -> Vec<u8> {
+    let goal = tiles.iter().fold(0, |index, tile| index << 4 | (*tile as usize - 1));
+    let mut distances = vec![u8::MAX; 1 << (4 * tiles.len())];
+    distances[goal] = 0;
+
+    let mut frontier = vec![goal];
+    let mut distance = 0;
+    while !frontier.is_empty() {
+        distance += 1;
+        let mut next = vec![];
+        for index in frontier {
+            let taken = (0..tiles.len()).fold(0u16, |taken, i| taken | 1 << (index >> (4 * i) & 0xF));
+            for i in 0..tiles.len() {
+                let position = index >> (4 * i) & 0xF;
+                let (x, y) = (position % 4, position / 4);
+                for (valid, to) in [(x > 0, position.wrapping_sub(1)), (x < 3, position + 1), (y > 0, position.wrapping_sub(4)), (y < 3, position + 4)] {
+                    if !valid || taken & 1 << to != 0 {
+                        continue;
+                    }
+                    let moved = index & !(0xF << (4 * i)) | to << (4 * i);
+                    if distances[moved] == u8::MAX {
+                        distances[moved] = distance;
+                        next.push(moved);
+                    }
+                }
+            }
+        }
+        frontier = next;
+    }
+    distances
+}
+
+/// a quick lower bound on the moves the board needs, for when running the full search would take too long.
+/// it never uses the pattern tables, so the same board always gets the same estimate whether they're built yet or not
+pub fn estimate(board: &Board) -> Option<u32> {
+    Some(Search::new(board)?.quick_estimate())
+}
+
+#[cfg(test)]
+mod tests {
+    use qg_shared::rand::{rngs::StdRng, seq::SliceRandom as _, SeedableRng as _};
+
+    use super::*;
+    use crate::Size;
+
+    /// a board from its spaces row by row, 0 is the empty space
+    fn board(size: Size, spaces: &[u8]) -> Board {
+        Board {
+            spaces: spaces.iter().map(|v| if *v == 0 { Space::Empty } else { Space::Value(*v) }).collect(),
+            size,
+        }
+    }
+
+    /// the published 15 puzzle instances number the tiles from 0 with the empty space home in the top left,
+    /// which is tile `v + 1` here with the empty space's home at 0
+    fn korf(spaces: [u8; 16]) -> Board {
+        board(Size::Four, &spaces.map(|v| if v == 0 { 0 } else { v + 1 }))
+    }
+
+    /// solves without the time budget, and with the pattern tables for 4x4, so a slow test build still gets the answer
+    fn solve_fully(board: &Board) -> Vec<usize> {
+        if board.size == Size::Four {
+            let tiles = board.spaces.iter().filter_map(|space| match space {
+                Space::Value(v) => Some(*v),
+                Space::Empty => None,
+            });
+            patterns(&tiles.collect::<Vec<u8>>(), true).unwrap();
+        }
+        let mut search = Search::new(board).unwrap();
+        search.deadline = None;
+        search.run().unwrap()
+    }
+
+    /// plays the solution out, checking it's made of real moves that end on the solved board
+    fn assert_solves(board: &Board, solution: &[usize]) {
+        let mut board = board.clone();
+        for tile in solution {
+            let empty = board.spaces.iter().position(|space| *space == Space::Empty).unwrap();
+            board.swap_checked(*tile, empty).unwrap();
+        }
+        assert!(board.check_winner());
+    }
+
+    /// `moves` random slides away from solved, never straight back, so the board is at most that many moves out
+    fn walk(size: Size, home: usize, moves: usize, seed: u64) -> Board {
+        let width = size.numeral();
+        let mut board = board(size, &(0..width * width).map(|i| if i == home { 0 } else { i as u8 + 1 }).collect::<Vec<u8>>());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut previous = usize::MAX;
+        for _ in 0..moves {
+            let empty = board.spaces.iter().position(|space| *space == Space::Empty).unwrap();
+            let (x, y) = (empty % width, empty / width);
+            let mut options = vec![];
+            if x > 0 {
+                options.push(empty - 1);
+            }
+            if x + 1 < width {
+                options.push(empty + 1);
+            }
+            if y > 0 {
+                options.push(empty - width);
+            }
+            if y + 1 < width {
+                options.push(empty + width);
+            }
+            options.retain(|option| *option != previous);
+            let tile = *options.choose(&mut rng).unwrap();
+            board.spaces.swap(tile, empty);
+            previous = empty;
+        }
+        board
+    }
+
+    #[test]
+    fn solved_board_needs_no_moves() {
+        let board = board(Size::Three, &[1, 2, 3, 4, 5, 6, 7, 8, 0]);
+        assert_eq!(solve(&board), Some(vec![]));
+        assert_eq!(estimate(&board), Some(0));
+    }
+
+    #[test]
+    fn empty_space_can_belong_anywhere() {
+        let board = board(Size::Three, &[2, 0, 3, 1, 5, 6, 7, 8, 9]);
+        let solution = solve(&board).unwrap();
+        assert_eq!(solution.len(), 2);
+        assert_solves(&board, &solution);
+    }
+
+    #[test]
+    fn eight_puzzle_optimal_lengths() {
+        for (spaces, optimal) in [
+            ([1, 2, 3, 4, 5, 6, 7, 0, 8], 1),
+            ([1, 2, 3, 4, 0, 6, 7, 5, 8], 2),
+            ([0, 1, 3, 4, 2, 5, 7, 8, 6], 4),
+            // the two hardest 8 puzzle scrambles
+            ([8, 6, 7, 2, 5, 4, 3, 0, 1], 31),
+            ([6, 4, 7, 8, 5, 0, 3, 2, 1], 31),
+        ] {
+            let board = board(Size::Three, &spaces);
+            let solution = solve(&board).unwrap();
+            assert_eq!(solution.len(), optimal, "{:?}", spaces);
+            assert_solves(&board, &solution);
+        }
+    }
+
+    #[test]
+    fn fifteen_puzzle_optimal_lengths() {
+        // the first two of Korf's 100 random instances
+        for (spaces, optimal) in [
+            ([14, 13, 15, 7, 11, 12, 9, 5, 6, 0, 2, 1, 4, 8, 10, 3], 57),
+            ([13, 5, 4, 10, 9, 12, 8, 14, 2, 3, 7, 1, 0, 15, 11, 6], 55),
+        ] {
+            let board = korf(spaces);
+            let solution = solve_fully(&board);
+            assert_eq!(solution.len(), optimal, "{:?}", spaces);
+            assert_solves(&board, &solution);
+        }
+    }
+
+    #[test]
+    fn estimates_never_overshoot() {
+        for (size, home) in [(Size::Three, 8), (Size::Three, 0), (Size::Four, 15), (Size::Four, 5), (Size::Five, 24)] {
+            for seed in 0..20 {
+                let board = walk(size, home, 30, seed);
+                let solution = solve_fully(&board);
+                assert!(solution.len() <= 30);
+                assert_solves(&board, &solution);
+                assert!(estimate(&board).unwrap() as usize <= solution.len(), "{:?}", board);
+                // with the pattern tables for 4x4
+                let search = Search::new(&board).unwrap();
+                assert!(search.estimate() as usize <= solution.len(), "{:?}", board);
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_tables_count_exact_moves() {
+        let tiles = (1..=15).collect::<Vec<u8>>();
+        let patterns = patterns(&tiles, true).unwrap();
+        assert_eq!(patterns.len(), 3);
+        // solved, then tile 15 and tile 11 each a slide from home
+        let mut positions = [0; MAX_TILES + 1];
+        for (tile, position) in positions.iter_mut().enumerate().take(16).skip(1) {
+            *position = tile as u8 - 1;
+        }
+        assert_eq!(patterns.iter().map(|pattern| pattern.lookup(&positions)).sum::<u32>(), 0);
+        positions[15] = 15;
+        assert_eq!(patterns.iter().map(|pattern| pattern.lookup(&positions)).sum::<u32>(), 1);
+        positions[11] = 14;
+        assert_eq!(patterns.iter().map(|pattern| pattern.lookup(&positions)).sum::<u32>(), 2);
+    }
+
+    #[test]
+    fn conflicts_count_tiles_out_of_order() {
+        assert_eq!(conflicts(&[]), 0);
+        assert_eq!(conflicts(&[0, 1, 2]), 0);
+        assert_eq!(conflicts(&[1, 0]), 2);
+        assert_eq!(conflicts(&[0, 2, 1]), 2);
+        assert_eq!(conflicts(&[2, 1, 0]), 4);
+        assert_eq!(conflicts(&[3, 0, 1, 2]), 2);
+    }
+}
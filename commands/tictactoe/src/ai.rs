@@ -0,0 +1,268 @@
+//! the bot opponent. the classic board is tiny, so perfect play there is just a full minimax search.
+//! bigger boards have far too many games to search to the end, so there it looks a few moves ahead instead
+
+use qg_shared::{
+    rand::{seq::SliceRandom, Rng},
+    serenity::all::UserId,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Board, Space};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Bot {
+    pub id: UserId,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Random,
+    Easy,
+    Medium,
+    #[default]
+    Perfect,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Easy => "easy",
+            Self::Medium => "medium",
+            Self::Perfect => "perfect",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(Self::Random),
+            "easy" => Some(Self::Easy),
+            "medium" => Some(Self::Medium),
+            "perfect" => Some(Self::Perfect),
+            _ => None,
+        }
+    }
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Random => "Random",
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Perfect => "Perfect",
+        }
+    }
+    /// how often the bot plays the best move instead of any old move
+    fn accuracy(&self) -> f64 {
+        match self {
+            Self::Random => 0.0,
+            Self::Easy => 0.4,
+            Self::Medium => 0.8,
+            Self::Perfect => 1.0,
+        }
+    }
+}
+
+/// how many moves ahead the bot looks on boards bigger than the classic one. that's enough to take a win, block one and see a fork coming,
+/// while a 5x5 board still only takes a moment
+const SEARCH_DEPTH: i32 = 4;
+
+/// a win scores this, less how many moves it took to get there
+const WIN: i32 = 100;
+
+/// picks the bot's move for `piece`, `None` if the board is full
+pub fn choose_move(board: &Board, piece: Space, win_length: usize, difficulty: Difficulty) -> Option<(usize, usize)> {
+    let mut rng = qg_shared::rand::thread_rng();
+
+    if !rng.gen_bool(difficulty.accuracy()) {
+        return empty_spaces(&board.spaces).choose(&mut rng).copied();
+    }
+
+    best_moves(board, piece, win_length).choose(&mut rng).copied()
+}
+
+/// every move that scores best for `piece`. each one is searched with the full window so equally good moves score the same
+fn best_moves(board: &Board, piece: Space, win_length: usize) -> Vec<(usize, usize)> {
+    let mut spaces = board.spaces.clone();
+    let max_depth = if spaces.len() == 3 { i32::MAX } else { SEARCH_DEPTH };
+
+    let mut best = i32::MIN;
+    let mut best_moves = vec![];
+    for (x, y) in empty_spaces(&spaces) {
+        spaces[x][y] = piece;
+        let score = if has_line_through(&spaces, x, y, win_length) {
+            WIN - 1
+        } else {
+            -negamax(&mut spaces, opponent_of(piece), win_length, 1, max_depth, -i32::MAX, i32::MAX)
+        };
+        spaces[x][y] = Space::Empty;
+
+        if score > best {
+            best = score;
+            best_moves.clear();
+        }
+        if score == best {
+            best_moves.push((x, y));
+        }
+    }
+    best_moves
+}
+
+/// the score of the position for whoever is `to_move`, winning sooner and losing later scores better.
+/// anything past `max_depth` is scored as a draw
+fn negamax(spaces: &mut [Vec<Space>], to_move: Space, win_length: usize, depth: i32, max_depth: i32, mut alpha: i32, beta: i32) -> i32 {
+    let moves = empty_spaces(spaces);
+    if moves.is_empty() || depth >= max_depth {
+        return 0;
+    }
+
+    let mut best = -i32::MAX;
+    for (x, y) in moves {
+        spaces[x][y] = to_move;
+        // a line can only go through the space that was just taken
+        let score = if has_line_through(spaces, x, y, win_length) {
+            WIN - depth - 1
+        } else {
+            -negamax(spaces, opponent_of(to_move), win_length, depth + 1, max_depth, -beta, -alpha)
+        };
+        spaces[x][y] = Space::Empty;
+
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+/// whether the piece at `x`, `y` is part of `win_length` in a row
+fn has_line_through(spaces: &[Vec<Space>], x: usize, y: usize, win_length: usize) -> bool {
+    let piece = spaces[x][y];
+    let size = spaces.len() as isize;
+    let count = |dx: isize, dy: isize| {
+        (1..win_length as isize)
+            .take_while(|i| {
+                let (x, y) = (x as isize + dx * i, y as isize + dy * i);
+                (0..size).contains(&x) && (0..size).contains(&y) && spaces[x as usize][y as usize] == piece
+            })
+            .count()
+    };
+    [(0, 1), (1, 0), (1, 1), (1, -1)].iter().any(|(dx, dy)| 1 + count(*dx, *dy) + count(-dx, -dy) >= win_length)
+}
+
+fn empty_spaces(spaces: &[Vec<Space>]) -> Vec<(usize, usize)> {
+    let mut moves = vec![];
+    for (x, row) in spaces.iter().enumerate() {
+        for (y, space) in row.iter().enumerate() {
+            if *space == Space::Empty {
+                moves.push((x, y));
+            }
+        }
+    }
+    moves
+}
+
+fn opponent_of(piece: Space) -> Space {
+    match piece {
+        Space::X => Space::O,
+        Space::O => Space::X,
+        Space::Empty => Space::Empty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// a board from its rows, `X`, `O` and `.` for empty
+    fn board(rows: &[&str]) -> Board {
+        Board {
+            spaces: rows
+                .iter()
+                .map(|row| {
+                    row.chars()
+                        .map(|c| match c {
+                            'X' => Space::X,
+                            'O' => Space::O,
+                            _ => Space::Empty,
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// the spaces `piece` could win on with its next move
+    fn winning_spaces(board: &Board, piece: Space, win_length: usize) -> Vec<(usize, usize)> {
+        let mut spaces = board.spaces.clone();
+        empty_spaces(&board.spaces)
+            .into_iter()
+            .filter(|(x, y)| {
+                spaces[*x][*y] = piece;
+                let wins = has_line_through(&spaces, *x, *y, win_length);
+                spaces[*x][*y] = Space::Empty;
+                wins
+            })
+            .collect()
+    }
+
+    /// plays every reply the opponent has against every move the bot would pick, failing if the opponent ever gets a line
+    fn never_loses(board: &mut Board, to_move: Space, bot: Space, seen: &mut HashSet<String>) {
+        let key = format!("{:?}{:?}", board.spaces, to_move);
+        if !seen.insert(key) {
+            return;
+        }
+        let moves = if to_move == bot { best_moves(board, bot, 3) } else { empty_spaces(&board.spaces) };
+        for (x, y) in moves {
+            board.spaces[x][y] = to_move;
+            if has_line_through(&board.spaces, x, y, 3) {
+                assert_eq!(to_move, bot, "the bot lost on {:?}", board.spaces);
+            } else {
+                never_loses(board, opponent_of(to_move), bot, seen);
+            }
+            board.spaces[x][y] = Space::Empty;
+        }
+    }
+
+    #[test]
+    fn perfect_never_loses_the_classic_board() {
+        let mut seen = HashSet::new();
+        never_loses(&mut board(&["...", "...", "..."]), Space::X, Space::X, &mut seen);
+        never_loses(&mut board(&["...", "...", "..."]), Space::X, Space::O, &mut seen);
+    }
+
+    #[test]
+    fn takes_a_win_before_blocking() {
+        assert_eq!(best_moves(&board(&["XX.", "OO.", "..."]), Space::X, 3), vec![(0, 2)]);
+        assert_eq!(best_moves(&board(&["XXX.", "OOO.", "....", "...."]), Space::X, 4), vec![(0, 3)]);
+        assert_eq!(best_moves(&board(&[".....", ".XXX.", ".OOO.", ".....", "....."]), Space::O, 4), vec![(2, 0), (2, 4)]);
+    }
+
+    #[test]
+    fn blocks_on_bigger_boards() {
+        assert_eq!(best_moves(&board(&["OO.O", "X...", "..X.", "...."]), Space::X, 4), vec![(0, 2)]);
+        assert_eq!(best_moves(&board(&["X....", ".O...", "..O..", ".....", "....X"]), Space::X, 3), vec![(3, 3)]);
+        assert_eq!(best_moves(&board(&[".....", "..O..", "..O..", "..O..", "X.X.."]), Space::X, 4), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn finds_forced_wins_on_bigger_boards() {
+        for (rows, win_length) in [(&[".X.O", "...X", "...O", "...."][..], 3), (&[".XX..", "O...O", ".O...", ".....", "...X."][..], 4)] {
+            let board = board(rows);
+            assert!(winning_spaces(&board, Space::X, win_length).is_empty());
+            for (x, y) in best_moves(&board, Space::X, win_length) {
+                let mut after = board.clone();
+                after.spaces[x][y] = Space::X;
+                // whatever the other player does, there's still a line to finish
+                for (ox, oy) in empty_spaces(&after.spaces) {
+                    after.spaces[ox][oy] = Space::O;
+                    assert!(!has_line_through(&after.spaces, ox, oy, win_length));
+                    assert!(!winning_spaces(&after, Space::X, win_length).is_empty(), "{:?} doesn't force a win on {:?}", (x, y), rows);
+                    after.spaces[ox][oy] = Space::Empty;
+                }
+            }
+        }
+    }
+}
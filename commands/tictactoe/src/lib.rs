@@ -8,6 +8,11 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+mod ai;
+
+/// how long an open challenge waits for someone to accept, in seconds
+const OPEN_CHALLENGE_TIMEOUT: u64 = 60 * 10;
+
 pub fn command() -> TicTacToe {
     TicTacToe
 }
@@ -29,13 +34,73 @@ impl qg_shared::Command for TicTacToe {
         qg_shared::CommandInfo {
             name: String::from("tictactoe"),
             description: String::from("Play a game of Tic Tac Toe"),
-            options: vec![qg_shared::CommandOption {
-                name: String::from("opponent"),
-                description: String::from("The opponent to play against"),
-                option_type: qg_shared::CommandOptionType::User,
-                choices: qg_shared::UnorderedVec::from(vec![]),
-                required: true,
-            }]
+            options: vec![
+                qg_shared::CommandOption {
+                    name: String::from("opponent"),
+                    description: String::from("The opponent to play against (leave empty for an open challenge anyone can accept)"),
+                    option_type: qg_shared::CommandOptionType::User,
+                    choices: qg_shared::UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("best_of"),
+                    description: String::from("Play a series instead of a single game, whoever wins most of the games takes it"),
+                    option_type: qg_shared::CommandOptionType::Integer,
+                    choices: qg_shared::UnorderedVec::from(
+                        [1, 3, 5, 7]
+                            .iter()
+                            .map(|n| qg_shared::CommandOptionChoice {
+                                name: format!("Best of {}", n),
+                                value: n.to_string(),
+                            })
+                            .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                    ),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("size"),
+                    description: String::from("How many spaces across the board is (defaults to 3)"),
+                    option_type: qg_shared::CommandOptionType::Integer,
+                    choices: qg_shared::UnorderedVec::from(
+                        qg_shared::db::TicTacToeVariant::SIZES
+                            .map(|n| qg_shared::CommandOptionChoice {
+                                name: format!("{}x{}", n, n),
+                                value: n.to_string(),
+                            })
+                            .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                    ),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("win_length"),
+                    description: String::from("How many in a row it takes to win, at most the size of the board (defaults to 3)"),
+                    option_type: qg_shared::CommandOptionType::Integer,
+                    choices: qg_shared::UnorderedVec::from(
+                        qg_shared::db::TicTacToeVariant::SIZES
+                            .map(|n| qg_shared::CommandOptionChoice {
+                                name: format!("{} in a row", n),
+                                value: n.to_string(),
+                            })
+                            .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                    ),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("difficulty"),
+                    description: String::from("Play against the bot instead, you can also pick the bot as your opponent (defaults to perfect)"),
+                    option_type: qg_shared::CommandOptionType::String,
+                    choices: qg_shared::UnorderedVec::from(
+                        [ai::Difficulty::Random, ai::Difficulty::Easy, ai::Difficulty::Medium, ai::Difficulty::Perfect]
+                            .iter()
+                            .map(|difficulty| qg_shared::CommandOptionChoice {
+                                name: difficulty.display_name().to_string(),
+                                value: difficulty.name().to_string(),
+                            })
+                            .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                    ),
+                    required: false,
+                },
+            ]
             .into(),
         }
     }
@@ -45,24 +110,90 @@ impl qg_shared::Command for TicTacToe {
             id: interaction.user.id,
             piece: Space::X,
         }];
-        let other;
-        players.push({
-            match interaction.data.options.first().ok_or(qg_shared::anyhow::anyhow!("No opponent specified"))?.value {
+        let mut difficulty = None;
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "difficulty") {
+            match option.value {
+                CommandDataOptionValue::String(ref value) => {
+                    difficulty = Some(ai::Difficulty::from_name(value).ok_or(qg_shared::anyhow::anyhow!("Unhandled value `{}`", value))?);
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("Invalid difficulty"));
+                }
+            }
+        }
+        let bot_id = ctx.cache.current_user().id;
+        // without an opponent anyone can claim the second seat
+        let mut invitee = None;
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "opponent") {
+            match option.value {
                 CommandDataOptionValue::User(user) => {
                     let user = user.to_user(&ctx.http).await?;
-                    if user.bot {
-                        return Err(qg_shared::anyhow::anyhow!("You cannot play against a bot"));
-                    }
+                    if user.id == bot_id {
+                        // challenging us is the same as asking for the bot
+                        difficulty = Some(difficulty.unwrap_or_default());
+                    } else {
+                        if difficulty.is_some() {
+                            return Err(qg_shared::anyhow::anyhow!("Difficulty is only for games against the bot, leave out the opponent"));
+                        }
+                        if user.bot {
+                            return Err(qg_shared::anyhow::anyhow!("You cannot play against a bot"));
+                        }
 
-                    other = user.clone();
-                    Player { id: other.id, piece: Space::O }
+                        invitee = Some(user.id);
+                        players.push(Player { id: user.id, piece: Space::O });
+                    }
                 }
                 _ => {
                     return Err(qg_shared::anyhow::anyhow!("Invalid opponent"));
                 }
             }
-        });
-        if !std::env::var("ALLOW_SELF_PLAY").ok().and_then(|s| s.parse::<bool>().ok()).unwrap_or(false) {
+        }
+        let mut series = qg_shared::Series::default();
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "best_of") {
+            match option.value {
+                CommandDataOptionValue::Integer(best_of) if (1..=7).contains(&best_of) && best_of % 2 == 1 => {
+                    series = qg_shared::Series::new(best_of as u8);
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("A series has to be an odd number of games, up to 7"));
+                }
+            }
+        }
+        let mut size = 3;
+        let mut win_length = 3;
+        for option in interaction.data.options.iter() {
+            match (option.name.as_str(), &option.value) {
+                ("size", CommandDataOptionValue::Integer(value)) => size = *value as usize,
+                ("win_length", CommandDataOptionValue::Integer(value)) => win_length = *value as usize,
+                ("size" | "win_length", _) => return Err(qg_shared::anyhow::anyhow!("Invalid {}", option.name)),
+                _ => {}
+            }
+        }
+        let variant = qg_shared::db::TicTacToeVariant::new(size, win_length)?;
+        if let Some(difficulty) = difficulty {
+            let bot = ai::Bot { id: bot_id, difficulty };
+            let mut game = Game {
+                players: CycleVec::new(vec![
+                    Player {
+                        id: interaction.user.id,
+                        piece: Space::X,
+                    },
+                    Player { id: bot.id, piece: Space::O },
+                ]),
+                gamestate: State::InProgress(InProgress::new(variant)),
+                last_time: qg_shared::current_time()?,
+                tournament_match: None,
+                series,
+                thread: None,
+                dm: None,
+                ai: Some(bot),
+                variant,
+            };
+            game.play_bot()?;
+            game.send(ctx, interaction).await?;
+            return Ok(());
+        }
+        if !qg_shared::allow_self_play() {
             let individuals = {
                 let mut individuals = players.iter().map(|player| player.id).collect::<Vec<UserId>>();
                 individuals.sort();
@@ -79,9 +210,19 @@ impl qg_shared::Command for TicTacToe {
             players: CycleVec::new(players),
             gamestate: State::AwaitingApproval(Awaiting {
                 inviter: interaction.user.id,
-                invitee: other.id,
+                invitee,
+                expires_at: match invitee {
+                    Some(_) => 0,
+                    None => qg_shared::current_time()? + OPEN_CHALLENGE_TIMEOUT,
+                },
             }),
             last_time: qg_shared::current_time()?,
+            tournament_match: None,
+            series,
+            thread: None,
+            dm: None,
+            ai: None,
+            variant,
         };
 
         game.send(ctx, interaction).await?;
@@ -89,7 +230,12 @@ impl qg_shared::Command for TicTacToe {
     }
 
     async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
-        let action = match Action::from_custom_id(&interaction.data.custom_id) {
+        // the move menu on boards too big for buttons has the space as its value
+        let custom_id = match interaction.data.kind {
+            ComponentInteractionDataKind::StringSelect { ref values } => format!("{}:{}", interaction.data.custom_id, values.first().ok_or(anyhow!("No space picked"))?),
+            _ => interaction.data.custom_id.clone(),
+        };
+        let action = match Action::from_custom_id(&custom_id) {
             Some(action) => action,
             None => return Err(qg_shared::anyhow::anyhow!("Invalid action id")),
         };
@@ -108,9 +254,74 @@ impl qg_shared::Command for TicTacToe {
     }
 }
 
+/// starts a game between two players in its own message, skipping the challenge since they've already been paired up (queues, tournaments)
+pub async fn open_game(ctx: &Context, channel_id: ChannelId, players: (UserId, UserId), tournament_match: Option<i64>) -> Result<Message> {
+    let game = Game {
+        players: CycleVec::new(vec![Player { id: players.0, piece: Space::X }, Player { id: players.1, piece: Space::O }]),
+        gamestate: State::InProgress(InProgress::new(qg_shared::db::TicTacToeVariant::default())),
+        last_time: qg_shared::current_time()?,
+        tournament_match,
+        series: qg_shared::Series::default(),
+        thread: None,
+        dm: None,
+        ai: None,
+        variant: qg_shared::db::TicTacToeVariant::default(),
+    };
+
+    game.post(ctx, channel_id).await
+}
+
+/// starts a game between two players who might not share a server, with a copy in each of their dms
+pub async fn open_dm_game(ctx: &Context, players: (UserId, UserId)) -> Result<qg_shared::DmGame> {
+    let game = Game {
+        players: CycleVec::new(vec![Player { id: players.0, piece: Space::X }, Player { id: players.1, piece: Space::O }]),
+        gamestate: State::InProgress(InProgress::new(qg_shared::db::TicTacToeVariant::default())),
+        last_time: qg_shared::current_time()?,
+        tournament_match: None,
+        series: qg_shared::Series::default(),
+        thread: None,
+        dm: None,
+        ai: None,
+        variant: qg_shared::db::TicTacToeVariant::default(),
+    };
+
+    game.post_dm(ctx).await
+}
+
+/// opens a tournament match in its own message
+pub async fn open_match(
+    ctx: &Context,
+    channel_id: ChannelId,
+    tournament_match: &mut qg_shared::db::TournamentMatch,
+    tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>,
+) -> Result<()> {
+    let players = tournament_match.discord_ids(tx).await?;
+    let message = open_game(ctx, channel_id, players, Some(tournament_match.id)).await?;
+    tournament_match.set_message(message.id, tx).await?;
+
+    Ok(())
+}
+
+/// records the result of a tournament match and announces the champion. whatever is ready to be played next is opened once the result has been saved
+async fn advance_tournament(ctx: &Context, tournament_match: i64, winner: Option<i64>, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<()> {
+    let advancement = qg_shared::db::Tournament::record_result(tournament_match, winner, tx).await?;
+
+    advancement.announce_champion(ctx).await;
+    qg_shared::db::TournamentMatch::open_later(advancement.opened);
+
+    Ok(())
+}
+
 pub enum Action {
     Accept,
     Decline,
+    Cancel,
+    Rematch,
+    Resign,
+    OfferDraw,
+    Takeback,
+    AcceptOffer,
+    DeclineOffer,
     Place(usize, usize),
 }
 
@@ -131,6 +342,48 @@ impl Action {
                 }
                 Some(Self::Decline)
             }
+            "Cancel" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Cancel)
+            }
+            "Rematch" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Rematch)
+            }
+            "Resign" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Resign)
+            }
+            "OfferDraw" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::OfferDraw)
+            }
+            "Takeback" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Takeback)
+            }
+            "AcceptOffer" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::AcceptOffer)
+            }
+            "DeclineOffer" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::DeclineOffer)
+            }
             "Place" => {
                 let x = split.next()?.parse().ok()?;
                 let y = split.next()?.parse().ok()?;
@@ -142,6 +395,10 @@ impl Action {
             _ => None,
         }
     }
+    /// the buttons under the board that aren't moves
+    fn is_control(&self) -> bool {
+        matches!(self, Self::Resign | Self::OfferDraw | Self::Takeback | Self::AcceptOffer | Self::DeclineOffer)
+    }
     pub fn to_custom_id(&self, command: &str) -> String {
         format!("{}:{}", command, self.name())
     }
@@ -149,6 +406,13 @@ impl Action {
         match self {
             Self::Accept => String::from("Accept"),
             Self::Decline => String::from("Decline"),
+            Self::Cancel => String::from("Cancel"),
+            Self::Rematch => String::from("Rematch"),
+            Self::Resign => String::from("Resign"),
+            Self::OfferDraw => String::from("OfferDraw"),
+            Self::Takeback => String::from("Takeback"),
+            Self::AcceptOffer => String::from("AcceptOffer"),
+            Self::DeclineOffer => String::from("DeclineOffer"),
             Self::Place(x, y) => format!("Place:{}:{}", x, y),
         }
     }
@@ -159,22 +423,54 @@ pub struct Game {
     players: CycleVec<Player>,
     gamestate: State,
     last_time: u64,
+    #[serde(default)]
+    tournament_match: Option<i64>, // the tournament match this game decides, see qg_shared::db::Tournament
+    #[serde(default)]
+    series: qg_shared::Series,
+    #[serde(default)]
+    thread: Option<qg_shared::GameThread>, // set when the game is played in its own thread
+    #[serde(default)]
+    dm: Option<qg_shared::DmGame>, // set when the game is played over dms, each player has their own copy
+    #[serde(default)]
+    ai: Option<ai::Bot>, // set when one of the players is the bot
+    #[serde(default)]
+    variant: qg_shared::db::TicTacToeVariant,
 }
 
 impl Game {
     pub async fn do_action(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut OptTrans<'_>) -> Result<()> {
         match self.gamestate {
             State::AwaitingApproval(ref u) => {
-                if interaction.user.id != u.invitee {
-                    return Err(anyhow!("You are not the invitee"));
+                if u.expired()? {
+                    self.gamestate = State::Cancelled("Nobody accepted in time".into());
+                    return self.render(ctx, interaction).await;
                 }
+                let (inviter, invitee) = (u.inviter, u.invitee);
                 match action {
                     Action::Accept => {
-                        self.gamestate = State::InProgress(InProgress {
-                            board: Board {
-                                spaces: vec![vec![Space::Empty; 3]; 3],
-                            },
-                        });
+                        match invitee {
+                            Some(invitee) => {
+                                if interaction.user.id != invitee {
+                                    return Err(anyhow!("You are not the invitee"));
+                                }
+                            }
+                            None => {
+                                if interaction.user.id == inviter && !qg_shared::allow_self_play() {
+                                    return Err(anyhow!("You can't accept your own challenge"));
+                                }
+                                // first come first served, then shuffle so the challenger doesn't always go first
+                                self.players.push(Player {
+                                    id: interaction.user.id,
+                                    piece: Space::O,
+                                });
+                                self.players.shuffle();
+                            }
+                        }
+                        self.gamestate = State::InProgress(InProgress::new(self.variant));
+                        // the server wants games in their own threads, the challenge message just points there now
+                        if self.move_to_thread(ctx, interaction, db).await? {
+                            return Ok(());
+                        }
                         // interaction
                         //     .create_response(&ctx.http, |f| {
                         //         f.kind(qg_shared::serenity::model::application::interaction::InteractionResponseType::DeferredUpdateMessage)
@@ -189,13 +485,16 @@ impl Game {
                                     .await?
                                     .create_dm_channel(&ctx.http)
                                     .await?
-                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", interaction.message.link())))
+                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", self.link(interaction, pid))))
                                     .await?;
                             }
                             self.last_time = now;
                         }
                     }
                     Action::Decline => {
+                        if Some(interaction.user.id) != invitee {
+                            return Err(anyhow!("You are not the invitee"));
+                        }
                         self.gamestate = State::Cancelled("Declined".into());
                         // interaction
                         //     .create_response(&ctx.http, |f| {
@@ -203,12 +502,21 @@ impl Game {
                         //     })
                         //     .await?;
                     }
+                    Action::Cancel => {
+                        if interaction.user.id != inviter {
+                            return Err(anyhow!("Only {} can cancel this challenge", inviter.mention()));
+                        }
+                        self.gamestate = State::Cancelled("Cancelled by the challenger".into());
+                    }
                     _ => {
                         return Err(anyhow!("Invalid action"));
                     }
                 }
             }
             State::InProgress(ref mut game) => {
+                if action.is_control() {
+                    return self.control(ctx, interaction, action, db).await;
+                }
                 if self.players.current().map(|s| s.id) != Some(interaction.user.id) {
                     return Err(anyhow!("It is not your turn"));
                 }
@@ -217,70 +525,31 @@ impl Game {
                         if let Err(e) = game.make_move(x, y, self.players.current().ok_or(anyhow!("Player not found"))?.piece) {
                             return Err(anyhow!("Invalid move: {}", e));
                         }
-                        if let Some(winner) = game.board.check_winner(&self.players) {
-                            self.gamestate = State::Finished(WonGame {
-                                winner: winner.clone(),
-                                board: game.board.clone(),
-                            });
-                            // render early so the messages and sql stuff doesnt lag the update
-                            self.render(ctx, interaction).await?;
-                            for player in self.players.all() {
-                                match ctx.http.get_user(player.id).await {
-                                    Ok(user) => match user.create_dm_channel(&ctx.http).await {
-                                        Ok(dm) => {
-                                            if let Err(e) = dm
-                                                .send_message(&ctx.http, {
-                                                    CreateMessage::default().content({
-                                                        if let Outcome::Win(p) = winner {
-                                                            format!("You {} in {}", if *player == p { "won" } else { "got your ass handed to you" }, interaction.message.link())
-                                                        } else {
-                                                            format!("You tied in {}", interaction.message.link())
-                                                        }
-                                                    })
-                                                })
-                                                .await
-                                            {
-                                                qg_shared::log::trace!("Failed to send message to user: {}", e);
-                                            }
-                                        }
-                                        Err(e) => {
-                                            qg_shared::log::trace!("Failed to create dm channel: {}", e);
-                                        }
-                                    },
-                                    Err(e) => {
-                                        qg_shared::log::trace!("Failed to get user: {}", e);
-                                    }
+                        if let Some(winner) = game.board.check_winner(&self.players, self.variant.win_length) {
+                            let board = game.board.clone();
+                            return self.finish(ctx, interaction, winner, board, None, db).await;
+                        } else {
+                            self.players.next_player();
+                            // the bot answers straight away, in the same interaction
+                            if self.ai.is_some() {
+                                if let Some((winner, board)) = self.play_bot()? {
+                                    return self.finish(ctx, interaction, winner, board, None, db).await;
                                 }
+                                return self.render(ctx, interaction).await;
                             }
-                            if let Some(db) = db {
-                                if let Some(winner) = winner.winner() {
-                                    let mut players: HashMap<UserId, qg_shared::db::User> = HashMap::new();
-
-                                    for player in self.players.all() {
-                                        players.insert(player.id, qg_shared::db::User::get_or_create(ctx, &player.id, db).await?);
-                                    }
-
-                                    for player in self.players.all() {
-                                        let user = &players[&player.id];
-                                        let opponent = &players[&self.players.all().find(|p| p.id != player.id).unwrap_or(player).id];
-                                        if user.id != opponent.id {
-                                            qg_shared::db::TicTacToe::create(user.id as i32, opponent.id as i32, player.id == winner.id, db).await?;
-                                        }
-                                    }
-                                }
+                            if let Some(ref mut thread) = self.thread {
+                                thread.ping(ctx, self.players.current().ok_or(anyhow!("Player not found"))?.id).await;
                             }
-                            return Ok(()); // we dont want to render again
-                        } else {
-                            self.players.next_player();
 
                             let now = qg_shared::current_time()?;
                             if now.saturating_sub(self.last_time) > 60 {
+                                let pid = self.players.current().ok_or(anyhow!("Player not found"))?.id;
                                 ctx.http
-                                    .get_user(self.players.current().ok_or(anyhow!("Player not found"))?.id)
+                                    .get_user(pid)
                                     .await?
                                     .create_dm_channel(&ctx.http)
                                     .await?
-                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", interaction.message.link())))
+                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", self.link(interaction, pid))))
                                     .await?;
                             }
                             self.last_time = now;
@@ -295,6 +564,31 @@ impl Game {
                     }
                 }
             }
+            State::Finished(ref mut won_game) => match action {
+                Action::Rematch => {
+                    if self.tournament_match.is_some() {
+                        return Err(anyhow!("Tournament matches can't be rematched"));
+                    }
+                    if !self.players.all().any(|p| p.id == interaction.user.id) {
+                        return Err(anyhow!("Only the players can start a rematch"));
+                    }
+                    if won_game.rematched {
+                        return Err(anyhow!("A rematch has already been started"));
+                    }
+                    won_game.rematched = true;
+                    match self.dm {
+                        Some(_) => {
+                            self.rematch()?.post_dm(ctx).await?;
+                        }
+                        None => {
+                            self.rematch()?.post(ctx, interaction.channel_id).await?;
+                        }
+                    }
+                }
+                _ => {
+                    return Err(anyhow!("Invalid action: {}", action.name()));
+                }
+            },
             _ => {
                 return Err(anyhow!("Invalid action: {}", action.name()));
             }
@@ -314,8 +608,7 @@ impl Game {
                     .await?;
             }
             State::AwaitingApproval(ref u) => {
-                let mut content = self.title_card()?;
-                content.push_str(u.challenge_message().as_str());
+                let content = self.challenge_message(u)?;
                 interaction
                     .edit_response(&ctx.http, {
                         // d.content(content).components(|c| {
@@ -324,94 +617,34 @@ impl Game {
                         //             .create_button(|b| b.style(ButtonStyle::Danger).label("Decline").custom_id(Action::Decline.to_custom_id("tictactoe")))
                         //     })
                         // })
-                        EditInteractionResponse::default().content(content).components({
-                            let rows = vec![CreateActionRow::Buttons(vec![
-                                CreateButton::new(Action::Accept.to_custom_id("ultimatetictactoe")).style(ButtonStyle::Success).label("Accept"),
-                                CreateButton::new(Action::Decline.to_custom_id("ultimatetictactoe")).style(ButtonStyle::Danger).label("Decline"),
-                            ])];
-                            rows
-                        })
+                        EditInteractionResponse::default().content(content).components(u.components("tictactoe"))
                     })
                     .await?;
             }
-            State::InProgress(game) => {
-                let mut content = self.title_card()?;
-                let current_player = self.players.current().ok_or(anyhow!("Player not found"))?;
-                content.push_str(&format!("It is {}'s turn [{}]", current_player.id.mention(), current_player.piece));
+            State::InProgress(_) => {
+                let (content, components) = self.in_progress_message()?;
                 interaction.defer(&ctx.http).await?;
                 interaction
-                    .edit_response(&ctx.http, {
-                        // d.content(content).components(|c| {
-                        //     for x in 0..=2 {
-                        //         c.create_action_row(|a| {
-                        //             for y in 0..=2 {
-                        //                 a.create_button(|b| {
-                        //                     game.board.button_for(x, y, b);
-                        //                     b
-                        //                 });
-                        //             }
-                        //             a
-                        //         });
-                        //     }
-                        //     c
-                        // })
-                        EditInteractionResponse::default().content(content).components({
-                            let mut rows = vec![];
-                            for x in 0..=2 {
-                                let mut buttons = vec![];
-                                for y in 0..=2 {
-                                    buttons.push({
-                                        let mut button = CreateButton::new(Action::Place(x, y).to_custom_id("tictactoe"));
-                                        button = game.board.button_for(x, y, button);
-                                        button
-                                    });
-                                }
-                                rows.push(CreateActionRow::Buttons(buttons));
-                            }
-                            rows
-                        })
-                    })
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(content.clone()).components(components.clone()))
                     .await?;
+                if let Some(dm) = self.dm {
+                    dm.sync(ctx, interaction.message.id, content, components).await;
+                }
             }
             State::Finished(won_game) => {
                 let mut content = self.title_card()?;
                 content.push_str(won_game.win_message().as_str());
+                content.push_str(&self.series_message());
+                let (grid, mut rows) = self.board_view(&won_game.board, true);
+                content.push_str(&grid);
+                rows.extend(self.rematch_row(won_game));
                 interaction.defer(&ctx.http).await?;
                 interaction
-                    .edit_response(&ctx.http, {
-                        // d.content(content).components(|c| {
-                        //     for x in 0..=2 {
-                        //         c.create_action_row(|a| {
-                        //             for y in 0..=2 {
-                        //                 a.create_button(|b| {
-                        //                     won_game.board.button_for(x, y, b);
-                        //                     b.disabled(true);
-                        //                     b
-                        //                 });
-                        //             }
-                        //             a
-                        //         });
-                        //     }
-                        //     c
-                        // })
-                        EditInteractionResponse::default().content(content).components({
-                            let mut rows = vec![];
-                            for x in 0..=2 {
-                                let mut buttons = vec![];
-                                for y in 0..=2 {
-                                    buttons.push({
-                                        let mut button = CreateButton::new(Action::Place(x, y).to_custom_id("tictactoe"));
-                                        button = won_game.board.button_for(x, y, button);
-                                        button = button.disabled(true);
-                                        button
-                                    });
-                                }
-                                rows.push(CreateActionRow::Buttons(buttons));
-                            }
-                            rows
-                        })
-                    })
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(content.clone()).components(rows.clone()))
                     .await?;
+                if let Some(dm) = self.dm {
+                    dm.sync(ctx, interaction.message.id, content, rows).await;
+                }
             }
         }
         Ok(())
@@ -419,8 +652,7 @@ impl Game {
     async fn send(&self, ctx: &Context, interaction: &mut CommandInteraction) -> Result<()> {
         match self.gamestate {
             State::AwaitingApproval(ref u) => {
-                let mut content = self.title_card()?;
-                content.push_str(u.challenge_message().as_str());
+                let content = self.challenge_message(u)?;
                 interaction
                     .create_response(&ctx.http, {
                         // f.interaction_response_data(|d| {
@@ -431,22 +663,419 @@ impl Game {
                         //         })
                         //     })
                         // })
-                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components({
-                            let rows = vec![CreateActionRow::Buttons(vec![
-                                CreateButton::new(Action::Accept.to_custom_id("tictactoe")).style(ButtonStyle::Success).label("Accept"),
-                                CreateButton::new(Action::Decline.to_custom_id("tictactoe")).style(ButtonStyle::Danger).label("Decline"),
-                            ])];
-                            rows
-                        }))
+                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components(u.components("tictactoe")))
                     })
                     .await?;
             }
+            State::InProgress(_) => {
+                let (content, components) = self.in_progress_message()?;
+                interaction
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components(components)),
+                    )
+                    .await?;
+            }
             _ => {
                 return Err(qg_shared::anyhow::anyhow!("Invalid game state"));
             }
         }
         Ok(())
     }
+    fn in_progress_message(&self) -> Result<(String, Vec<CreateActionRow>)> {
+        let game = match self.gamestate {
+            State::InProgress(ref game) => game,
+            _ => return Err(anyhow!("Invalid game state")),
+        };
+        let mut content = self.title_card()?;
+        if self.tournament_match.is_some() {
+            content.push_str("Tournament match! ");
+        }
+        if let Some(bot) = self.ai {
+            content.push_str(&format!("Unranked game against the bot ({}). ", bot.difficulty.display_name()));
+        }
+        if !self.variant.is_classic() {
+            content.push_str(&format!("{}. ", self.variant.name()));
+        }
+        if self.series.is_series() {
+            content.push_str(&format!(
+                "Game {} of a best of {} ({}). ",
+                self.series.played() + 1,
+                self.series.best_of(),
+                self.series.score(self.players.all().map(|p| p.id))
+            ));
+        }
+        let current_player = self.players.current().ok_or(anyhow!("Player not found"))?;
+        content.push_str(&format!("It is {}'s turn [{}]", current_player.id.mention(), current_player.piece));
+        if let Some(offer) = game.offer {
+            content.push_str(&offer.message());
+        }
+
+        let (grid, mut rows) = self.board_view(&game.board, false);
+        content.push_str(&grid);
+        rows.push(game.controls("tictactoe", self.ai.is_none()));
+
+        Ok((content, rows))
+    }
+    /// a button per space, unless that leaves no room for the row under the board (discord allows five rows, a 5x5 board would take them all).
+    /// then the board is drawn in the message instead, with a menu of the empty spaces to move in
+    fn board_view(&self, board: &Board, finished: bool) -> (String, Vec<CreateActionRow>) {
+        if self.variant.size < 5 {
+            let rows = (0..self.variant.size)
+                .map(|x| {
+                    CreateActionRow::Buttons(
+                        (0..self.variant.size)
+                            .map(|y| {
+                                board
+                                    .button_for(x, y, CreateButton::new(Action::Place(x, y).to_custom_id("tictactoe")))
+                                    .disabled(finished || board.spaces[x][y] != Space::Empty)
+                            })
+                            .collect(),
+                    )
+                })
+                .collect();
+            return (String::new(), rows);
+        }
+
+        let mut grid = String::from("\n");
+        for x in 0..self.variant.size {
+            grid.push('\n');
+            grid.push_str(&(0..self.variant.size).map(|y| board.spaces[x][y].grid_emoji()).collect::<Vec<&str>>().join(" "));
+        }
+        if finished {
+            return (grid, vec![]);
+        }
+        let options = (0..self.variant.size)
+            .flat_map(|x| (0..self.variant.size).map(move |y| (x, y)))
+            .filter(|(x, y)| board.spaces[*x][*y] == Space::Empty)
+            .map(|(x, y)| CreateSelectMenuOption::new(format!("Row {}, column {}", x + 1, y + 1), format!("{}:{}", x, y)))
+            .collect();
+        let menu = CreateSelectMenu::new("tictactoe:Place", CreateSelectMenuKind::String { options }).placeholder("Pick a space");
+        (grid, vec![CreateActionRow::SelectMenu(menu)])
+    }
+    /// ends the game, letting the players know and recording the result. `reason` explains an ending that didn't happen on the board
+    async fn finish(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, winner: Outcome, board: Board, reason: Option<String>, db: &mut OptTrans<'_>) -> Result<()> {
+        self.gamestate = State::Finished(WonGame {
+            winner: winner.clone(),
+            board,
+            rematched: false,
+            reason,
+        });
+        self.series.record(winner.winner().map(|p| p.id));
+        // render early so the messages and sql stuff doesnt lag the update
+        self.render(ctx, interaction).await?;
+        self.close_thread(ctx).await;
+        for player in self.players.all() {
+            if matches!(self.ai, Some(bot) if bot.id == player.id) {
+                continue;
+            }
+            match ctx.http.get_user(player.id).await {
+                Ok(user) => match user.create_dm_channel(&ctx.http).await {
+                    Ok(dm) => {
+                        if let Err(e) = dm
+                            .send_message(&ctx.http, {
+                                CreateMessage::default().content({
+                                    if let Outcome::Win(p) = winner {
+                                        format!("You {} in {}", if *player == p { "won" } else { "got your ass handed to you" }, self.link(interaction, player.id))
+                                    } else {
+                                        format!("You tied in {}", self.link(interaction, player.id))
+                                    }
+                                })
+                            })
+                            .await
+                        {
+                            qg_shared::log::trace!("Failed to send message to user: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        qg_shared::log::trace!("Failed to create dm channel: {}", e);
+                    }
+                },
+                Err(e) => {
+                    qg_shared::log::trace!("Failed to get user: {}", e);
+                }
+            }
+        }
+        if let (Some(db), Some(bot)) = (db.as_mut(), self.ai) {
+            // games against the bot are unranked, so they're kept off the leaderboards
+            if let Some(human) = self.players.all().find(|p| p.id != bot.id) {
+                let user = qg_shared::db::User::get_or_create(ctx, &human.id, db).await?;
+                let won = winner.winner().map(|winner| winner.id == human.id);
+                qg_shared::db::AiGame::create(&user, qg_shared::db::GameKind::TicTacToe, bot.difficulty.name(), won, interaction.guild_id.map(|g| g.get() as i64), db).await?;
+            }
+            return Ok(());
+        }
+        if let Some(db) = db {
+            if let Some(winner) = winner.winner() {
+                let mut players: HashMap<UserId, qg_shared::db::User> = HashMap::new();
+
+                for player in self.players.all() {
+                    players.insert(player.id, qg_shared::db::User::get_or_create(ctx, &player.id, db).await?);
+                }
+
+                for player in self.players.all() {
+                    let user = &players[&player.id];
+                    let opponent = &players[&self.players.all().find(|p| p.id != player.id).unwrap_or(player).id];
+                    if user.id != opponent.id {
+                        qg_shared::db::TicTacToe::create(
+                            user.id as i32,
+                            opponent.id as i32,
+                            player.id == winner.id,
+                            self.variant,
+                            interaction.guild_id.map(|g| g.get() as i64),
+                            db,
+                        )
+                        .await?;
+                        let unlocked = qg_shared::db::Achievement::evaluate(user, &qg_shared::db::GameResult::TicTacToe { won: player.id == winner.id }, db).await?;
+                        qg_shared::db::Achievement::notify_later(player.id, unlocked);
+                    }
+                }
+
+                // the game that decides a series records the series too
+                if let Some(series_winner) = self.series.winner() {
+                    if let Some(series_loser) = self.players.all().find(|p| p.id != series_winner) {
+                        qg_shared::db::SeriesResult::create(
+                            qg_shared::db::GameKind::TicTacToe,
+                            players[&series_winner].id,
+                            players[&series_loser.id].id,
+                            self.series.best_of(),
+                            (self.series.wins(series_winner), self.series.wins(series_loser.id)),
+                            interaction.guild_id.map(|g| g.get() as i64),
+                            db,
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+        if let (Some(db), Some(tournament_match)) = (db.as_mut(), self.tournament_match) {
+            let winner = match winner.winner() {
+                Some(winner) => Some(qg_shared::db::User::get_or_create(ctx, &winner.id, db).await?.id),
+                None => None,
+            };
+            advance_tournament(ctx, tournament_match, winner, db).await?;
+        }
+        Ok(())
+    }
+    /// resigning, draw offers and takebacks, which either player can use whoever's turn it is
+    async fn control(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut OptTrans<'_>) -> Result<()> {
+        let player = *self.players.all().find(|p| p.id == interaction.user.id).ok_or(anyhow!("You are not playing in this game"))?;
+        if self.ai.is_some() && !matches!(action, Action::Resign) {
+            return Err(anyhow!("The bot doesn't take offers"));
+        }
+        let game = match self.gamestate {
+            State::InProgress(ref mut game) => game,
+            _ => return Err(anyhow!("Invalid game state")),
+        };
+        match action {
+            Action::Resign => {
+                let winner = *self.players.all().find(|p| p.id != player.id).unwrap_or(&player);
+                let board = game.board.clone();
+                return self.finish(ctx, interaction, Outcome::Win(winner), board, Some(format!("{} resigned", player.id.mention())), db).await;
+            }
+            Action::OfferDraw => {
+                if game.offer.is_some() {
+                    return Err(anyhow!("There's already an offer waiting for an answer"));
+                }
+                game.offer = Some(Offer::Draw(player.id));
+            }
+            Action::Takeback => {
+                if game.offer.is_some() {
+                    return Err(anyhow!("There's already an offer waiting for an answer"));
+                }
+                if !game.can_undo() {
+                    return Err(anyhow!("There's no move to take back"));
+                }
+                // only whoever just moved can ask for it back
+                if self.players.current().map(|p| p.id) == Some(player.id) && !qg_shared::allow_self_play() {
+                    return Err(anyhow!("You can only take back your own move"));
+                }
+                game.offer = Some(Offer::Takeback(player.id));
+            }
+            Action::AcceptOffer => {
+                let offer = game.offer.ok_or(anyhow!("There's no offer to accept"))?;
+                if offer.from() == player.id && !qg_shared::allow_self_play() {
+                    return Err(anyhow!("You can't accept your own offer"));
+                }
+                match offer {
+                    Offer::Draw(_) => {
+                        let board = game.board.clone();
+                        return self.finish(ctx, interaction, Outcome::Tie, board, Some(String::from("draw agreed")), db).await;
+                    }
+                    Offer::Takeback(_) => {
+                        game.undo()?;
+                        // two players, so going forward one is going back one
+                        self.players.next_player();
+                    }
+                }
+            }
+            Action::DeclineOffer => {
+                // declining your own offer takes it back
+                if game.offer.take().is_none() {
+                    return Err(anyhow!("There's no offer to decline"));
+                }
+            }
+            _ => {
+                return Err(anyhow!("Invalid action: {}", action.name()));
+            }
+        }
+
+        self.render(ctx, interaction).await
+    }
+    fn challenge_message(&self, u: &Awaiting) -> Result<String> {
+        let mut content = self.title_card()?;
+        content.push_str(u.challenge_message().as_str());
+        if !self.variant.is_classic() {
+            content.push_str(&format!(" ({})", self.variant.name()));
+        }
+        if self.series.is_series() {
+            content.push_str(&format!(" (best of {})", self.series.best_of()));
+        }
+        Ok(content)
+    }
+    /// how the series stands after this game, empty for a single game
+    fn series_message(&self) -> String {
+        if !self.series.is_series() {
+            return String::new();
+        }
+        let score = self.series.score(self.players.all().map(|p| p.id));
+        match self.series.winner() {
+            Some(winner) => format!(" {} wins the best of {} series! ({})", winner.mention(), self.series.best_of(), score),
+            None => format!(" Best of {}: {}", self.series.best_of(), score),
+        }
+    }
+    /// the button under a finished game, tournament matches are only played once
+    fn rematch_row(&self, won_game: &WonGame) -> Option<CreateActionRow> {
+        if self.tournament_match.is_some() {
+            return None;
+        }
+        let label = if self.series.is_series() && self.series.winner().is_none() { "Next game" } else { "Rematch" };
+        Some(CreateActionRow::Buttons(vec![CreateButton::new(Action::Rematch.to_custom_id("tictactoe"))
+            .style(ButtonStyle::Primary)
+            .label(label)
+            .disabled(won_game.rematched)]))
+    }
+    /// the same players again with whoever went second going first, carrying the series along
+    fn rematch(&self) -> Result<Game> {
+        let mut game = Game {
+            players: self.players.rotated(),
+            gamestate: State::InProgress(InProgress::new(self.variant)),
+            last_time: qg_shared::current_time()?,
+            tournament_match: None,
+            series: self.series.next(),
+            thread: self.thread,
+            dm: None,
+            ai: self.ai,
+            variant: self.variant,
+        };
+        game.play_bot()?;
+        Ok(game)
+    }
+    /// plays the bot's move if it's the bot's turn, returning the outcome and final board if that ended the game
+    fn play_bot(&mut self) -> Result<Option<(Outcome, Board)>> {
+        let bot = match self.ai {
+            Some(bot) => bot,
+            None => return Ok(None),
+        };
+        let current = *self.players.current().ok_or(anyhow!("Player not found"))?;
+        if current.id != bot.id {
+            return Ok(None);
+        }
+        let game = match self.gamestate {
+            State::InProgress(ref mut game) => game,
+            _ => return Err(anyhow!("Invalid game state")),
+        };
+
+        let (x, y) = ai::choose_move(&game.board, current.piece, self.variant.win_length, bot.difficulty).ok_or(anyhow!("The bot has no moves left"))?;
+        game.make_move(x, y, current.piece)?;
+        if let Some(outcome) = game.board.check_winner(&self.players, self.variant.win_length) {
+            return Ok(Some((outcome, game.board.clone())));
+        }
+        self.players.next_player();
+        Ok(None)
+    }
+    /// moves the game into its own thread if the server has game threads turned on.
+    /// returns whether it did, the challenge message is left behind pointing to the thread
+    async fn move_to_thread(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut OptTrans<'_>) -> Result<bool> {
+        let (guild_id, db) = match (interaction.guild_id, db.as_mut()) {
+            (Some(guild_id), Some(db)) => (guild_id, db),
+            _ => return Ok(false),
+        };
+        if !qg_shared::db::GuildSettings::get(guild_id.get() as i64, db).await?.game_threads {
+            return Ok(false);
+        }
+
+        let mut names = vec![];
+        for player in self.players.all() {
+            names.push(
+                qg_shared::identity::resolve(ctx, &player.id)
+                    .await
+                    .map(|identity| identity.name)
+                    .unwrap_or_else(|| player.id.to_string()),
+            );
+        }
+        let first = self.players.current().ok_or(anyhow!("Player not found"))?.id;
+        let mut summary = format!("{} goes first in {}", first.mention(), self.players_message());
+        if self.series.is_series() {
+            summary.push_str(&format!(", best of {}", self.series.best_of()));
+        }
+        summary.push_str(". Spectators welcome, the board is below!");
+
+        let thread = qg_shared::GameThread::open(ctx, interaction.channel_id, interaction.message.id, format!("Tic Tac Toe: {}", names.join(" vs ")), summary).await?;
+        self.thread = Some(thread);
+        self.post(ctx, thread.id).await?;
+
+        interaction.defer(&ctx.http).await?;
+        interaction
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::default()
+                    .content(format!("Tic Tac Toe between {} is being played in {}", self.players_message(), thread.id.mention()))
+                    .components(vec![]),
+            )
+            .await?;
+
+        Ok(true)
+    }
+    /// mirrors the result to the parent channel and archives the thread, if the game was played in one
+    async fn close_thread(&self, ctx: &Context) {
+        let (thread, won_game) = match (&self.thread, &self.gamestate) {
+            (Some(thread), State::Finished(won_game)) => (thread, won_game),
+            _ => return,
+        };
+        let result = format!(
+            "Tic Tac Toe between {}: {}{} (played in {})",
+            self.players_message(),
+            won_game.win_message(),
+            self.series_message(),
+            thread.id.mention()
+        );
+        thread.close(ctx, result).await;
+    }
+    /// where the player can find the game, their own copy when it's played over dms
+    fn link(&self, interaction: &ComponentInteraction, player: UserId) -> String {
+        self.dm.and_then(|dm| dm.link(player)).unwrap_or_else(|| interaction.message.link())
+    }
+    fn players_message(&self) -> String {
+        self.players.all().map(|p| p.id.mention().to_string()).collect::<Vec<String>>().join(" and ")
+    }
+    /// posts a game that's already under way in its own message
+    async fn post(&self, ctx: &Context, channel_id: ChannelId) -> Result<Message> {
+        let (content, components) = self.in_progress_message()?;
+        let message = channel_id.send_message(&ctx.http, CreateMessage::default().content(content).components(components)).await?;
+
+        Ok(message)
+    }
+    /// posts a game that's already under way to both players' dms
+    async fn post_dm(mut self, ctx: &Context) -> Result<qg_shared::DmGame> {
+        let players = self.players.all().map(|p| p.id).collect::<Vec<UserId>>();
+        let dm = qg_shared::DmGame::open(ctx, (players[0], players[1])).await?;
+        self.dm = Some(dm);
+        let (content, components) = self.in_progress_message()?;
+        dm.update(ctx, content, components).await?;
+
+        Ok(dm)
+    }
     fn title_card(&self) -> Result<String> {
         Ok(format!("```{}\nTic Tac Toe\n```", qg_shared::serialize(&self)?.replace('\n', "")))
     }
@@ -459,6 +1088,14 @@ pub enum Space {
     Empty,
 }
 impl Space {
+    /// for boards drawn in the message, where the empty spaces need to line up with the pieces
+    fn grid_emoji(&self) -> &'static str {
+        match self {
+            Space::X => "🇽",
+            Space::O => "🇴",
+            Space::Empty => "⬜",
+        }
+    }
     fn button_style(&self) -> ButtonStyle {
         match self {
             Space::X => ButtonStyle::Primary,
@@ -489,44 +1126,142 @@ pub enum State {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Awaiting {
     inviter: UserId,
-    invitee: UserId,
+    invitee: Option<UserId>, // None is an open challenge
+    #[serde(default)]
+    expires_at: u64, // 0 never expires
 }
 
 impl Awaiting {
     fn challenge_message(&self) -> String {
-        format!("{} has challenged {} to a game of Tic Tac Toe", self.inviter.mention(), self.invitee.mention())
+        match self.invitee {
+            Some(invitee) => format!("{} has challenged {} to a game of Tic Tac Toe", self.inviter.mention(), invitee.mention()),
+            None => format!(
+                "{} is looking for an opponent for a game of Tic Tac Toe, first to accept plays! (expires <t:{}:R>)",
+                self.inviter.mention(),
+                self.expires_at
+            ),
+        }
+    }
+    fn expired(&self) -> Result<bool> {
+        Ok(self.expires_at != 0 && qg_shared::current_time()? > self.expires_at)
+    }
+    fn components(&self, command: &str) -> Vec<CreateActionRow> {
+        let mut buttons = vec![CreateButton::new(Action::Accept.to_custom_id(command)).style(ButtonStyle::Success).label("Accept")];
+        if self.invitee.is_some() {
+            buttons.push(CreateButton::new(Action::Decline.to_custom_id(command)).style(ButtonStyle::Danger).label("Decline"));
+        }
+        buttons.push(CreateButton::new(Action::Cancel.to_custom_id(command)).style(ButtonStyle::Secondary).label("Cancel"));
+        vec![CreateActionRow::Buttons(buttons)]
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InProgress {
     board: Board,
+    #[serde(default)]
+    offer: Option<Offer>, // a draw or takeback waiting for the other player, cleared by the next move
+    #[serde(default)]
+    last_move: Option<(usize, usize)>, // what a takeback undoes
 }
 
 impl InProgress {
+    fn new(variant: qg_shared::db::TicTacToeVariant) -> InProgress {
+        InProgress {
+            board: Board {
+                spaces: vec![vec![Space::Empty; variant.size]; variant.size],
+            },
+            offer: None,
+            last_move: None,
+        }
+    }
     fn make_move(&mut self, x: usize, y: usize, piece: Space) -> Result<()> {
-        if x > 2 || y > 2 {
+        if x >= self.board.spaces.len() || y >= self.board.spaces.len() {
             return Err(qg_shared::anyhow::anyhow!("Invalid move, out of bounds"));
         }
         if self.board.spaces[x][y] != Space::Empty {
             return Err(qg_shared::anyhow::anyhow!("Invalid move, space already occupied"));
         }
         self.board.spaces[x][y] = piece;
+        self.last_move = Some((x, y));
+        self.offer = None;
         Ok(())
     }
+    fn can_undo(&self) -> bool {
+        self.last_move.is_some()
+    }
+    /// takes back the last move, only one move can be taken back
+    fn undo(&mut self) -> Result<()> {
+        let (x, y) = self.last_move.take().ok_or(anyhow!("There's no move to take back"))?;
+        self.board.spaces[x][y] = Space::Empty;
+        self.offer = None;
+        Ok(())
+    }
+    /// resign, draw and takeback, or the answers to whatever offer is waiting. offers need someone to answer them, so against the bot there's only resign
+    fn controls(&self, command: &str, offers: bool) -> CreateActionRow {
+        let resign = CreateButton::new(Action::Resign.to_custom_id(command)).style(ButtonStyle::Danger).label("Resign");
+        if !offers {
+            return CreateActionRow::Buttons(vec![resign]);
+        }
+        CreateActionRow::Buttons(match self.offer {
+            Some(offer) => vec![
+                CreateButton::new(Action::AcceptOffer.to_custom_id(command)).style(ButtonStyle::Success).label(match offer {
+                    Offer::Draw(_) => "Accept draw",
+                    Offer::Takeback(_) => "Allow takeback",
+                }),
+                CreateButton::new(Action::DeclineOffer.to_custom_id(command)).style(ButtonStyle::Secondary).label("Decline"),
+                resign,
+            ],
+            None => vec![
+                resign,
+                CreateButton::new(Action::OfferDraw.to_custom_id(command)).style(ButtonStyle::Secondary).label("Offer draw"),
+                CreateButton::new(Action::Takeback.to_custom_id(command))
+                    .style(ButtonStyle::Secondary)
+                    .label("Takeback")
+                    .disabled(!self.can_undo()),
+            ],
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Offer {
+    Draw(UserId), // who made the offer
+    Takeback(UserId),
+}
+
+impl Offer {
+    fn from(&self) -> UserId {
+        match self {
+            Self::Draw(id) | Self::Takeback(id) => *id,
+        }
+    }
+    fn message(&self) -> String {
+        match self {
+            Self::Draw(id) => format!(". {} offers a draw", id.mention()),
+            Self::Takeback(id) => format!(". {} asks to take back their last move", id.mention()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WonGame {
     winner: Outcome,
     board: Board,
+    #[serde(default)]
+    rematched: bool,
+    #[serde(default)]
+    reason: Option<String>, // why the game ended off the board, a resignation or an agreed draw
 }
 
 impl WonGame {
     fn win_message(&self) -> String {
-        match self.winner {
+        let message = match self.winner {
             Outcome::Win(player) => format!("{} [{}] has won!", player.id.mention(), player.piece),
             Outcome::Tie => String::from("It's a tie!"),
+        };
+        match self.reason {
+            Some(ref reason) => format!("{} ({})", message, reason),
+            None => message,
         }
     }
 }
@@ -547,38 +1282,27 @@ impl Board {
         button
     }
 
-    fn check_winner(&self, players: &CycleVec<Player>) -> Option<Outcome> {
-        // check rows
-        for row in self.spaces.iter() {
-            if row.iter().all(|s| *s == Space::X) {
-                return Some(Outcome::Win(players.all().find(|p| p.piece == Space::X).copied()?));
-            }
-            if row.iter().all(|s| *s == Space::O) {
-                return Some(Outcome::Win(players.all().find(|p| p.piece == Space::O).copied()?));
-            }
-        }
-        // check columns
-        for x in 0..3 {
-            if self.spaces.iter().all(|row| row[x] == Space::X) {
-                return Some(Outcome::Win(players.all().find(|p| p.piece == Space::X).copied()?));
-            }
-            if self.spaces.iter().all(|row| row[x] == Space::O) {
-                return Some(Outcome::Win(players.all().find(|p| p.piece == Space::O).copied()?));
+    /// looks for `win_length` of the same piece in a row, across, down or along either diagonal
+    fn check_winner(&self, players: &CycleVec<Player>, win_length: usize) -> Option<Outcome> {
+        let size = self.spaces.len() as isize;
+        for x in 0..size {
+            for y in 0..size {
+                let piece = self.spaces[x as usize][y as usize];
+                if piece == Space::Empty {
+                    continue;
+                }
+                // each line is found from its first space, so only looking forwards is enough
+                for (dx, dy) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                    let line = (0..win_length as isize).all(|i| {
+                        let (x, y) = (x + dx * i, y + dy * i);
+                        (0..size).contains(&x) && (0..size).contains(&y) && self.spaces[x as usize][y as usize] == piece
+                    });
+                    if line {
+                        return Some(Outcome::Win(players.all().find(|p| p.piece == piece).copied()?));
+                    }
+                }
             }
         }
-        // check diagonals
-        if self.spaces[0][0] == Space::X && self.spaces[1][1] == Space::X && self.spaces[2][2] == Space::X {
-            return Some(Outcome::Win(players.all().find(|p| p.piece == Space::X).copied()?));
-        }
-        if self.spaces[0][0] == Space::O && self.spaces[1][1] == Space::O && self.spaces[2][2] == Space::O {
-            return Some(Outcome::Win(players.all().find(|p| p.piece == Space::O).copied()?));
-        }
-        if self.spaces[0][2] == Space::X && self.spaces[1][1] == Space::X && self.spaces[2][0] == Space::X {
-            return Some(Outcome::Win(players.all().find(|p| p.piece == Space::X).copied()?));
-        }
-        if self.spaces[0][2] == Space::O && self.spaces[1][1] == Space::O && self.spaces[2][0] == Space::O {
-            return Some(Outcome::Win(players.all().find(|p| p.piece == Space::O).copied()?));
-        }
         // check tie
         if self.spaces.iter().flatten().all(|s| *s != Space::Empty) {
             return Some(Outcome::Tie);
@@ -607,3 +1331,95 @@ pub struct Player {
     id: UserId,
     piece: Space,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const X: Player = Player { id: UserId::new(1), piece: Space::X };
+    const O: Player = Player { id: UserId::new(2), piece: Space::O };
+
+    /// a board from its rows, `X`, `O` and `.` for empty
+    fn board(rows: &[&str]) -> Board {
+        Board {
+            spaces: rows
+                .iter()
+                .map(|row| {
+                    row.chars()
+                        .map(|c| match c {
+                            'X' => Space::X,
+                            'O' => Space::O,
+                            _ => Space::Empty,
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    fn winner(rows: &[&str], win_length: usize) -> Option<Player> {
+        let players = CycleVec::new(vec![X, O]);
+        match board(rows).check_winner(&players, win_length)? {
+            Outcome::Win(player) => Some(player),
+            Outcome::Tie => None,
+        }
+    }
+
+    fn tied(rows: &[&str], win_length: usize) -> bool {
+        let players = CycleVec::new(vec![X, O]);
+        matches!(board(rows).check_winner(&players, win_length), Some(Outcome::Tie))
+    }
+
+    #[test]
+    fn classic_lines() {
+        assert_eq!(winner(&["XXX", "OO.", "..."], 3), Some(X));
+        assert_eq!(winner(&["X..", "XOO", "X.."], 3), Some(X));
+        assert_eq!(winner(&["O.X", ".OX", "X.O"], 3), Some(O));
+        assert_eq!(winner(&["X.O", ".OX", "O.X"], 3), Some(O));
+        assert_eq!(winner(&["XO.", "OX.", "..."], 3), None);
+        assert!(!tied(&["XO.", "OX.", "..."], 3));
+    }
+
+    #[test]
+    fn full_board_without_a_line_is_a_tie() {
+        assert!(tied(&["XOX", "XOO", "OXX"], 3));
+    }
+
+    #[test]
+    fn lines_along_the_edges() {
+        let rows = ["....X", "....X", "....X", "....X", "OOO.."];
+        assert_eq!(winner(&rows, 4), Some(X));
+        assert_eq!(winner(&rows, 5), None);
+        assert_eq!(winner(&[".....", ".....", ".....", ".....", ".OOOO"], 4), Some(O));
+        assert_eq!(winner(&["X....", "X....", "X....", ".....", "....."], 3), Some(X));
+    }
+
+    #[test]
+    fn shorter_line_than_the_board() {
+        // three in a row anywhere on a bigger board, not just from the corner
+        assert_eq!(winner(&["....", ".XXX", "....", "...."], 3), Some(X));
+        assert_eq!(winner(&["....", ".XX.", "....", "...."], 3), None);
+        assert_eq!(winner(&[".....", ".....", "..XXX", ".....", "....."], 3), Some(X));
+    }
+
+    #[test]
+    fn diagonals_off_the_middle() {
+        assert_eq!(winner(&[".X...", "..X..", "...X.", "....X", "....."], 4), Some(X));
+        assert_eq!(winner(&[".....", "X....", ".X...", "..X..", "...X."], 4), Some(X));
+        assert_eq!(winner(&[".X...", "..X..", "...X.", ".....", "....."], 4), None);
+    }
+
+    #[test]
+    fn anti_diagonals_off_the_middle() {
+        assert_eq!(winner(&["...O.", "..O..", ".O...", "O....", "....."], 4), Some(O));
+        assert_eq!(winner(&[".....", "....O", "...O.", "..O..", ".O..."], 4), Some(O));
+        assert_eq!(winner(&["....O", "...O.", "..O..", ".....", "O...."], 4), None);
+    }
+
+    #[test]
+    fn lines_dont_wrap_around() {
+        // the end of one row followed by the start of the next isn't a line
+        assert_eq!(winner(&["..XX", "X...", "....", "...."], 3), None);
+        assert_eq!(winner(&["...X", "X...", ".X..", "...."], 3), None);
+    }
+}
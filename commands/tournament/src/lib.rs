@@ -0,0 +1,362 @@
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    db::{GameKind, Tournament, TournamentFormat, TournamentMatch},
+    log,
+    serenity::all::*,
+    sqlx::types::chrono,
+    UnorderedVec,
+};
+
+use std::collections::HashMap;
+
+pub fn command() -> TournamentCommand {
+    TournamentCommand
+}
+
+pub struct TournamentCommand;
+
+/// how long sign-ups stay open when no window is given
+const DEFAULT_SIGNUP_MINUTES: i64 = 60;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for TournamentCommand {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("tournament"),
+            description: String::from("Run a Tic Tac Toe or Ultimate Tic Tac Toe tournament in this server"),
+            options: vec![
+                qg_shared::CommandOption {
+                    name: String::from("create"),
+                    description: String::from("Open sign-ups for a new tournament"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![
+                        choice_option("game", "The game to play", &[("Tic Tac Toe", "tictactoe"), ("Ultimate Tic Tac Toe", "ultimatetictactoe")], true),
+                        choice_option(
+                            "format",
+                            "How players are paired",
+                            &[("Single Elimination", "single_elimination"), ("Round Robin", "round_robin")],
+                            true,
+                        ),
+                        qg_shared::CommandOption {
+                            name: String::from("signup_minutes"),
+                            description: format!("How long sign-ups stay open (defaults to {} minutes)", DEFAULT_SIGNUP_MINUTES),
+                            option_type: qg_shared::CommandOptionType::Integer,
+                            choices: UnorderedVec::from(vec![]),
+                            required: false,
+                        },
+                    ])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("start"),
+                    description: String::from("Close sign-ups and open the first round"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("bracket"),
+                    description: String::from("Show the current tournament's matches"),
+                    option_type: qg_shared::CommandOptionType::SubCommand(UnorderedVec::from(vec![])),
+                    choices: UnorderedVec::from(vec![]),
+                    required: false,
+                },
+            ]
+            .into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let tx = match db {
+            Some(db) => db,
+            None => return Err(anyhow!("No database connection")),
+        };
+
+        let guild_id = interaction.guild_id.ok_or(anyhow!("Tournaments can only be run in servers"))?;
+
+        let option = interaction.data.options.first().ok_or(anyhow!("No options found"))?.clone();
+        let options = match option.value {
+            CommandDataOptionValue::SubCommand(options) => options,
+            _ => return Err(anyhow!("Expected SubCommand, got {:?}", option.value)),
+        };
+
+        match option.name.as_str() {
+            "create" => Self::create(ctx, interaction, guild_id, &options, tx).await,
+            "start" => Self::start(ctx, interaction, guild_id, tx).await,
+            "bracket" => Self::bracket(ctx, interaction, guild_id, tx).await,
+            v => Err(anyhow!("Unhandled option `{}`", v)),
+        }
+    }
+
+    async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let tx = match db {
+            Some(db) => db,
+            None => return Err(anyhow!("No database connection")),
+        };
+
+        let tournament_id = match Action::from_custom_id(&interaction.data.custom_id) {
+            Some(Action::Join(tournament_id)) => tournament_id,
+            None => return Err(anyhow!("Invalid action id")),
+        };
+
+        let tournament = Tournament::get(tournament_id, tx).await?.ok_or(anyhow!("Tournament not found"))?;
+        let user = qg_shared::db::User::get_or_create(ctx, &interaction.user.id, tx).await?;
+        tournament.join(&user, tx).await?;
+
+        let players = tournament.get_players(tx).await?;
+
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(CreateInteractionResponseMessage::new().content(signup_message(&tournament, players.len())?)),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn choice_option(name: &str, description: &str, choices: &[(&str, &str)], required: bool) -> qg_shared::CommandOption {
+    qg_shared::CommandOption {
+        name: name.to_string(),
+        description: description.to_string(),
+        option_type: qg_shared::CommandOptionType::String,
+        choices: UnorderedVec::from(
+            choices
+                .iter()
+                .map(|(name, value)| qg_shared::CommandOptionChoice {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+        ),
+        required,
+    }
+}
+
+fn signup_message(tournament: &Tournament, players: usize) -> Result<String> {
+    Ok(format!(
+        "A {} {} tournament is open for sign-ups until <t:{}:t>!\n{} signed up so far.",
+        tournament.format()?.display_name(),
+        tournament.game()?.display_name(),
+        tournament.signup_closes_at.and_utc().timestamp(),
+        players
+    ))
+}
+
+impl TournamentCommand {
+    async fn create(
+        ctx: &Context,
+        interaction: &mut CommandInteraction,
+        guild_id: GuildId,
+        options: &[CommandDataOption],
+        tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>,
+    ) -> Result<()> {
+        if Tournament::get_active(guild_id.get() as i64, tx).await?.is_some() {
+            return Err(anyhow!("There's already a tournament running in this server"));
+        }
+
+        let mut game = None;
+        let mut format = None;
+        let mut signup_minutes = DEFAULT_SIGNUP_MINUTES;
+
+        for option in options {
+            match (option.name.as_str(), &option.value) {
+                ("game", CommandDataOptionValue::String(value)) => {
+                    game = match GameKind::from_name(value) {
+                        Some(GameKind::SlidingPuzzle | GameKind::ConnectFour | GameKind::Minesweeper | GameKind::Wordle) | None => return Err(anyhow!("Unhandled value `{}`", value)),
+                        kind => kind,
+                    };
+                }
+                ("format", CommandDataOptionValue::String(value)) => {
+                    format = Some(TournamentFormat::from_name(value).ok_or(anyhow!("Unhandled value `{}`", value))?);
+                }
+                ("signup_minutes", CommandDataOptionValue::Integer(value)) => {
+                    // anywhere from a minute to a week
+                    signup_minutes = (*value).clamp(1, 60 * 24 * 7);
+                }
+                (v, _) => {
+                    return Err(anyhow!("Unhandled option `{}`", v));
+                }
+            }
+        }
+
+        let game = game.ok_or(anyhow!("No game specified"))?;
+        let format = format.ok_or(anyhow!("No format specified"))?;
+        let signup_closes_at = chrono::DateTime::from_timestamp(qg_shared::current_time()? as i64 + signup_minutes * 60, 0)
+            .ok_or(anyhow!("Invalid sign-up time"))?
+            .naive_utc();
+
+        let tournament = Tournament::create(
+            guild_id.get() as i64,
+            interaction.channel_id.get() as i64,
+            game,
+            format,
+            interaction.user.id.get() as i64,
+            signup_closes_at,
+            tx,
+        )
+        .await?;
+
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(signup_message(&tournament, 0)?)
+                        .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(Action::Join(tournament.id).to_custom_id())
+                            .style(ButtonStyle::Success)
+                            .label("Join")])]),
+                ),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn start(ctx: &Context, interaction: &mut CommandInteraction, guild_id: GuildId, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<()> {
+        let mut tournament = Tournament::get_active(guild_id.get() as i64, tx).await?.ok_or(anyhow!("There's no tournament to start"))?;
+
+        let is_organizer = tournament.created_by == interaction.user.id.get() as i64;
+        let is_manager = interaction.member.as_ref().and_then(|member| member.permissions).map(|p| p.manage_guild()).unwrap_or(false);
+        if !is_organizer && !is_manager {
+            return Err(anyhow!("Only the organizer or a server manager can start the tournament"));
+        }
+
+        // the first round can be a lot of messages, more than discord's rate limit lets us post before the response is due
+        interaction.defer(&ctx.http).await?;
+
+        let matches = tournament.start(tx).await?;
+
+        let channel_id = ChannelId::new(tournament.channel_id as u64);
+        interaction
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::default().content(format!("The tournament has started! {} matches are being opened in {}", matches.len(), channel_id.mention())),
+            )
+            .await?;
+
+        TournamentMatch::open_later(matches);
+
+        Ok(())
+    }
+
+    async fn bracket(ctx: &Context, interaction: &mut CommandInteraction, guild_id: GuildId, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<()> {
+        let tournament = Tournament::get_latest(guild_id.get() as i64, tx).await?.ok_or(anyhow!("This server hasn't had any tournaments yet"))?;
+
+        let players = tournament.get_players(tx).await?;
+        let names = players.iter().map(|user| (user.id, user.identity().name)).collect::<HashMap<i64, String>>();
+        let name = |id: i64| names.get(&id).cloned().unwrap_or(String::from("Unknown"));
+
+        let matches = tournament.get_matches(tx).await?;
+
+        let mut embed = CreateEmbed::default().title(format!("{} {} Tournament", tournament.format()?.display_name(), tournament.game()?.display_name()));
+
+        if tournament.is_signing_up() {
+            embed = embed.description(format!(
+                "Sign-ups close <t:{}:R>\n{}",
+                tournament.signup_closes_at.and_utc().timestamp(),
+                players.iter().map(|user| user.identity().name).collect::<Vec<String>>().join(", ")
+            ));
+        } else if let Some(champion) = tournament.champion_id {
+            embed = embed.description(format!("🏆 Champion: **{}**", name(champion as i64)));
+        }
+
+        let mut rounds: Vec<(i32, String)> = vec![];
+        for tournament_match in matches.iter() {
+            let line = match (tournament_match.player_two.map(i64::from), tournament_match.finished, tournament_match.winner_id.map(i64::from)) {
+                (None, _, _) => format!("{} - bye", name(tournament_match.player_one)),
+                (Some(player_two), true, Some(winner)) => format!("{} vs {} - **{}** won", name(tournament_match.player_one), name(player_two), name(winner)),
+                (Some(player_two), true, None) => format!("{} vs {} - draw", name(tournament_match.player_one), name(player_two)),
+                (Some(player_two), false, _) => match tournament_match.message_id {
+                    Some(message_id) => format!(
+                        "{} vs {} - [playing](https://discord.com/channels/{}/{}/{})",
+                        name(tournament_match.player_one),
+                        name(player_two),
+                        tournament.guild_id,
+                        tournament.channel_id,
+                        message_id
+                    ),
+                    None => format!("{} vs {}", name(tournament_match.player_one), name(player_two)),
+                },
+            };
+            match rounds.last_mut() {
+                Some((round, value)) if *round == tournament_match.round => {
+                    // embed field values are capped at 1024 characters
+                    if value.len() + line.len() < 1024 {
+                        value.push('\n');
+                        value.push_str(&line);
+                    }
+                }
+                _ => rounds.push((tournament_match.round, line)),
+            }
+        }
+
+        // embeds are capped at 25 fields
+        for (round, value) in rounds.into_iter().take(25) {
+            embed = embed.field(format!("Round {}", round), value, false);
+        }
+
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().embed(embed)))
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// posts the games for matches collected with `TournamentMatch::collect`, after the transaction that made them playable has committed.
+/// each match is saved with its message on its own, and failing to open one shouldn't stop the rest, so errors are only logged
+pub async fn open_matches(ctx: &Context, pool: &qg_shared::sqlx::PgPool, matches: Vec<TournamentMatch>) {
+    for mut tournament_match in matches {
+        if let Err(e) = open_match(ctx, pool, &mut tournament_match).await {
+            log::error!("Failed to open tournament match {}: {}", tournament_match.id, e);
+        }
+    }
+}
+
+async fn open_match(ctx: &Context, pool: &qg_shared::sqlx::PgPool, tournament_match: &mut TournamentMatch) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let tournament = Tournament::get(tournament_match.tournament_id, &mut tx).await?.ok_or(anyhow!("Tournament not found"))?;
+    let channel_id = ChannelId::new(tournament.channel_id as u64);
+
+    match tournament.game()? {
+        GameKind::TicTacToe => qg_tictactoe::open_match(ctx, channel_id, tournament_match, &mut tx).await?,
+        GameKind::UltimateTicTacToe => qg_ulttictactoe::open_match(ctx, channel_id, tournament_match, &mut tx).await?,
+        GameKind::SlidingPuzzle => return Err(anyhow!("Sliding puzzle tournaments aren't supported")),
+        GameKind::ConnectFour => return Err(anyhow!("Connect Four tournaments aren't supported")),
+        GameKind::Minesweeper => return Err(anyhow!("Minesweeper tournaments aren't supported")),
+        GameKind::Wordle => return Err(anyhow!("Wordle tournaments aren't supported")),
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub enum Action {
+    Join(i64),
+}
+
+impl Action {
+    pub fn from_custom_id(custom_id: &str) -> Option<Self> {
+        let mut split = custom_id.split(':').skip(1);
+        let action = split.next()?;
+        match action {
+            "Join" => {
+                let id = split.next()?.parse().ok()?;
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Join(id))
+            }
+            _ => None,
+        }
+    }
+    pub fn to_custom_id(&self) -> String {
+        match self {
+            Self::Join(id) => format!("tournament:Join:{}", id),
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! the bot opponent, a monte carlo tree search over a compact copy of the board.
+//! the rules here have to match `MetaBoard` and `InProgress::make_move`, including ties counting for either player on the big board
+
+use std::time::{Duration, Instant};
+
+use qg_shared::{rand::Rng, serenity::all::UserId};
+
+use serde::{Deserialize, Serialize};
+
+use super::{MetaBoard, MetaSpace, Space};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Bot {
+    pub id: UserId,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Easy => "easy",
+            Self::Medium => "medium",
+            Self::Hard => "hard",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Self::Easy),
+            "medium" => Some(Self::Medium),
+            "hard" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Hard => "Hard",
+        }
+    }
+    /// how many playouts the search gets, and how long it can take at most.
+    /// the handler holds the command's lock while the bot thinks, so every ultimate tic tac toe click in every server waits on the search.
+    /// the time limits are kept well short of discord's 3 second window for that reason, hard still gets through its playouts in that time
+    fn budget(&self) -> (u32, Duration) {
+        match self {
+            Self::Easy => (200, Duration::from_millis(50)),
+            Self::Medium => (3_000, Duration::from_millis(200)),
+            Self::Hard => (20_000, Duration::from_millis(500)),
+        }
+    }
+}
+
+/// picks the bot's move for `piece` as (board, space), `None` if there's nothing to play
+pub fn choose_move(board: &MetaBoard, piece: Space, difficulty: Difficulty) -> Option<((usize, usize), (usize, usize))> {
+    let root = Position::from_board(board, piece);
+    let (playouts, time) = difficulty.budget();
+
+    let (board, cell) = Search::new(root).run(playouts, time)?;
+    Some(((board as usize / 3, board as usize % 3), (cell as usize / 3, cell as usize % 3)))
+}
+
+const FULL: u16 = 0b111_111_111;
+
+/// the 8 ways to get three in a row, as masks over the spaces numbered x * 3 + y
+const LINES: [u16; 8] = [0b000_000_111, 0b000_111_000, 0b111_000_000, 0b001_001_001, 0b010_010_010, 0b100_100_100, 0b100_010_001, 0b001_010_100];
+
+fn has_line(mask: u16) -> bool {
+    LINES.iter().any(|line| mask & line == *line)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Finish {
+    Win(usize),
+    Tie,
+}
+
+/// the whole game in a few integers so playouts are cheap to copy and play out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    cells: [[u16; 9]; 2], // per player then per small board, the spaces they hold. player 0 is X
+    won: [u16; 2],        // the small boards each player has taken
+    tied: u16,            // small boards that filled up without a winner
+    forced: Option<u8>,   // the small board the next move has to be in, `None` is a free choice
+    to_move: usize,
+}
+
+impl Position {
+    fn from_board(board: &MetaBoard, piece: Space) -> Self {
+        let mut position = Self {
+            cells: [[0; 9]; 2],
+            won: [0; 2],
+            tied: 0,
+            forced: board.selected.map(|(x, y)| (x * 3 + y) as u8),
+            to_move: if piece == Space::O { 1 } else { 0 },
+        };
+        for (x, row) in board.spaces.iter().enumerate() {
+            for (y, space) in row.iter().enumerate() {
+                let index = x * 3 + y;
+                let small = match space {
+                    MetaSpace::X(small) => {
+                        position.won[0] |= 1 << index;
+                        small
+                    }
+                    MetaSpace::O(small) => {
+                        position.won[1] |= 1 << index;
+                        small
+                    }
+                    MetaSpace::Tie(small) => {
+                        position.tied |= 1 << index;
+                        small
+                    }
+                    MetaSpace::Empty(small) => small,
+                };
+                for (sx, srow) in small.spaces.iter().enumerate() {
+                    for (sy, cell) in srow.iter().enumerate() {
+                        match cell {
+                            Space::X => position.cells[0][index] |= 1 << (sx * 3 + sy),
+                            Space::O => position.cells[1][index] |= 1 << (sx * 3 + sy),
+                            Space::Empty => {}
+                        }
+                    }
+                }
+            }
+        }
+        position
+    }
+
+    /// small boards that can still be played in
+    fn open(&self) -> u16 {
+        !(self.won[0] | self.won[1] | self.tied) & FULL
+    }
+
+    fn moves(&self, moves: &mut Vec<(u8, u8)>) {
+        moves.clear();
+        let boards = match self.forced {
+            Some(board) => self.open() & (1 << board),
+            None => self.open(),
+        };
+        for board in 0..9u8 {
+            if boards & (1 << board) == 0 {
+                continue;
+            }
+            let empty = !(self.cells[0][board as usize] | self.cells[1][board as usize]) & FULL;
+            for cell in 0..9u8 {
+                if empty & (1 << cell) != 0 {
+                    moves.push((board, cell));
+                }
+            }
+        }
+    }
+
+    fn play(&mut self, board: u8, cell: u8) {
+        let (me, board, cell) = (self.to_move, board as usize, cell as usize);
+        self.cells[me][board] |= 1 << cell;
+        if has_line(self.cells[me][board]) {
+            self.won[me] |= 1 << board;
+        } else if self.cells[0][board] | self.cells[1][board] == FULL {
+            self.tied |= 1 << board;
+        }
+        // the space played in picks the next board, unless that board is already decided
+        self.forced = if self.open() & (1 << cell) != 0 { Some(cell as u8) } else { None };
+        self.to_move = 1 - me;
+    }
+
+    /// the same checks as `MetaBoard::check_winner`, in the same order
+    fn finish(&self) -> Option<Finish> {
+        let decided = self.won[0] | self.won[1] | self.tied;
+        // a line needs every board decided, at least one of them won by the player and none by their opponent, ties fill the gaps
+        let line_for = |player: usize| LINES.iter().any(|line| decided & line == *line && self.won[player] & line != 0 && self.won[1 - player] & line == 0);
+
+        if decided == FULL {
+            return Some(Finish::Tie);
+        }
+        match (line_for(0), line_for(1)) {
+            (true, true) => Some(Finish::Tie),
+            (true, false) => Some(Finish::Win(0)),
+            (false, true) => Some(Finish::Win(1)),
+            (false, false) => None,
+        }
+    }
+}
+
+struct Node {
+    play: (u8, u8),
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<(u8, u8)>,
+    mover: usize, // who made the move leading here, the score is from their side
+    visits: u32,
+    score: f64,
+}
+
+struct Search {
+    root: Position,
+    nodes: Vec<Node>,
+    rng: XorShift,
+}
+
+impl Search {
+    fn new(root: Position) -> Self {
+        let mut untried = vec![];
+        if root.finish().is_none() {
+            root.moves(&mut untried);
+        }
+        Self {
+            root,
+            nodes: vec![Node {
+                play: (0, 0),
+                parent: None,
+                children: vec![],
+                untried,
+                mover: 1 - root.to_move,
+                visits: 0,
+                score: 0.0,
+            }],
+            rng: XorShift::new(),
+        }
+    }
+
+    /// searches until either budget runs out, then plays the most visited move
+    fn run(&mut self, playouts: u32, time: Duration) -> Option<(u8, u8)> {
+        match self.nodes[0].untried.len() {
+            0 => return None,
+            1 => return Some(self.nodes[0].untried[0]),
+            _ => {}
+        }
+
+        let start = Instant::now();
+        let mut moves = Vec::with_capacity(81);
+        for playout in 0..playouts {
+            // checking the clock every playout costs more than the playout does
+            if playout % 256 == 0 && start.elapsed() > time {
+                break;
+            }
+            self.iterate(&mut moves);
+        }
+
+        self.nodes[0].children.iter().map(|child| &self.nodes[*child]).max_by_key(|child| child.visits).map(|child| child.play)
+    }
+
+    fn iterate(&mut self, moves: &mut Vec<(u8, u8)>) {
+        let mut position = self.root;
+        let mut node = 0;
+
+        // selection, down through fully expanded nodes
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select(node);
+            let (board, cell) = self.nodes[node].play;
+            position.play(board, cell);
+        }
+
+        // expansion, one new child per playout
+        if !self.nodes[node].untried.is_empty() {
+            let index = self.rng.below(self.nodes[node].untried.len());
+            let (board, cell) = self.nodes[node].untried.swap_remove(index);
+            let mover = position.to_move;
+            position.play(board, cell);
+
+            let mut untried = vec![];
+            if position.finish().is_none() {
+                position.moves(&mut untried);
+            }
+            self.nodes.push(Node {
+                play: (board, cell),
+                parent: Some(node),
+                children: vec![],
+                untried,
+                mover,
+                visits: 0,
+                score: 0.0,
+            });
+            let child = self.nodes.len() - 1;
+            self.nodes[node].children.push(child);
+            node = child;
+        }
+
+        // simulation, random moves until the game is over
+        let finish = loop {
+            if let Some(finish) = position.finish() {
+                break finish;
+            }
+            position.moves(moves);
+            if moves.is_empty() {
+                break Finish::Tie;
+            }
+            let (board, cell) = moves[self.rng.below(moves.len())];
+            position.play(board, cell);
+        };
+
+        // backpropagation
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.score += match finish {
+                Finish::Win(player) if player == node.mover => 1.0,
+                Finish::Win(_) => 0.0,
+                Finish::Tie => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    /// the child with the best upper confidence bound
+    fn select(&self, node: usize) -> usize {
+        let parent_visits = (self.nodes[node].visits.max(1) as f64).ln();
+        let uct = |child: &Node| child.score / child.visits as f64 + std::f64::consts::SQRT_2 * (parent_visits / child.visits as f64).sqrt();
+
+        let mut best = self.nodes[node].children[0];
+        let mut best_value = f64::MIN;
+        for child in self.nodes[node].children.iter() {
+            let value = uct(&self.nodes[*child]);
+            if value > best_value {
+                best = *child;
+                best_value = value;
+            }
+        }
+        best
+    }
+}
+
+/// a tiny rng for the playouts, the search doesn't need anything better and thread_rng is slow enough to show up here
+struct XorShift(u64);
+
+impl XorShift {
+    fn new() -> Self {
+        Self(qg_shared::rand::thread_rng().gen::<u64>() | 1)
+    }
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CycleVec, InProgress, Outcome, Player};
+
+    const X: Player = Player { id: UserId::new(1), piece: Space::X };
+    const O: Player = Player { id: UserId::new(2), piece: Space::O };
+
+    /// plays the move through the game's own rules, picking the board first when it's a free choice
+    fn play(game: &mut InProgress, players: &CycleVec<Player>, piece: Space, (board, cell): (u8, u8)) -> bool {
+        let (bx, by, x, y) = (board as usize / 3, board as usize % 3, cell as usize / 3, cell as usize % 3);
+        if game.board.selected.is_none() && game.make_move(bx, by, piece, players).is_err() {
+            return false;
+        }
+        game.board.selected == Some((bx, by)) && game.make_move(x, y, piece, players).is_ok()
+    }
+
+    #[test]
+    fn positions_follow_the_games_rules() {
+        let players = CycleVec::new(vec![X, O]);
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        let mut moves = vec![];
+        let mut finishes = vec![];
+
+        for _ in 0..100 {
+            let mut game = InProgress::new();
+            let mut position = Position::from_board(&game.board, Space::X);
+            let mut piece = Space::X;
+
+            loop {
+                assert_eq!(position, Position::from_board(&game.board, piece));
+
+                let expected = match game.board.check_winner(&players) {
+                    Some(Outcome::Win(player)) => Some(Finish::Win(if player.piece == Space::X { 0 } else { 1 })),
+                    Some(Outcome::Tie) => Some(Finish::Tie),
+                    None => None,
+                };
+                assert_eq!(position.finish(), expected);
+                if let Some(finish) = expected {
+                    finishes.push(finish);
+                    break;
+                }
+
+                // every move the search would consider is one the game takes, and nothing else is
+                position.moves(&mut moves);
+                for board in 0..9 {
+                    for cell in 0..9 {
+                        let mut copy = InProgress::new();
+                        copy.board = game.board.clone();
+                        assert_eq!(play(&mut copy, &players, piece, (board, cell)), moves.contains(&(board, cell)), "{:?}", (board, cell));
+                    }
+                }
+
+                let next = moves[rng.below(moves.len())];
+                assert!(play(&mut game, &players, piece, next));
+                position.play(next.0, next.1);
+                piece = if piece == Space::X { Space::O } else { Space::X };
+            }
+        }
+
+        // random games end every way there is, so each way of finishing gets checked
+        for finish in [Finish::Win(0), Finish::Win(1), Finish::Tie] {
+            assert!(finishes.contains(&finish), "no game ended in {:?}", finish);
+        }
+    }
+
+    #[test]
+    fn hard_fits_its_playouts_in_its_time() {
+        let (playouts, time) = Difficulty::Hard.budget();
+        assert!(playouts >= 10_000);
+
+        let mut search = Search::new(Position::from_board(&InProgress::new().board, Space::X));
+        search.run(playouts, time);
+        // debug builds are several times slower than what's deployed, so the time only holds up with optimizations on (cargo test --release)
+        if cfg!(not(debug_assertions)) {
+            assert_eq!(search.nodes[0].visits, playouts);
+        }
+    }
+}
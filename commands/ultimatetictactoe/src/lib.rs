@@ -10,6 +10,11 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+mod engine;
+
+/// how long an open challenge waits for someone to accept, in seconds
+const OPEN_CHALLENGE_TIMEOUT: u64 = 60 * 10;
+
 pub fn command() -> UltimateTicTacToe {
     UltimateTicTacToe
 }
@@ -22,13 +27,45 @@ impl qg_shared::Command for UltimateTicTacToe {
         qg_shared::CommandInfo {
             name: String::from("ultimatetictactoe"),
             description: String::from("Play a game of Ultimate Tic Tac Toe"),
-            options: vec![qg_shared::CommandOption {
-                name: String::from("opponent"),
-                description: String::from("The opponent to play against"),
-                option_type: qg_shared::CommandOptionType::User,
-                choices: qg_shared::UnorderedVec::from(vec![]),
-                required: true,
-            }]
+            options: vec![
+                qg_shared::CommandOption {
+                    name: String::from("opponent"),
+                    description: String::from("The opponent to play against (leave empty for an open challenge anyone can accept)"),
+                    option_type: qg_shared::CommandOptionType::User,
+                    choices: qg_shared::UnorderedVec::from(vec![]),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("best_of"),
+                    description: String::from("Play a series instead of a single game, whoever wins most of the games takes it"),
+                    option_type: qg_shared::CommandOptionType::Integer,
+                    choices: qg_shared::UnorderedVec::from(
+                        [1, 3, 5, 7]
+                            .iter()
+                            .map(|n| qg_shared::CommandOptionChoice {
+                                name: format!("Best of {}", n),
+                                value: n.to_string(),
+                            })
+                            .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                    ),
+                    required: false,
+                },
+                qg_shared::CommandOption {
+                    name: String::from("difficulty"),
+                    description: String::from("Play against the bot instead, you can also pick the bot as your opponent (defaults to medium)"),
+                    option_type: qg_shared::CommandOptionType::String,
+                    choices: qg_shared::UnorderedVec::from(
+                        [engine::Difficulty::Easy, engine::Difficulty::Medium, engine::Difficulty::Hard]
+                            .iter()
+                            .map(|difficulty| qg_shared::CommandOptionChoice {
+                                name: difficulty.display_name().to_string(),
+                                value: difficulty.name().to_string(),
+                            })
+                            .collect::<Vec<qg_shared::CommandOptionChoice>>(),
+                    ),
+                    required: false,
+                },
+            ]
             .into(),
         }
     }
@@ -38,24 +75,79 @@ impl qg_shared::Command for UltimateTicTacToe {
             id: interaction.user.id,
             piece: Space::X,
         }];
-        let other;
-        players.push({
-            match interaction.data.options.first().ok_or(qg_shared::anyhow::anyhow!("No opponent specified"))?.value {
+        let mut difficulty = None;
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "difficulty") {
+            match option.value {
+                CommandDataOptionValue::String(ref value) => {
+                    difficulty = Some(engine::Difficulty::from_name(value).ok_or(qg_shared::anyhow::anyhow!("Unhandled value `{}`", value))?);
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("Invalid difficulty"));
+                }
+            }
+        }
+        let bot_id = ctx.cache.current_user().id;
+        // without an opponent anyone can claim the second seat
+        let mut invitee = None;
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "opponent") {
+            match option.value {
                 CommandDataOptionValue::User(user) => {
                     let user = user.to_user(&ctx.http).await?;
-                    if user.bot {
-                        return Err(qg_shared::anyhow::anyhow!("You cannot play against a bot"));
-                    }
+                    if user.id == bot_id {
+                        // challenging us is the same as asking for the bot
+                        difficulty = Some(difficulty.unwrap_or_default());
+                    } else {
+                        if difficulty.is_some() {
+                            return Err(qg_shared::anyhow::anyhow!("Difficulty is only for games against the bot, leave out the opponent"));
+                        }
+                        if user.bot {
+                            return Err(qg_shared::anyhow::anyhow!("You cannot play against a bot"));
+                        }
 
-                    other = user.clone();
-                    Player { id: other.id, piece: Space::O }
+                        invitee = Some(user.id);
+                        players.push(Player { id: user.id, piece: Space::O });
+                    }
                 }
                 _ => {
                     return Err(qg_shared::anyhow::anyhow!("Invalid opponent"));
                 }
             }
-        });
-        if !std::env::var("ALLOW_SELF_PLAY").ok().and_then(|s| s.parse::<bool>().ok()).unwrap_or(false) {
+        }
+        let mut series = qg_shared::Series::default();
+        if let Some(option) = interaction.data.options.iter().find(|option| option.name == "best_of") {
+            match option.value {
+                CommandDataOptionValue::Integer(best_of) if (1..=7).contains(&best_of) && best_of % 2 == 1 => {
+                    series = qg_shared::Series::new(best_of as u8);
+                }
+                _ => {
+                    return Err(qg_shared::anyhow::anyhow!("A series has to be an odd number of games, up to 7"));
+                }
+            }
+        }
+        if let Some(difficulty) = difficulty {
+            let bot = engine::Bot { id: bot_id, difficulty };
+            let mut game = Game {
+                players: CycleVec::new(vec![
+                    Player {
+                        id: interaction.user.id,
+                        piece: Space::X,
+                    },
+                    Player { id: bot.id, piece: Space::O },
+                ]),
+                gamestate: State::InProgress(InProgress::new()),
+                last_time: qg_shared::current_time()?,
+                tournament_match: None,
+                series,
+                thread: None,
+                dm: None,
+                ai: Some(bot),
+                deferred: false,
+            };
+            game.play_bot().await?;
+            game.send(ctx, interaction).await?;
+            return Ok(());
+        }
+        if !qg_shared::allow_self_play() {
             let individuals = {
                 let mut individuals = players.iter().map(|player| player.id).collect::<Vec<UserId>>();
                 individuals.sort();
@@ -72,9 +164,19 @@ impl qg_shared::Command for UltimateTicTacToe {
             players: CycleVec::new(players),
             gamestate: State::AwaitingApproval(Awaiting {
                 inviter: interaction.user.id,
-                invitee: other.id,
+                invitee,
+                expires_at: match invitee {
+                    Some(_) => 0,
+                    None => qg_shared::current_time()? + OPEN_CHALLENGE_TIMEOUT,
+                },
             }),
             last_time: qg_shared::current_time()?,
+            tournament_match: None,
+            series,
+            thread: None,
+            dm: None,
+            ai: None,
+            deferred: false,
         };
 
         game.send(ctx, interaction).await?;
@@ -104,9 +206,74 @@ impl qg_shared::Command for UltimateTicTacToe {
     }
 }
 
+/// starts a game between two players in its own message, skipping the challenge since they've already been paired up (queues, tournaments)
+pub async fn open_game(ctx: &Context, channel_id: ChannelId, players: (UserId, UserId), tournament_match: Option<i64>) -> Result<Message> {
+    let game = Game {
+        players: CycleVec::new(vec![Player { id: players.0, piece: Space::X }, Player { id: players.1, piece: Space::O }]),
+        gamestate: State::InProgress(InProgress::new()),
+        last_time: qg_shared::current_time()?,
+        tournament_match,
+        series: qg_shared::Series::default(),
+        thread: None,
+        dm: None,
+        ai: None,
+        deferred: false,
+    };
+
+    game.post(ctx, channel_id).await
+}
+
+/// starts a game between two players who might not share a server, with a copy in each of their dms
+pub async fn open_dm_game(ctx: &Context, players: (UserId, UserId)) -> Result<qg_shared::DmGame> {
+    let game = Game {
+        players: CycleVec::new(vec![Player { id: players.0, piece: Space::X }, Player { id: players.1, piece: Space::O }]),
+        gamestate: State::InProgress(InProgress::new()),
+        last_time: qg_shared::current_time()?,
+        tournament_match: None,
+        series: qg_shared::Series::default(),
+        thread: None,
+        dm: None,
+        ai: None,
+        deferred: false,
+    };
+
+    game.post_dm(ctx).await
+}
+
+/// opens a tournament match in its own message
+pub async fn open_match(
+    ctx: &Context,
+    channel_id: ChannelId,
+    tournament_match: &mut qg_shared::db::TournamentMatch,
+    tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>,
+) -> Result<()> {
+    let players = tournament_match.discord_ids(tx).await?;
+    let message = open_game(ctx, channel_id, players, Some(tournament_match.id)).await?;
+    tournament_match.set_message(message.id, tx).await?;
+
+    Ok(())
+}
+
+/// records the result of a tournament match and announces the champion. whatever is ready to be played next is opened once the result has been saved
+async fn advance_tournament(ctx: &Context, tournament_match: i64, winner: Option<i64>, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<()> {
+    let advancement = qg_shared::db::Tournament::record_result(tournament_match, winner, tx).await?;
+
+    advancement.announce_champion(ctx).await;
+    qg_shared::db::TournamentMatch::open_later(advancement.opened);
+
+    Ok(())
+}
+
 pub enum Action {
     Accept,
     Decline,
+    Cancel,
+    Rematch,
+    Resign,
+    OfferDraw,
+    Takeback,
+    AcceptOffer,
+    DeclineOffer,
     Place(usize, usize),
 }
 
@@ -127,6 +294,48 @@ impl Action {
                 }
                 Some(Self::Decline)
             }
+            "Cancel" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Cancel)
+            }
+            "Rematch" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Rematch)
+            }
+            "Resign" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Resign)
+            }
+            "OfferDraw" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::OfferDraw)
+            }
+            "Takeback" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::Takeback)
+            }
+            "AcceptOffer" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::AcceptOffer)
+            }
+            "DeclineOffer" => {
+                if split.next().is_some() {
+                    return None;
+                }
+                Some(Self::DeclineOffer)
+            }
             "Place" => {
                 let x = split.next()?.parse().ok()?;
                 let y = split.next()?.parse().ok()?;
@@ -138,6 +347,10 @@ impl Action {
             _ => None,
         }
     }
+    /// the buttons under the board that aren't moves
+    fn is_control(&self) -> bool {
+        matches!(self, Self::Resign | Self::OfferDraw | Self::Takeback | Self::AcceptOffer | Self::DeclineOffer)
+    }
     pub fn to_custom_id(&self, command: &str) -> String {
         format!("{}:{}", command, self.name())
     }
@@ -145,6 +358,13 @@ impl Action {
         match self {
             Self::Accept => String::from("Accept"),
             Self::Decline => String::from("Decline"),
+            Self::Cancel => String::from("Cancel"),
+            Self::Rematch => String::from("Rematch"),
+            Self::Resign => String::from("Resign"),
+            Self::OfferDraw => String::from("OfferDraw"),
+            Self::Takeback => String::from("Takeback"),
+            Self::AcceptOffer => String::from("AcceptOffer"),
+            Self::DeclineOffer => String::from("DeclineOffer"),
             Self::Place(x, y) => format!("Place:{}:{}", x, y),
         }
     }
@@ -155,18 +375,54 @@ pub struct Game {
     players: CycleVec<Player>,
     gamestate: State,
     last_time: u64,
+    #[serde(default)]
+    tournament_match: Option<i64>, // the tournament match this game decides, see qg_shared::db::Tournament
+    #[serde(default)]
+    series: qg_shared::Series,
+    #[serde(default)]
+    thread: Option<qg_shared::GameThread>, // set when the game is played in its own thread
+    #[serde(default)]
+    dm: Option<qg_shared::DmGame>, // set when the game is played over dms, each player has their own copy
+    #[serde(default)]
+    ai: Option<engine::Bot>, // set when one of the players is the bot
+    #[serde(skip)]
+    deferred: bool, // the interaction has already been acknowledged, so rendering only edits the response
 }
 
 impl Game {
     pub async fn do_action(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut OptTrans<'_>) -> Result<()> {
         match self.gamestate {
             State::AwaitingApproval(ref u) => {
-                if interaction.user.id != u.invitee {
-                    return Err(anyhow!("You are not the invitee"));
+                if u.expired()? {
+                    self.gamestate = State::Cancelled("Nobody accepted in time".into());
+                    return self.render(ctx, interaction).await;
                 }
+                let (inviter, invitee) = (u.inviter, u.invitee);
                 match action {
                     Action::Accept => {
+                        match invitee {
+                            Some(invitee) => {
+                                if interaction.user.id != invitee {
+                                    return Err(anyhow!("You are not the invitee"));
+                                }
+                            }
+                            None => {
+                                if interaction.user.id == inviter && !qg_shared::allow_self_play() {
+                                    return Err(anyhow!("You can't accept your own challenge"));
+                                }
+                                // first come first served, then shuffle so the challenger doesn't always go first
+                                self.players.push(Player {
+                                    id: interaction.user.id,
+                                    piece: Space::O,
+                                });
+                                self.players.shuffle();
+                            }
+                        }
                         self.gamestate = State::InProgress(InProgress::new());
+                        // the server wants games in their own threads, the challenge message just points there now
+                        if self.move_to_thread(ctx, interaction, db).await? {
+                            return Ok(());
+                        }
 
                         let pid = self.players.current().ok_or(anyhow!("Player not found"))?.id;
                         if pid != interaction.user.id {
@@ -186,7 +442,7 @@ impl Game {
                                         log::error!("Error creating dm channel: {}", e);
                                         e
                                     })?
-                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", interaction.message.link())))
+                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", self.link(interaction, pid))))
                                     .await
                                     .map_err(|e| {
                                         log::error!("Error sending message to user: {}", e);
@@ -198,14 +454,26 @@ impl Game {
                         }
                     }
                     Action::Decline => {
+                        if Some(interaction.user.id) != invitee {
+                            return Err(anyhow!("You are not the invitee"));
+                        }
                         self.gamestate = State::Cancelled("Declined".into());
                     }
+                    Action::Cancel => {
+                        if interaction.user.id != inviter {
+                            return Err(anyhow!("Only {} can cancel this challenge", inviter.mention()));
+                        }
+                        self.gamestate = State::Cancelled("Cancelled by the challenger".into());
+                    }
                     _ => {
                         return Err(anyhow!("Invalid action: {}", action.name()));
                     }
                 }
             }
             State::InProgress(ref mut game) => {
+                if action.is_control() {
+                    return self.control(ctx, interaction, action, db).await;
+                }
                 if self.players.current().map(|s| s.id) != Some(interaction.user.id) {
                     return Err(anyhow!("It is not your turn"));
                 }
@@ -218,67 +486,29 @@ impl Game {
                             }
                         };
                         if let Some(winner) = game.board.check_winner(&self.players) {
-                            self.gamestate = State::Finished(WonGame {
-                                winner: winner.clone(),
-                                board: game.board.clone(),
-                            });
-                            // render early so the messages and sql stuff doesnt lag the update
-                            self.render(ctx, interaction).await?;
-                            for player in self.players.all() {
-                                match ctx.http.get_user(player.id).await {
-                                    Ok(user) => match user.create_dm_channel(&ctx.http).await {
-                                        Ok(dm) => {
-                                            if let Err(e) = dm
-                                                .send_message(&ctx.http, {
-                                                    CreateMessage::default().content({
-                                                        if let Outcome::Win(p) = winner {
-                                                            format!("You {} in {}", if *player == p { "won" } else { "got your ass handed to you" }, interaction.message.link())
-                                                        } else {
-                                                            format!("You tied in {}", interaction.message.link())
-                                                        }
-                                                    })
-                                                })
-                                                .await
-                                            {
-                                                qg_shared::log::trace!("Failed to send message to user: {}", e);
-                                            }
-                                        }
-                                        Err(e) => {
-                                            qg_shared::log::trace!("Failed to create dm channel: {}", e);
-                                        }
-                                    },
-                                    Err(e) => {
-                                        qg_shared::log::trace!("Failed to get user: {}", e);
-                                    }
+                            let board = game.board.clone();
+                            return self.finish(ctx, interaction, winner, board, None, db).await;
+                        } else if next_player {
+                            self.players.next_player();
+                            // the bot answers straight away, in the same interaction
+                            if self.ai.is_some() {
+                                // the bot can take a while to think, so the click is acknowledged before it starts
+                                self.defer(ctx, interaction).await?;
+                                self.deferred = true;
+                                if let Some((winner, board)) = self.play_bot().await? {
+                                    return self.finish(ctx, interaction, winner, board, None, db).await;
                                 }
+                                return self.render(ctx, interaction).await;
                             }
-
-                            if let Some(db) = db {
-                                if let Some(winner) = winner.winner() {
-                                    let mut players: HashMap<UserId, qg_shared::db::User> = HashMap::new();
-
-                                    for player in self.players.all() {
-                                        players.insert(player.id, qg_shared::db::User::get_or_create(ctx, &player.id, db).await?);
-                                    }
-
-                                    for player in self.players.all() {
-                                        let user = &players[&player.id];
-                                        let opponent = &players[&self.players.all().find(|p| p.id != player.id).unwrap_or(player).id];
-                                        if user.id != opponent.id {
-                                            qg_shared::db::UltimateTicTacToe::create(user.id as i32, opponent.id as i32, player.id == winner.id, db).await?;
-                                        }
-                                    }
-                                }
+                            if let Some(ref mut thread) = self.thread {
+                                thread.ping(ctx, self.players.current().ok_or(anyhow!("Player not found"))?.id).await;
                             }
 
-                            return Ok(()); // we dont want to render again
-                        } else if next_player {
-                            self.players.next_player();
-
                             let now = qg_shared::current_time()?;
                             if now.saturating_sub(self.last_time) > 60 {
+                                let pid = self.players.current().ok_or(anyhow!("Player not found"))?.id;
                                 ctx.http
-                                    .get_user(self.players.current().ok_or(anyhow!("Player not found"))?.id)
+                                    .get_user(pid)
                                     .await
                                     .map_err(|e| {
                                         log::error!("Error getting user: {}", e);
@@ -290,7 +520,7 @@ impl Game {
                                         log::error!("Error creating dm channel: {}", e);
                                         e
                                     })?
-                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", interaction.message.link())))
+                                    .send_message(&ctx.http, CreateMessage::default().content(format!("It is your turn in {}", self.link(interaction, pid))))
                                     .await
                                     .map_err(|e| {
                                         log::error!("Error sending message to user: {}", e);
@@ -305,6 +535,35 @@ impl Game {
                     }
                 }
             }
+            State::Finished(ref mut won_game) => match action {
+                Action::Rematch => {
+                    if self.tournament_match.is_some() {
+                        return Err(anyhow!("Tournament matches can't be rematched"));
+                    }
+                    if !self.players.all().any(|p| p.id == interaction.user.id) {
+                        return Err(anyhow!("Only the players can start a rematch"));
+                    }
+                    if won_game.rematched {
+                        return Err(anyhow!("A rematch has already been started"));
+                    }
+                    won_game.rematched = true;
+                    if self.ai.is_some() {
+                        self.defer(ctx, interaction).await?;
+                        self.deferred = true;
+                    }
+                    match self.dm {
+                        Some(_) => {
+                            self.rematch().await?.post_dm(ctx).await?;
+                        }
+                        None => {
+                            self.rematch().await?.post(ctx, interaction.channel_id).await?;
+                        }
+                    }
+                }
+                _ => {
+                    return Err(anyhow!("Invalid action: {}", action.name()));
+                }
+            },
             _ => {
                 return Err(anyhow!("Invalid action: {}", action.name()));
             }
@@ -315,10 +574,17 @@ impl Game {
         Ok(())
     }
 
+    /// acknowledges the click, unless the bot's move already did before it started thinking
+    async fn defer(&self, ctx: &Context, interaction: &mut ComponentInteraction) -> Result<()> {
+        if !self.deferred {
+            interaction.defer(&ctx.http).await?;
+        }
+        Ok(())
+    }
     async fn render(&self, ctx: &Context, interaction: &mut ComponentInteraction) -> Result<()> {
         match &self.gamestate {
             State::Cancelled(reason) => {
-                interaction.defer(&ctx.http).await?;
+                self.defer(ctx, interaction).await?;
                 interaction
                     .edit_response(&ctx.http, EditInteractionResponse::default().content(format!("Game cancelled: {}", reason)).components(vec![]))
                     .await
@@ -328,102 +594,45 @@ impl Game {
                     })?;
             }
             State::AwaitingApproval(ref u) => {
-                let mut content = self.title_card()?;
-
-                content.push_str(u.challenge_message().as_str());
+                let content = self.challenge_message(u)?;
 
                 interaction
-                    .edit_response(&ctx.http, {
-                        EditInteractionResponse::default().content(content).components({
-                            let rows = vec![CreateActionRow::Buttons(vec![
-                                CreateButton::new(Action::Accept.to_custom_id("ultimatetictactoe")).style(ButtonStyle::Success).label("Accept"),
-                                CreateButton::new(Action::Decline.to_custom_id("ultimatetictactoe")).style(ButtonStyle::Danger).label("Decline"),
-                            ])];
-                            rows
-                        })
-                    })
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(content).components(u.components("ultimatetictactoe")))
                     .await
                     .map_err(|e| {
                         log::error!("Error editing interaction response: {}", e);
                         e
                     })?;
             }
-            State::InProgress(game) => {
-                let mut content = self.title_card()?;
-                let current_player = self.players.current().ok_or(anyhow!("Player not found"))?;
-
-                content.push_str(&format!("It is {}'s turn [{}]", current_player.id.mention(), current_player.piece));
-                if game.board.selected.is_none() {
-                    content.push_str(" (Select a board)");
-                }
-
-                content.push_str(&game.board.string_map());
-
-                interaction.defer(&ctx.http).await?;
-
+            State::InProgress(_) => {
+                let (content, components) = self.in_progress_message()?;
+                self.defer(ctx, interaction).await?;
                 interaction
-                    // .message
-                    .edit_response(&ctx.http, {
-                        EditInteractionResponse::default().content(content).components({
-                            let mut rows = vec![];
-                            for x in 0..=2 {
-                                let mut buttons = vec![];
-                                for y in 0..=2 {
-                                    buttons.push(game.board.button_for(x, y));
-                                }
-                                rows.push(CreateActionRow::Buttons(buttons));
-                            }
-                            rows
-                        })
-
-                        // d.content(content).components(|c| {
-                        //     for x in 0..=2 {
-                        //         c.create_action_row(|a| {
-                        //             for y in 0..=2 {
-                        //                 a.create_button(|b| {
-                        //                     game.board.button_for(x, y, b);
-                        //                     b
-                        //                 });
-                        //             }
-                        //             a
-                        //         });
-                        //     }
-                        //     c
-                        // })
-                    })
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(content.clone()).components(components.clone()))
                     .await
                     .map_err(|e| {
                         log::error!("Error editing interaction response: {}", e);
                         e
                     })?;
+                if let Some(dm) = self.dm {
+                    dm.sync(ctx, interaction.message.id, content, components).await;
+                }
             }
             State::Finished(won_game) => {
                 let mut content = self.title_card()?;
                 content.push_str(won_game.win_message().as_str());
+                content.push_str(&self.series_message());
 
                 content.push_str(&won_game.board.raw_string_map());
+                let rows = self.rematch_row(won_game).into_iter().collect::<Vec<CreateActionRow>>();
 
-                interaction.defer(&ctx.http).await?;
+                self.defer(ctx, interaction).await?;
                 interaction
-                    .edit_response(&ctx.http, {
-                        EditInteractionResponse::default().content(content).components({
-                            vec![]
-                            // for x in 0..=2 {
-                            //     c.create_action_row(|a| {
-                            //         for y in 0..=2 {
-                            //             a.create_button(|b| {
-                            //                 won_game.board.button_for(x, y, b);
-                            //                 b.disabled(true);
-                            //                 b
-                            //             });
-                            //         }
-                            //         a
-                            //     });
-                            // }
-                            // c
-                        })
-                    })
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(content.clone()).components(rows.clone()))
                     .await?;
+                if let Some(dm) = self.dm {
+                    dm.sync(ctx, interaction.message.id, content, rows).await;
+                }
             }
         }
         Ok(())
@@ -431,32 +640,409 @@ impl Game {
     async fn send(&self, ctx: &Context, interaction: &mut CommandInteraction) -> Result<()> {
         match self.gamestate {
             State::AwaitingApproval(ref u) => {
-                let mut content = self.title_card()?;
-                content.push_str(u.challenge_message().as_str());
+                let content = self.challenge_message(u)?;
                 interaction
                     .create_response(&ctx.http, {
-                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components({
-                            let rows = vec![CreateActionRow::Buttons(vec![
-                                CreateButton::new(Action::Accept.to_custom_id("ultimatetictactoe")).style(ButtonStyle::Success).label("Accept"),
-                                CreateButton::new(Action::Decline.to_custom_id("ultimatetictactoe")).style(ButtonStyle::Danger).label("Decline"),
-                            ])];
-
-                            rows
-
-                            // c.create_action_row(|a| {
-                            //     a.create_button(|b| b.style(ButtonStyle::Success).label("Accept").custom_id(Action::Accept.to_custom_id("ultimatetictactoe")))
-                            //         .create_button(|b| b.style(ButtonStyle::Danger).label("Decline").custom_id(Action::Decline.to_custom_id("ultimatetictactoe")))
-                            // })
-                        }))
+                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components(u.components("ultimatetictactoe")))
                     })
                     .await?;
             }
+            State::InProgress(_) => {
+                let (content, components) = self.in_progress_message()?;
+                interaction
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(content).components(components)),
+                    )
+                    .await?;
+            }
             _ => {
                 return Err(qg_shared::anyhow::anyhow!("Invalid game state"));
             }
         }
         Ok(())
     }
+    fn in_progress_message(&self) -> Result<(String, Vec<CreateActionRow>)> {
+        let game = match self.gamestate {
+            State::InProgress(ref game) => game,
+            _ => return Err(anyhow!("Invalid game state")),
+        };
+        let mut content = self.title_card()?;
+        if self.tournament_match.is_some() {
+            content.push_str("Tournament match! ");
+        }
+        if let Some(bot) = self.ai {
+            content.push_str(&format!("Unranked game against the bot ({}). ", bot.difficulty.display_name()));
+        }
+        if self.series.is_series() {
+            content.push_str(&format!(
+                "Game {} of a best of {} ({}). ",
+                self.series.played() + 1,
+                self.series.best_of(),
+                self.series.score(self.players.all().map(|p| p.id))
+            ));
+        }
+        let current_player = self.players.current().ok_or(anyhow!("Player not found"))?;
+        content.push_str(&format!("It is {}'s turn [{}]", current_player.id.mention(), current_player.piece));
+        if let Some(offer) = game.offer {
+            content.push_str(&offer.message());
+        }
+        if game.board.selected.is_none() {
+            content.push_str(" (Select a board)");
+        }
+
+        content.push_str(&game.board.string_map());
+        let mut rows = vec![];
+        for x in 0..=2 {
+            let mut buttons = vec![];
+            for y in 0..=2 {
+                buttons.push(game.board.button_for(x, y));
+            }
+            rows.push(CreateActionRow::Buttons(buttons));
+        }
+        rows.push(game.controls("ultimatetictactoe", self.ai.is_none()));
+
+        Ok((content, rows))
+    }
+    /// ends the game, letting the players know and recording the result. `reason` explains an ending that didn't happen on the board
+    async fn finish(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, winner: Outcome, board: MetaBoard, reason: Option<String>, db: &mut OptTrans<'_>) -> Result<()> {
+        self.gamestate = State::Finished(WonGame {
+            winner: winner.clone(),
+            board,
+            rematched: false,
+            reason,
+        });
+        self.series.record(winner.winner().map(|p| p.id));
+        // render early so the messages and sql stuff doesnt lag the update
+        self.render(ctx, interaction).await?;
+        self.close_thread(ctx).await;
+        for player in self.players.all() {
+            if matches!(self.ai, Some(bot) if bot.id == player.id) {
+                continue;
+            }
+            match ctx.http.get_user(player.id).await {
+                Ok(user) => match user.create_dm_channel(&ctx.http).await {
+                    Ok(dm) => {
+                        if let Err(e) = dm
+                            .send_message(&ctx.http, {
+                                CreateMessage::default().content({
+                                    if let Outcome::Win(p) = winner {
+                                        format!("You {} in {}", if *player == p { "won" } else { "got your ass handed to you" }, self.link(interaction, player.id))
+                                    } else {
+                                        format!("You tied in {}", self.link(interaction, player.id))
+                                    }
+                                })
+                            })
+                            .await
+                        {
+                            qg_shared::log::trace!("Failed to send message to user: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        qg_shared::log::trace!("Failed to create dm channel: {}", e);
+                    }
+                },
+                Err(e) => {
+                    qg_shared::log::trace!("Failed to get user: {}", e);
+                }
+            }
+        }
+
+        if let (Some(db), Some(bot)) = (db.as_mut(), self.ai) {
+            // games against the bot are unranked, so they're kept off the leaderboards
+            if let Some(human) = self.players.all().find(|p| p.id != bot.id) {
+                let user = qg_shared::db::User::get_or_create(ctx, &human.id, db).await?;
+                let won = winner.winner().map(|winner| winner.id == human.id);
+                qg_shared::db::AiGame::create(
+                    &user,
+                    qg_shared::db::GameKind::UltimateTicTacToe,
+                    bot.difficulty.name(),
+                    won,
+                    interaction.guild_id.map(|g| g.get() as i64),
+                    db,
+                )
+                .await?;
+            }
+            return Ok(());
+        }
+
+        if let Some(db) = db {
+            if let Some(winner) = winner.winner() {
+                let mut players: HashMap<UserId, qg_shared::db::User> = HashMap::new();
+
+                for player in self.players.all() {
+                    players.insert(player.id, qg_shared::db::User::get_or_create(ctx, &player.id, db).await?);
+                }
+
+                for player in self.players.all() {
+                    let user = &players[&player.id];
+                    let opponent = &players[&self.players.all().find(|p| p.id != player.id).unwrap_or(player).id];
+                    if user.id != opponent.id {
+                        qg_shared::db::UltimateTicTacToe::create(user.id as i32, opponent.id as i32, player.id == winner.id, interaction.guild_id.map(|g| g.get() as i64), db).await?;
+                        let boards_lost = match self.gamestate {
+                            State::Finished(ref won_game) => won_game.board.boards_won_by_opponent_of(player.piece),
+                            _ => 0,
+                        };
+                        let result = qg_shared::db::GameResult::UltimateTicTacToe {
+                            won: player.id == winner.id,
+                            boards_lost,
+                        };
+                        let unlocked = qg_shared::db::Achievement::evaluate(user, &result, db).await?;
+                        qg_shared::db::Achievement::notify_later(player.id, unlocked);
+                    }
+                }
+
+                // the game that decides a series records the series too
+                if let Some(series_winner) = self.series.winner() {
+                    if let Some(series_loser) = self.players.all().find(|p| p.id != series_winner) {
+                        qg_shared::db::SeriesResult::create(
+                            qg_shared::db::GameKind::UltimateTicTacToe,
+                            players[&series_winner].id,
+                            players[&series_loser.id].id,
+                            self.series.best_of(),
+                            (self.series.wins(series_winner), self.series.wins(series_loser.id)),
+                            interaction.guild_id.map(|g| g.get() as i64),
+                            db,
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+
+        if let (Some(db), Some(tournament_match)) = (db.as_mut(), self.tournament_match) {
+            let winner = match winner.winner() {
+                Some(winner) => Some(qg_shared::db::User::get_or_create(ctx, &winner.id, db).await?.id),
+                None => None,
+            };
+            advance_tournament(ctx, tournament_match, winner, db).await?;
+        }
+        Ok(())
+    }
+    /// resigning, draw offers and takebacks, which either player can use whoever's turn it is
+    async fn control(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, action: Action, db: &mut OptTrans<'_>) -> Result<()> {
+        let player = *self.players.all().find(|p| p.id == interaction.user.id).ok_or(anyhow!("You are not playing in this game"))?;
+        if self.ai.is_some() && !matches!(action, Action::Resign) {
+            return Err(anyhow!("The bot doesn't take offers"));
+        }
+        let game = match self.gamestate {
+            State::InProgress(ref mut game) => game,
+            _ => return Err(anyhow!("Invalid game state")),
+        };
+        match action {
+            Action::Resign => {
+                let winner = *self.players.all().find(|p| p.id != player.id).unwrap_or(&player);
+                let board = game.board.clone();
+                return self.finish(ctx, interaction, Outcome::Win(winner), board, Some(format!("{} resigned", player.id.mention())), db).await;
+            }
+            Action::OfferDraw => {
+                if game.offer.is_some() {
+                    return Err(anyhow!("There's already an offer waiting for an answer"));
+                }
+                game.offer = Some(Offer::Draw(player.id));
+            }
+            Action::Takeback => {
+                if game.offer.is_some() {
+                    return Err(anyhow!("There's already an offer waiting for an answer"));
+                }
+                if !game.can_undo() {
+                    return Err(anyhow!("There's no move to take back"));
+                }
+                // only whoever just moved can ask for it back
+                if self.players.current().map(|p| p.id) == Some(player.id) && !qg_shared::allow_self_play() {
+                    return Err(anyhow!("You can only take back your own move"));
+                }
+                game.offer = Some(Offer::Takeback(player.id));
+            }
+            Action::AcceptOffer => {
+                let offer = game.offer.ok_or(anyhow!("There's no offer to accept"))?;
+                if offer.from() == player.id && !qg_shared::allow_self_play() {
+                    return Err(anyhow!("You can't accept your own offer"));
+                }
+                match offer {
+                    Offer::Draw(_) => {
+                        let board = game.board.clone();
+                        return self.finish(ctx, interaction, Outcome::Tie, board, Some(String::from("draw agreed")), db).await;
+                    }
+                    Offer::Takeback(_) => {
+                        game.undo()?;
+                        // two players, so going forward one is going back one
+                        self.players.next_player();
+                    }
+                }
+            }
+            Action::DeclineOffer => {
+                // declining your own offer takes it back
+                if game.offer.take().is_none() {
+                    return Err(anyhow!("There's no offer to decline"));
+                }
+            }
+            _ => {
+                return Err(anyhow!("Invalid action: {}", action.name()));
+            }
+        }
+
+        self.render(ctx, interaction).await
+    }
+    fn challenge_message(&self, u: &Awaiting) -> Result<String> {
+        let mut content = self.title_card()?;
+        content.push_str(u.challenge_message().as_str());
+        if self.series.is_series() {
+            content.push_str(&format!(" (best of {})", self.series.best_of()));
+        }
+        Ok(content)
+    }
+    /// how the series stands after this game, empty for a single game
+    fn series_message(&self) -> String {
+        if !self.series.is_series() {
+            return String::new();
+        }
+        let score = self.series.score(self.players.all().map(|p| p.id));
+        match self.series.winner() {
+            Some(winner) => format!(" {} wins the best of {} series! ({})", winner.mention(), self.series.best_of(), score),
+            None => format!(" Best of {}: {}", self.series.best_of(), score),
+        }
+    }
+    /// the button under a finished game, tournament matches are only played once
+    fn rematch_row(&self, won_game: &WonGame) -> Option<CreateActionRow> {
+        if self.tournament_match.is_some() {
+            return None;
+        }
+        let label = if self.series.is_series() && self.series.winner().is_none() { "Next game" } else { "Rematch" };
+        Some(CreateActionRow::Buttons(vec![CreateButton::new(Action::Rematch.to_custom_id("ultimatetictactoe"))
+            .style(ButtonStyle::Primary)
+            .label(label)
+            .disabled(won_game.rematched)]))
+    }
+    /// the same players again with whoever went second going first, carrying the series along
+    async fn rematch(&self) -> Result<Game> {
+        let mut game = Game {
+            players: self.players.rotated(),
+            gamestate: State::InProgress(InProgress::new()),
+            last_time: qg_shared::current_time()?,
+            tournament_match: None,
+            series: self.series.next(),
+            thread: self.thread,
+            dm: None,
+            ai: self.ai,
+            deferred: false,
+        };
+        game.play_bot().await?;
+        Ok(game)
+    }
+    /// plays the bot's move if it's the bot's turn, returning the outcome and final board if that ended the game
+    async fn play_bot(&mut self) -> Result<Option<(Outcome, MetaBoard)>> {
+        let bot = match self.ai {
+            Some(bot) => bot,
+            None => return Ok(None),
+        };
+        let current = *self.players.current().ok_or(anyhow!("Player not found"))?;
+        if current.id != bot.id {
+            return Ok(None);
+        }
+        let game = match self.gamestate {
+            State::InProgress(ref mut game) => game,
+            _ => return Err(anyhow!("Invalid game state")),
+        };
+
+        // the search can take half a second, too long to hold up one of the async workers
+        let (board, piece) = (game.board.clone(), current.piece);
+        let ((bx, by), (x, y)) = tokio::task::spawn_blocking(move || engine::choose_move(&board, piece, bot.difficulty))
+            .await?
+            .ok_or(anyhow!("The bot has no moves left"))?;
+        // the bot picks its board and space in one go
+        if game.board.selected.is_none() {
+            game.make_move(bx, by, current.piece, &self.players)?;
+        }
+        game.make_move(x, y, current.piece, &self.players)?;
+        if let Some(outcome) = game.board.check_winner(&self.players) {
+            return Ok(Some((outcome, game.board.clone())));
+        }
+        self.players.next_player();
+        Ok(None)
+    }
+    /// moves the game into its own thread if the server has game threads turned on.
+    /// returns whether it did, the challenge message is left behind pointing to the thread
+    async fn move_to_thread(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, db: &mut OptTrans<'_>) -> Result<bool> {
+        let (guild_id, db) = match (interaction.guild_id, db.as_mut()) {
+            (Some(guild_id), Some(db)) => (guild_id, db),
+            _ => return Ok(false),
+        };
+        if !qg_shared::db::GuildSettings::get(guild_id.get() as i64, db).await?.game_threads {
+            return Ok(false);
+        }
+
+        let mut names = vec![];
+        for player in self.players.all() {
+            names.push(
+                qg_shared::identity::resolve(ctx, &player.id)
+                    .await
+                    .map(|identity| identity.name)
+                    .unwrap_or_else(|| player.id.to_string()),
+            );
+        }
+        let first = self.players.current().ok_or(anyhow!("Player not found"))?.id;
+        let mut summary = format!("{} goes first in {}", first.mention(), self.players_message());
+        if self.series.is_series() {
+            summary.push_str(&format!(", best of {}", self.series.best_of()));
+        }
+        summary.push_str(". Spectators welcome, the board is below!");
+
+        let thread = qg_shared::GameThread::open(ctx, interaction.channel_id, interaction.message.id, format!("Ultimate Tic Tac Toe: {}", names.join(" vs ")), summary).await?;
+        self.thread = Some(thread);
+        self.post(ctx, thread.id).await?;
+
+        interaction.defer(&ctx.http).await?;
+        interaction
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::default()
+                    .content(format!("Ultimate Tic Tac Toe between {} is being played in {}", self.players_message(), thread.id.mention()))
+                    .components(vec![]),
+            )
+            .await?;
+
+        Ok(true)
+    }
+    /// mirrors the result to the parent channel and archives the thread, if the game was played in one
+    async fn close_thread(&self, ctx: &Context) {
+        let (thread, won_game) = match (&self.thread, &self.gamestate) {
+            (Some(thread), State::Finished(won_game)) => (thread, won_game),
+            _ => return,
+        };
+        let result = format!(
+            "Ultimate Tic Tac Toe between {}: {}{} (played in {})",
+            self.players_message(),
+            won_game.win_message(),
+            self.series_message(),
+            thread.id.mention()
+        );
+        thread.close(ctx, result).await;
+    }
+    /// where the player can find the game, their own copy when it's played over dms
+    fn link(&self, interaction: &ComponentInteraction, player: UserId) -> String {
+        self.dm.and_then(|dm| dm.link(player)).unwrap_or_else(|| interaction.message.link())
+    }
+    fn players_message(&self) -> String {
+        self.players.all().map(|p| p.id.mention().to_string()).collect::<Vec<String>>().join(" and ")
+    }
+    /// posts a game that's already under way in its own message
+    async fn post(&self, ctx: &Context, channel_id: ChannelId) -> Result<Message> {
+        let (content, components) = self.in_progress_message()?;
+        let message = channel_id.send_message(&ctx.http, CreateMessage::default().content(content).components(components)).await?;
+
+        Ok(message)
+    }
+    /// posts a game that's already under way to both players' dms
+    async fn post_dm(mut self, ctx: &Context) -> Result<qg_shared::DmGame> {
+        let players = self.players.all().map(|p| p.id).collect::<Vec<UserId>>();
+        let dm = qg_shared::DmGame::open(ctx, (players[0], players[1])).await?;
+        self.dm = Some(dm);
+        let (content, components) = self.in_progress_message()?;
+        dm.update(ctx, content, components).await?;
+
+        Ok(dm)
+    }
     fn title_card(&self) -> Result<String> {
         Ok(format!("```{}\nUltimate Tic Tac Toe\n```", qg_shared::serialize(&self)?.replace('\n', "")))
     }
@@ -580,18 +1166,42 @@ pub enum State {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Awaiting {
     inviter: UserId,
-    invitee: UserId,
+    invitee: Option<UserId>, // None is an open challenge
+    #[serde(default)]
+    expires_at: u64, // 0 never expires
 }
 
 impl Awaiting {
     fn challenge_message(&self) -> String {
-        format!("{} has challenged {} to a game of Ultimate Tic Tac Toe", self.inviter.mention(), self.invitee.mention())
+        match self.invitee {
+            Some(invitee) => format!("{} has challenged {} to a game of Ultimate Tic Tac Toe", self.inviter.mention(), invitee.mention()),
+            None => format!(
+                "{} is looking for an opponent for a game of Ultimate Tic Tac Toe, first to accept plays! (expires <t:{}:R>)",
+                self.inviter.mention(),
+                self.expires_at
+            ),
+        }
+    }
+    fn expired(&self) -> Result<bool> {
+        Ok(self.expires_at != 0 && qg_shared::current_time()? > self.expires_at)
+    }
+    fn components(&self, command: &str) -> Vec<CreateActionRow> {
+        let mut buttons = vec![CreateButton::new(Action::Accept.to_custom_id(command)).style(ButtonStyle::Success).label("Accept")];
+        if self.invitee.is_some() {
+            buttons.push(CreateButton::new(Action::Decline.to_custom_id(command)).style(ButtonStyle::Danger).label("Decline"));
+        }
+        buttons.push(CreateButton::new(Action::Cancel.to_custom_id(command)).style(ButtonStyle::Secondary).label("Cancel"));
+        vec![CreateActionRow::Buttons(buttons)]
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InProgress {
     board: MetaBoard,
+    #[serde(default)]
+    offer: Option<Offer>, // a draw or takeback waiting for the other player, cleared by the next move
+    #[serde(default)]
+    previous: Option<MetaBoard>, // the board before the last move, what a takeback goes back to
 }
 
 impl InProgress {
@@ -602,12 +1212,15 @@ impl InProgress {
 
         Ok(match self.board.selected {
             Some((bx, by)) => {
+                let previous = self.board.clone();
                 // do move
                 let board = self.board.spaces[bx][by].board_mut();
                 if board.spaces[x][y] != Space::Empty {
                     return Err(qg_shared::anyhow::anyhow!("Invalid move, space already taken"));
                 }
                 board.spaces[x][y] = piece;
+                self.previous = Some(previous);
+                self.offer = None;
                 // check if board has been won
                 match board.check_winner(players) {
                     None => {
@@ -617,7 +1230,8 @@ impl InProgress {
                         self.board.handle_outcome(bx, by, outcome);
                     }
                 }
-                if self.board.spaces[x][y].ignore_board() == Space::Empty {
+                // a tied board is full, so it can't be sent to any more than a won one
+                if matches!(self.board.spaces[x][y], MetaSpace::Empty(_)) {
                     self.board.selected = Some((x, y));
                 } else {
                     self.board.selected = None;
@@ -627,7 +1241,7 @@ impl InProgress {
             None => {
                 // select board
                 // if board has already been won, error
-                if self.board.spaces[x][y].ignore_board() != Space::Empty {
+                if !matches!(self.board.spaces[x][y], MetaSpace::Empty(_)) {
                     return Err(qg_shared::anyhow::anyhow!("Invalid move, board already won"));
                 }
                 // else select board
@@ -638,7 +1252,65 @@ impl InProgress {
     }
 
     fn new() -> InProgress {
-        InProgress { board: MetaBoard::new() }
+        InProgress {
+            board: MetaBoard::new(),
+            offer: None,
+            previous: None,
+        }
+    }
+    fn can_undo(&self) -> bool {
+        self.previous.is_some()
+    }
+    /// takes back the last move, only one move can be taken back
+    fn undo(&mut self) -> Result<()> {
+        self.board = self.previous.take().ok_or(anyhow!("There's no move to take back"))?;
+        self.offer = None;
+        Ok(())
+    }
+    /// resign, draw and takeback, or the answers to whatever offer is waiting. offers need someone to answer them, so against the bot there's only resign
+    fn controls(&self, command: &str, offers: bool) -> CreateActionRow {
+        let resign = CreateButton::new(Action::Resign.to_custom_id(command)).style(ButtonStyle::Danger).label("Resign");
+        if !offers {
+            return CreateActionRow::Buttons(vec![resign]);
+        }
+        CreateActionRow::Buttons(match self.offer {
+            Some(offer) => vec![
+                CreateButton::new(Action::AcceptOffer.to_custom_id(command)).style(ButtonStyle::Success).label(match offer {
+                    Offer::Draw(_) => "Accept draw",
+                    Offer::Takeback(_) => "Allow takeback",
+                }),
+                CreateButton::new(Action::DeclineOffer.to_custom_id(command)).style(ButtonStyle::Secondary).label("Decline"),
+                resign,
+            ],
+            None => vec![
+                resign,
+                CreateButton::new(Action::OfferDraw.to_custom_id(command)).style(ButtonStyle::Secondary).label("Offer draw"),
+                CreateButton::new(Action::Takeback.to_custom_id(command))
+                    .style(ButtonStyle::Secondary)
+                    .label("Takeback")
+                    .disabled(!self.can_undo()),
+            ],
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Offer {
+    Draw(UserId), // who made the offer
+    Takeback(UserId),
+}
+
+impl Offer {
+    fn from(&self) -> UserId {
+        match self {
+            Self::Draw(id) | Self::Takeback(id) => *id,
+        }
+    }
+    fn message(&self) -> String {
+        match self {
+            Self::Draw(id) => format!(". {} offers a draw", id.mention()),
+            Self::Takeback(id) => format!(". {} asks to take back their last move", id.mention()),
+        }
     }
 }
 
@@ -646,6 +1318,10 @@ impl InProgress {
 pub struct WonGame {
     winner: Outcome,
     board: MetaBoard,
+    #[serde(default)]
+    rematched: bool,
+    #[serde(default)]
+    reason: Option<String>, // why the game ended off the board, a resignation or an agreed draw
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -673,6 +1349,19 @@ impl MetaBoard {
         }
     }
 
+    /// how many of the small boards were taken by whoever isn't playing `piece`
+    fn boards_won_by_opponent_of(&self, piece: Space) -> usize {
+        self.spaces
+            .iter()
+            .flatten()
+            .filter(|space| match space {
+                MetaSpace::X(_) => piece != Space::X,
+                MetaSpace::O(_) => piece != Space::O,
+                _ => false,
+            })
+            .count()
+    }
+
     // pub fn into_board(&self) -> Board {
     //     Board {
     //         spaces: self.spaces.iter().map(|row| row.iter().map(|s| s.ignore_board()).collect()).collect(),
@@ -930,9 +1619,13 @@ fn all_equal(x: &MetaSpace, y: &MetaSpace, z: &MetaSpace) -> Option<Space> {
 
 impl WonGame {
     fn win_message(&self) -> String {
-        match self.winner {
+        let message = match self.winner {
             Outcome::Win(player) => format!("{} [{}] has won!", player.id.mention(), player.piece),
             Outcome::Tie => String::from("It's a tie!"),
+        };
+        match self.reason {
+            Some(ref reason) => format!("{} ({})", message, reason),
+            None => message,
         }
     }
 }
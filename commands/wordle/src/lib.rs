@@ -0,0 +1,363 @@
+//! the daily word game. everyone gets the same word each day, and since the message is public the word is never in it.
+//! the first player of the day picks it at random, and it's kept in the database along with the guesses
+
+use qg_shared::{
+    anyhow::{anyhow, Result},
+    rand::seq::IteratorRandom as _,
+    serenity::all::*,
+    OptTrans,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// every word that can be the answer, kept to ones most players will know
+const ANSWERS: &str = include_str!("answers.txt");
+/// every word that can be guessed, the answers and plenty of less common words besides
+const GUESSES: &str = include_str!("guesses.txt");
+const WORD_LENGTH: usize = 5;
+const MAX_GUESSES: usize = 6;
+
+pub fn command() -> Wordle {
+    Wordle
+}
+
+pub struct Wordle;
+
+#[qg_shared::async_trait]
+impl qg_shared::Command for Wordle {
+    fn get_command_info(&self) -> qg_shared::CommandInfo {
+        qg_shared::CommandInfo {
+            name: String::from("wordle"),
+            description: String::from("Guess the word of the day in six tries"),
+            options: vec![].into(),
+        }
+    }
+
+    async fn application_command(&mut self, ctx: &Context, interaction: &mut CommandInteraction, db: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        let tx = db.as_mut().ok_or(anyhow!("Wordle needs the database to keep track of your guesses"))?;
+        let user = qg_shared::db::User::get_or_create(ctx, &interaction.user.id, tx).await?;
+        let game = Game {
+            player: interaction.user.id,
+            day: today()?,
+        };
+        let answer = game.answer(tx).await?;
+        let guesses = game.guesses(&user, tx).await?;
+        // coming back to a finished day just shows how it went
+        let streak = match qg_shared::db::Wordle::get(&user, game.day, tx).await? {
+            Some(_) => qg_shared::db::WordleStreak::get(&user, tx).await?,
+            None => None,
+        };
+
+        let (content, components) = game.message(&answer, &guesses, streak.as_ref())?;
+        // guesses give the word away, so only the player sees their board
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().ephemeral(true).content(content).components(components)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn message_component(&mut self, ctx: &Context, interaction: &mut ComponentInteraction, _: &mut qg_shared::OptTrans<'_>) -> Result<()> {
+        if interaction.data.custom_id != GUESS_ID {
+            return Err(anyhow!("Invalid action id"));
+        }
+        let game = Game::from_message(&interaction.message)?;
+        game.check(interaction.user.id)?;
+
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Modal(CreateModal::new(GUESS_ID, format!("Wordle #{}", game.day)).components(vec![CreateActionRow::InputText(
+                    CreateInputText::new(InputTextStyle::Short, "Your guess", GUESS_INPUT_ID)
+                        .placeholder("a five letter word")
+                        .min_length(WORD_LENGTH as u16)
+                        .max_length(WORD_LENGTH as u16),
+                )])),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn modal_submit(&mut self, ctx: &Context, interaction: &mut ModalInteraction, db: &mut OptTrans<'_>) -> Result<()> {
+        if interaction.data.custom_id != GUESS_ID {
+            return Err(anyhow!("Invalid modal id"));
+        }
+        let game = Game::from_message(interaction.message.as_ref().ok_or(anyhow!("No game data found"))?)?;
+        game.check(interaction.user.id)?;
+
+        let guess = interaction
+            .data
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input) if input.custom_id == GUESS_INPUT_ID => input.value.clone(),
+                _ => None,
+            })
+            .ok_or(anyhow!("No guess found"))?
+            .trim()
+            .to_lowercase();
+        if !allowed().any(|word| word == guess) {
+            return Err(anyhow!("`{}` isn't in the word list", guess));
+        }
+
+        let tx = db.as_mut().ok_or(anyhow!("Wordle needs the database to keep track of your guesses"))?;
+        let user = qg_shared::db::User::get_or_create(ctx, &interaction.user.id, tx).await?;
+        let answer = game.answer(tx).await?;
+        let mut guesses = game.guesses(&user, tx).await?;
+        if over(&answer, &guesses) {
+            return Err(anyhow!("You've already finished today's word, come back tomorrow!"));
+        }
+        qg_shared::db::WordleGuess::create(&user, game.day, guesses.len() as i32 + 1, &guess, tx).await?;
+        guesses.push(guess);
+
+        let streak = match over(&answer, &guesses) {
+            true => {
+                let solved = solved(&answer, &guesses);
+                let streak = qg_shared::db::Wordle::create(&user, game.day, solved, guesses.len() as i32, interaction.guild_id.map(|g| g.get() as i64), tx).await?;
+                let result = qg_shared::db::GameResult::Wordle {
+                    solved,
+                    guesses: guesses.len() as i32,
+                    streak: streak.current,
+                };
+                let unlocked = qg_shared::db::Achievement::evaluate(&user, &result, tx).await?;
+                qg_shared::db::Achievement::notify_later(interaction.user.id, unlocked);
+                Some(streak)
+            }
+            false => None,
+        };
+
+        let (content, components) = game.message(&answer, &guesses, streak.as_ref())?;
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(CreateInteractionResponseMessage::default().content(content).components(components)),
+            )
+            .await?;
+
+        // the board is private, so the result is shared without the letters
+        if streak.is_some() {
+            let result = if solved(&answer, &guesses) { guesses.len().to_string() } else { String::from("X") };
+            interaction
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new().content(format!(
+                        "{} played Wordle #{} {}/{}\n{}",
+                        interaction.user.id.mention(),
+                        game.day,
+                        result,
+                        MAX_GUESSES,
+                        squares(&answer, &guesses)
+                    )),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// the button and the modal it opens share an id, both only ever mean "make a guess"
+const GUESS_ID: &str = "wordle:Guess";
+const GUESS_INPUT_ID: &str = "guess";
+
+fn answers() -> impl Iterator<Item = &'static str> {
+    ANSWERS.lines().map(str::trim).filter(|word| !word.is_empty())
+}
+
+fn allowed() -> impl Iterator<Item = &'static str> {
+    GUESSES.lines().map(str::trim).filter(|word| !word.is_empty())
+}
+
+/// days since the unix epoch, the word changes at midnight utc
+fn today() -> Result<i32> {
+    Ok((qg_shared::current_time()? / (60 * 60 * 24)) as i32)
+}
+
+fn solved(answer: &str, guesses: &[String]) -> bool {
+    guesses.last().map(|guess| guess == answer).unwrap_or(false)
+}
+
+fn over(answer: &str, guesses: &[String]) -> bool {
+    solved(answer, guesses) || guesses.len() >= MAX_GUESSES
+}
+
+/// just the colours, a line per guess
+fn squares(answer: &str, guesses: &[String]) -> String {
+    guesses
+        .iter()
+        .map(|guess| score(guess, answer).iter().map(Mark::emoji).collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Correct, // right letter in the right place
+    Present, // in the word, somewhere else
+    Absent,
+}
+
+impl Mark {
+    fn emoji(&self) -> &'static str {
+        match self {
+            Self::Correct => "🟩",
+            Self::Present => "🟨",
+            Self::Absent => "⬛",
+        }
+    }
+}
+
+/// marks each letter of the guess. a letter is only marked present as many times as it's left over in the answer,
+/// so guessing a double letter against a single one shows one yellow and one grey
+fn score(guess: &str, answer: &str) -> Vec<Mark> {
+    let guess = guess.chars().collect::<Vec<char>>();
+    let answer = answer.chars().collect::<Vec<char>>();
+    let mut marks = vec![Mark::Absent; guess.len()];
+    let mut leftover = vec![];
+    for (i, letter) in answer.iter().enumerate() {
+        if guess.get(i) == Some(letter) {
+            marks[i] = Mark::Correct;
+        } else {
+            leftover.push(*letter);
+        }
+    }
+    for (i, letter) in guess.iter().enumerate() {
+        if marks[i] == Mark::Correct {
+            continue;
+        }
+        if let Some(position) = leftover.iter().position(|l| l == letter) {
+            leftover.remove(position);
+            marks[i] = Mark::Present;
+        }
+    }
+    marks
+}
+
+/// all that's kept in the message, whose board it is and which day's word
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Game {
+    player: UserId,
+    day: i32,
+}
+
+impl Game {
+    fn from_message(message: &Message) -> Result<Self> {
+        // get first line of message content, strip the ``` prefix and deserialize
+        let mut lines = message.content.lines();
+        let game = lines.next().ok_or(anyhow!("No game data found"))?;
+        let game = game.strip_prefix("```").ok_or(anyhow!("No game data found"))?;
+        qg_shared::deserialize::<Self>(game)
+    }
+
+    /// only the player can guess, and only while it's still their word's day
+    fn check(&self, user: UserId) -> Result<()> {
+        if user != self.player {
+            return Err(anyhow!("You are not the player"));
+        }
+        if self.day != today()? {
+            return Err(anyhow!("That word has gone, start today's with /wordle"));
+        }
+        Ok(())
+    }
+
+    /// the day's word, picked at random by whoever plays it first
+    async fn answer(&self, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<String> {
+        let word = answers().choose(&mut qg_shared::rand::thread_rng()).ok_or(anyhow!("The word list is empty"))?;
+        Ok(qg_shared::db::WordleDay::get_or_create(self.day, word, tx).await?.word)
+    }
+
+    async fn guesses(&self, user: &qg_shared::db::User, tx: &mut qg_shared::sqlx::Transaction<'_, qg_shared::sqlx::Postgres>) -> Result<Vec<String>> {
+        Ok(qg_shared::db::WordleGuess::for_day(user, self.day, tx).await?.into_iter().map(|guess| guess.guess).collect())
+    }
+
+    /// `streak` is only passed once the day is done
+    fn message(&self, answer: &str, guesses: &[String], streak: Option<&qg_shared::db::WordleStreak>) -> Result<(String, Vec<CreateActionRow>)> {
+        let mut content = format!("```{}\nWordle\n```", qg_shared::serialize(&self)?.replace('\n', ""));
+        content.push_str(&format!("**Wordle #{}**\n", self.day));
+        for guess in guesses {
+            let marks = score(guess, answer);
+            content.push_str(&format!("{} `{}`\n", marks.iter().map(Mark::emoji).collect::<String>(), guess.to_uppercase()));
+        }
+        for _ in guesses.len()..MAX_GUESSES {
+            content.push_str(&format!("{}\n", "⬜".repeat(WORD_LENGTH)));
+        }
+
+        if solved(answer, guesses) {
+            content.push_str(&format!("Solved in {}/{}!", guesses.len(), MAX_GUESSES));
+        } else if over(answer, guesses) {
+            content.push_str(&format!("Out of guesses, the word was **{}**", answer.to_uppercase()));
+        } else {
+            // letters that have been guessed and aren't anywhere in the word
+            let mut absent = guesses.iter().flat_map(|guess| guess.chars()).filter(|letter| !answer.contains(*letter)).collect::<Vec<char>>();
+            absent.sort();
+            absent.dedup();
+            content.push_str(&format!("Guess {} of {}", guesses.len() + 1, MAX_GUESSES));
+            if !absent.is_empty() {
+                content.push_str(&format!(" · Not in the word: `{}`", absent.iter().collect::<String>().to_uppercase()));
+            }
+        }
+        if let Some(streak) = streak {
+            content.push_str(&format!("\nStreak: {} (best {})", streak.current, streak.best));
+        }
+
+        let components = match over(answer, guesses) {
+            true => vec![],
+            false => vec![CreateActionRow::Buttons(vec![CreateButton::new(GUESS_ID).style(ButtonStyle::Primary).label("Guess")])],
+        };
+        Ok((content, components))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Mark::{Absent as A, Correct as C, Present as P};
+
+    #[test]
+    fn score_marks_each_letter() {
+        assert_eq!(score("crane", "crane"), vec![C, C, C, C, C]);
+        assert_eq!(score("about", "crane"), vec![P, A, A, A, A]);
+        assert_eq!(score("nacre", "crane"), vec![P, P, P, P, C]);
+    }
+
+    #[test]
+    fn score_double_letter_guessed_against_a_single() {
+        // only one of the guess's e's is in the answer, the one in the right place takes it
+        assert_eq!(score("geese", "those"), vec![A, A, A, C, C]);
+        // neither is in the right place, so only the first is marked present
+        assert_eq!(score("eerie", "crane"), vec![A, A, P, A, C]);
+        assert_eq!(score("speed", "abide"), vec![A, A, P, A, P]);
+    }
+
+    #[test]
+    fn score_single_letter_guessed_against_a_double() {
+        // the answer has two l's, the guess only one, and it's marked once
+        assert_eq!(score("light", "skill"), vec![P, P, A, A, A]);
+        assert_eq!(score("plant", "allow"), vec![A, C, P, A, A]);
+    }
+
+    #[test]
+    fn score_double_letters_in_both() {
+        assert_eq!(score("sleep", "geese"), vec![P, A, C, P, A]);
+        assert_eq!(score("allow", "skill"), vec![A, P, P, A, A]);
+    }
+
+    #[test]
+    fn every_answer_can_be_guessed() {
+        let allowed = allowed().collect::<std::collections::HashSet<&str>>();
+        for word in answers() {
+            assert!(allowed.contains(word), "{} can't be guessed", word);
+        }
+        assert!(allowed.len() > answers().count());
+    }
+
+    #[test]
+    fn words_are_five_lowercase_letters() {
+        for word in allowed() {
+            assert!(word.len() == WORD_LENGTH && word.chars().all(|letter| letter.is_ascii_lowercase()), "`{}`", word);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS achievements (
+    id SERIAL PRIMARY KEY,
+    key text NOT NULL UNIQUE, -- stable identifier so the definitions below can be re-seeded safely
+    name text NOT NULL,
+    description text NOT NULL,
+    criteria jsonb NOT NULL, -- what has to happen to unlock it, see qg_shared::db::Criteria. new achievements are just new rows
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS user_achievements (
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    achievement_id integer NOT NULL REFERENCES achievements(id),
+    unlocked_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (user_id, achievement_id)
+);
+
+INSERT INTO achievements (key, name, description, criteria) VALUES
+    ('first_win', 'First Blood', 'Win your first game', '{"type": "wins", "game": null, "count": 1}'),
+    ('slidingpuzzle_5x5_hard_300', 'Tile Whisperer', 'Solve a 5x5 Hard sliding puzzle in 300 moves or less', '{"type": "sliding_puzzle_moves", "size": 2, "difficulty": 2, "max_moves": 300}'),
+    ('win_streak_10', 'Unstoppable', 'Win 10 games in a row', '{"type": "win_streak", "game": null, "count": 10}'),
+    ('ultimatetictactoe_flawless', 'Flawless', 'Win a game of Ultimate Tic Tac Toe without losing a single board', '{"type": "ultimate_flawless"}')
+ON CONFLICT (key) DO NOTHING;
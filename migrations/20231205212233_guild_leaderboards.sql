@@ -0,0 +1,5 @@
+-- Add migration script here
+-- where the game was played, NULL for games played before this was tracked (or outside of a server) so they only show up on the global boards
+ALTER TABLE tictactoe ADD COLUMN IF NOT EXISTS guild_id bigint;
+ALTER TABLE ultimate_tictactoe ADD COLUMN IF NOT EXISTS guild_id bigint;
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS guild_id bigint;
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- standings take each user's best run for a size and difficulty (DISTINCT ON user_id), these match both sort orders so postgres can walk the index instead of sorting every row
+CREATE INDEX IF NOT EXISTS slidingpuzzle_standings_score_idx ON slidingpuzzle (size, difficulty, user_id, score, time, created_at);
+CREATE INDEX IF NOT EXISTS slidingpuzzle_standings_time_idx ON slidingpuzzle (size, difficulty, user_id, time, score, created_at);
+-- server leaderboards
+CREATE INDEX IF NOT EXISTS slidingpuzzle_guild_idx ON slidingpuzzle (guild_id, size, difficulty) WHERE guild_id IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- name and avatar are cached here so leaderboards don't have to ask discord about every player
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar text;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS refreshed_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP;
+CREATE INDEX IF NOT EXISTS users_discord_id_idx ON users (discord_id);
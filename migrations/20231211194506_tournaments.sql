@@ -0,0 +1,36 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS tournaments (
+    id SERIAL PRIMARY KEY,
+    guild_id bigint NOT NULL,
+    channel_id bigint NOT NULL, -- where matches are opened and the champion is announced
+    game text NOT NULL, -- see qg_shared::db::GameKind
+    format text NOT NULL, -- 'single_elimination' or 'round_robin'
+    status text NOT NULL DEFAULT 'signup', -- 'signup', 'running' or 'finished'
+    created_by bigint NOT NULL, -- discord id of the organizer
+    signup_closes_at timestamp NOT NULL,
+    champion_id integer REFERENCES users(id),
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS tournament_players (
+    id SERIAL PRIMARY KEY,
+    tournament_id integer NOT NULL REFERENCES tournaments(id),
+    user_id integer NOT NULL REFERENCES users(id),
+    joined_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (tournament_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS tournament_matches (
+    id SERIAL PRIMARY KEY,
+    tournament_id integer NOT NULL REFERENCES tournaments(id),
+    round integer NOT NULL,
+    player_one integer NOT NULL REFERENCES users(id),
+    player_two integer REFERENCES users(id), -- NULL is a bye, player_one goes through without playing
+    winner_id integer REFERENCES users(id), -- NULL until finished, and for round robin draws
+    finished boolean NOT NULL DEFAULT false,
+    message_id bigint, -- the message the game is being played in
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS tournaments_guild_idx ON tournaments (guild_id, created_at);
+CREATE INDEX IF NOT EXISTS tournament_matches_round_idx ON tournament_matches (tournament_id, round);
@@ -0,0 +1,17 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS queue (
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    game text NOT NULL, -- see qg_shared::db::GameKind
+    guild_id bigint, -- the server's queue, NULL is the global queue
+    channel_id bigint NOT NULL, -- where they joined from
+    rating float8 NOT NULL, -- their rating when they joined, used to find them a fair opponent
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at timestamp NOT NULL,
+    UNIQUE (user_id, game)
+);
+
+CREATE TABLE IF NOT EXISTS guild_settings (
+    guild_id bigint PRIMARY KEY,
+    queue_channel_id bigint -- where matchmade games are posted, NULL posts them wherever the match was found
+);
@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS series_results (
+    id SERIAL PRIMARY KEY,
+    game text NOT NULL, -- see qg_shared::db::GameKind
+    winner_id integer NOT NULL REFERENCES users(id),
+    loser_id integer NOT NULL REFERENCES users(id),
+    best_of integer NOT NULL,
+    winner_wins integer NOT NULL,
+    loser_wins integer NOT NULL,
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    guild_id bigint -- where the series was played, NULL means it only counts globally
+);
+
+CREATE INDEX IF NOT EXISTS series_results_winner_id_idx ON series_results (winner_id);
+CREATE INDEX IF NOT EXISTS series_results_loser_id_idx ON series_results (loser_id);
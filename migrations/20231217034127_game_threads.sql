@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS game_threads boolean NOT NULL DEFAULT false;
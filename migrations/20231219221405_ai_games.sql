@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS ai_games (
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    game text NOT NULL, -- see qg_shared::db::GameKind
+    difficulty text NOT NULL,
+    won boolean, -- NULL is a tie
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    guild_id bigint -- where the game was played
+);
+
+CREATE INDEX IF NOT EXISTS ai_games_user_id_idx ON ai_games (user_id);
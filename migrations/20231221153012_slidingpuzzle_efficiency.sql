@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS optimal integer; -- the fewest moves the scramble could be solved in, NULL if the solver gave up
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS efficiency float8; -- optimal / score, 1 is a perfect solve
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS hinted boolean NOT NULL DEFAULT false; -- whether the player asked for a hint
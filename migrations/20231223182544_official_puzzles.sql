@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS official_puzzles (
+    id SERIAL PRIMARY KEY,
+    guild_id bigint NOT NULL,
+    image_hash bigint NOT NULL, -- see qg_shared::db::OfficialPuzzle::hash
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (guild_id, image_hash)
+);
@@ -0,0 +1,15 @@
+-- Add migration script here
+-- times were whole seconds, they're milliseconds from now on
+UPDATE slidingpuzzle SET time = time * 1000;
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS message_id bigint; -- the game's message, its moves are logged under it in slidingpuzzle_moves
+
+CREATE TABLE IF NOT EXISTS slidingpuzzle_moves (
+    id SERIAL PRIMARY KEY,
+    message_id bigint NOT NULL, -- there's no result row until the puzzle is solved, so moves are keyed by the game's message
+    number integer NOT NULL, -- 1 is the first move
+    tile integer NOT NULL, -- the number on the tile that slid into the empty space
+    elapsed integer NOT NULL, -- milliseconds on the clock when the move was made, paused time isn't counted
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS slidingpuzzle_moves_message_id_idx ON slidingpuzzle_moves (message_id, number);
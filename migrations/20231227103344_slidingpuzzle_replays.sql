@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS seed bigint; -- regenerates the scramble, NULL for games from before replays were checked
+ALTER TABLE slidingpuzzle ADD COLUMN IF NOT EXISTS solution integer[]; -- the number on each tile moved, in order, replayed from the scramble before the row is saved
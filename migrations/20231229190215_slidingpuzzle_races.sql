@@ -0,0 +1,20 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS slidingpuzzle_races (
+    -- like tictactoe there's a row for each player, so standings only have to look at user_id
+    id SERIAL PRIMARY KEY,
+    race_id bigint NOT NULL, -- the scoreboard message, both players' rows share it
+    user_id integer NOT NULL REFERENCES users(id),
+    opponent_id integer NOT NULL REFERENCES users(id),
+    won boolean NOT NULL,
+    size integer NOT NULL,
+    difficulty integer NOT NULL,
+    seed bigint NOT NULL, -- both players were given the scramble this makes
+    time integer, -- milliseconds the winner took, NULL for the player who didn't finish first
+    moves integer, -- NULL for the player who didn't finish first
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    guild_id bigint -- where the race was played, NULL means it only counts globally
+);
+
+-- only one solve can claim a race, whoever's insert lands first
+CREATE UNIQUE INDEX IF NOT EXISTS slidingpuzzle_races_winner_idx ON slidingpuzzle_races (race_id) WHERE won;
+CREATE INDEX IF NOT EXISTS slidingpuzzle_races_user_id_idx ON slidingpuzzle_races (user_id);
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- larger boards and shorter lines, every game before this was the classic 3x3 three in a row
+ALTER TABLE tictactoe ADD COLUMN IF NOT EXISTS size integer NOT NULL DEFAULT 3;
+ALTER TABLE tictactoe ADD COLUMN IF NOT EXISTS win_length integer NOT NULL DEFAULT 3;
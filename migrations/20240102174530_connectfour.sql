@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS connectfour (
+    -- multiplayer game, so we'll be keeping track of only if the player won or lost
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    opponent_id integer NOT NULL REFERENCES users(id), -- not really used, we double up on the entries so its easier to query
+    won boolean NOT NULL, -- true if the user won, false if the user lost. draws aren't recorded, same as tictactoe
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    guild_id bigint -- where the game was played, NULL means it only counts globally
+);
+
+CREATE INDEX IF NOT EXISTS connectfour_user_id_idx ON connectfour (user_id);
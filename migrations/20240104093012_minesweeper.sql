@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS minesweeper (
+    -- singleplayer game, only cleared boards are recorded
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    difficulty integer NOT NULL, -- 0 = easy, 1 = medium, 2 = hard
+    time integer NOT NULL, -- milliseconds from the first reveal to clearing the board
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    guild_id bigint -- where the game was played, NULL means it only counts globally
+);
+
+CREATE INDEX IF NOT EXISTS minesweeper_difficulty_time_idx ON minesweeper (difficulty, time);
@@ -0,0 +1,33 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS wordle_guesses (
+    -- guesses live here rather than in the message, so a second message for the same day can't start the word over
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    day integer NOT NULL, -- days since the unix epoch, everyone gets the same word on the same day
+    number integer NOT NULL, -- 1 is the first guess
+    guess text NOT NULL,
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- two submits landing together can't both be the same guess number
+CREATE UNIQUE INDEX IF NOT EXISTS wordle_guesses_user_day_number_idx ON wordle_guesses (user_id, day, number);
+
+CREATE TABLE IF NOT EXISTS wordle (
+    -- one row per player per day, written when the word is solved or the guesses run out
+    id SERIAL PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users(id),
+    day integer NOT NULL,
+    solved boolean NOT NULL,
+    guesses integer NOT NULL,
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    guild_id bigint -- where the game was played, NULL means it only counts globally
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS wordle_user_day_idx ON wordle (user_id, day);
+
+CREATE TABLE IF NOT EXISTS wordle_streaks (
+    user_id integer PRIMARY KEY REFERENCES users(id),
+    current integer NOT NULL, -- days solved in a row, up to last_day
+    best integer NOT NULL,
+    last_day integer NOT NULL -- the last day a result was recorded
+);
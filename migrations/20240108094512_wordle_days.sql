@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS wordle_days (
+    -- the word is picked the first time anyone plays that day, so it doesn't depend on anything that can change mid-day
+    day integer PRIMARY KEY, -- days since the unix epoch
+    word text NOT NULL,
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- the global queue could pair people with no server in common, and there was nowhere to post the game both of them could see
+DELETE FROM queue WHERE guild_id IS NULL;
+ALTER TABLE queue ALTER COLUMN guild_id SET NOT NULL;
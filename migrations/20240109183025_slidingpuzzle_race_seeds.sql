@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS slidingpuzzle_race_seeds (
+    -- kept out of the scoreboard, which everyone can read, so nobody can work out the scramble before pressing Start
+    race_id bigint PRIMARY KEY, -- the scoreboard message
+    seed bigint NOT NULL,
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- Add migration script here
+INSERT INTO achievements (key, name, description, criteria) VALUES
+    ('connectfour_wins_25', 'Four Seasons', 'Win 25 games of Connect Four', '{"type": "wins", "game": "connectfour", "count": 25}')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,4 @@
+-- Add migration script here
+INSERT INTO achievements (key, name, description, criteria) VALUES
+    ('minesweeper_hard_180', 'Bomb Squad', 'Clear a Hard Minesweeper board in under 3 minutes', '{"type": "minesweeper_time", "difficulty": 2, "max_time": 180000}')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,4 @@
+-- Add migration script here
+INSERT INTO achievements (key, name, description, criteria) VALUES
+    ('wordle_streak_7', 'Word of the Week', 'Solve the daily word 7 days in a row', '{"type": "wordle_streak", "count": 7}')
+ON CONFLICT (key) DO NOTHING;
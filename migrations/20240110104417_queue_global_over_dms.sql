@@ -0,0 +1,3 @@
+-- Add migration script here
+-- the global queue is back, its games are played over dms so the players don't need a server in common
+ALTER TABLE queue ALTER COLUMN guild_id DROP NOT NULL;
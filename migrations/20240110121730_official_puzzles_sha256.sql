@@ -0,0 +1,3 @@
+-- Add migration script here
+-- image_hash is now the start of a sha-256 rather than fnv-1a, so pictures picked before this no longer match and have to be picked again
+DELETE FROM official_puzzles;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- a click on a stale message could log a second move under the same number, keep the one logged last
+DELETE FROM slidingpuzzle_moves a USING slidingpuzzle_moves b WHERE a.message_id = b.message_id AND a.number = b.number AND a.id < b.id;
+DROP INDEX IF EXISTS slidingpuzzle_moves_message_id_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS slidingpuzzle_moves_message_id_number_idx ON slidingpuzzle_moves (message_id, number);
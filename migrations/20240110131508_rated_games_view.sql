@@ -0,0 +1,8 @@
+-- Add migration script here
+-- every game rated by wins and losses in one place, so the standings for all of them come from the same query
+CREATE OR REPLACE VIEW rated_games AS
+    SELECT 'tictactoe' AS game, user_id, won, created_at, guild_id, size, win_length FROM tictactoe
+    UNION ALL
+    SELECT 'ultimatetictactoe' AS game, user_id, won, created_at, guild_id, NULL::integer AS size, NULL::integer AS win_length FROM ultimate_tictactoe
+    UNION ALL
+    SELECT 'connectfour' AS game, user_id, won, created_at, guild_id, NULL::integer AS size, NULL::integer AS win_length FROM connectfour;
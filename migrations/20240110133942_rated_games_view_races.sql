@@ -0,0 +1,10 @@
+-- Add migration script here
+-- sliding puzzle races are rated by wins and losses too, so their standings come from the same view
+CREATE OR REPLACE VIEW rated_games AS
+    SELECT 'tictactoe' AS game, user_id, won, created_at, guild_id, size, win_length FROM tictactoe
+    UNION ALL
+    SELECT 'ultimatetictactoe' AS game, user_id, won, created_at, guild_id, NULL::integer AS size, NULL::integer AS win_length FROM ultimate_tictactoe
+    UNION ALL
+    SELECT 'connectfour' AS game, user_id, won, created_at, guild_id, NULL::integer AS size, NULL::integer AS win_length FROM connectfour
+    UNION ALL
+    SELECT 'slidingpuzzle_race' AS game, user_id, won, created_at, guild_id, NULL::integer AS size, NULL::integer AS win_length FROM slidingpuzzle_races;
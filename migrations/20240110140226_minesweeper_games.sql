@@ -0,0 +1,9 @@
+-- Add migration script here
+-- the message only says what the board looked like when it was sent. a click on an older copy, like the second half of a
+-- double click, would carry on from there, so how far each game has really got is kept here
+CREATE TABLE IF NOT EXISTS minesweeper_games (
+    nonce bigint PRIMARY KEY, -- the game's nonce, as it's in the message
+    revealed integer NOT NULL, -- how many spaces the newest copy of the board has revealed
+    won boolean, -- NULL while the game is being played
+    created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
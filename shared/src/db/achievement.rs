@@ -0,0 +1,326 @@
+// CREATE TABLE IF NOT EXISTS achievements (
+//     id SERIAL PRIMARY KEY,
+//     key text NOT NULL UNIQUE, -- stable identifier so the definitions below can be re-seeded safely
+//     name text NOT NULL,
+//     description text NOT NULL,
+//     criteria jsonb NOT NULL, -- what has to happen to unlock it, see qg_shared::db::Criteria. new achievements are just new rows
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+// CREATE TABLE IF NOT EXISTS user_achievements (
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     achievement_id integer NOT NULL REFERENCES achievements(id),
+//     unlocked_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     UNIQUE (user_id, achievement_id)
+// );
+
+use std::{cell::RefCell, future::Future};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::*;
+use sqlx::{
+    types::{chrono, Json},
+    Acquire,
+};
+
+use crate::anyhow::Result;
+
+use super::User;
+
+tokio::task_local! {
+    /// what's been unlocked while handling the current interaction. nobody is told until its transaction has committed,
+    /// so a game that gets rolled back doesn't hand out achievements it never saved
+    static UNLOCKED: RefCell<Vec<(UserId, Vec<Achievement>)>>;
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Achievement {
+    pub id: i64,
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub criteria: Json<Criteria>,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl Achievement {
+    /// checks every achievement the user hasn't unlocked yet against the game they just finished, unlocking and returning the ones they've earned.
+    /// this expects the result to have already been written, so the counts include it.
+    pub async fn evaluate(user: &User, result: &GameResult, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<Self>> {
+        let locked = sqlx::query_as!(
+            Self,
+            r#"
+            SELECT id, key, name, description, criteria as "criteria: Json<Criteria>", created_at FROM achievements
+            WHERE id NOT IN (SELECT achievement_id FROM user_achievements WHERE user_id = $1)
+            ORDER BY id ASC
+            "#,
+            user.id as i32
+        )
+        .fetch_all(tx.acquire().await?)
+        .await?;
+
+        let mut unlocked = Vec::new();
+
+        for achievement in locked {
+            if achievement.criteria.is_met(user, result, tx).await? {
+                sqlx::query!(
+                    "INSERT INTO user_achievements (user_id, achievement_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                    user.id as i32,
+                    achievement.id as i32
+                )
+                .execute(tx.acquire().await?)
+                .await?;
+                unlocked.push(achievement);
+            }
+        }
+
+        Ok(unlocked)
+    }
+
+    pub async fn get_unlocked(user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<UnlockedAchievement>> {
+        let unlocked = sqlx::query_as!(
+            UnlockedAchievement,
+            r#"
+            SELECT achievements.name, achievements.description, user_achievements.unlocked_at FROM user_achievements
+            INNER JOIN achievements ON achievements.id = user_achievements.achievement_id
+            WHERE user_achievements.user_id = $1
+            ORDER BY user_achievements.unlocked_at ASC
+            "#,
+            user.id as i32
+        )
+        .fetch_all(tx.acquire().await?)
+        .await?;
+
+        Ok(unlocked)
+    }
+
+    pub async fn count(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<i64> {
+        let count = sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM achievements"#).fetch_one(tx.acquire().await?).await?;
+        Ok(count)
+    }
+
+    /// runs an interaction's handler, returning what it unlocked along with its result. pass them to `notify` once the transaction has committed
+    pub async fn collect<F: Future>(handler: F) -> (F::Output, Vec<(UserId, Vec<Self>)>) {
+        UNLOCKED
+            .scope(RefCell::new(Vec::new()), async {
+                let output = handler.await;
+                (output, UNLOCKED.with(|unlocked| unlocked.take()))
+            })
+            .await
+    }
+
+    /// holds on to what the player unlocked until the interaction is done, see `collect`
+    pub fn notify_later(discord_id: UserId, unlocked: Vec<Self>) {
+        if unlocked.is_empty() {
+            return;
+        }
+        if UNLOCKED.try_with(|pending| pending.borrow_mut().push((discord_id, unlocked))).is_err() {
+            log::warn!("Achievements were unlocked outside of an interaction, nobody will be told");
+        }
+    }
+
+    /// let the players know what they've unlocked. failing to dm someone shouldn't fail the game, so errors are only logged
+    pub async fn notify(ctx: &Context, pending: Vec<(UserId, Vec<Self>)>) {
+        for (discord_id, unlocked) in pending {
+            let content = unlocked
+                .iter()
+                .map(|achievement| format!("🏆 Achievement unlocked: **{}**\n{}", achievement.name, achievement.description))
+                .collect::<Vec<String>>()
+                .join("\n\n");
+
+            match discord_id.create_dm_channel(&ctx.http).await {
+                Ok(dm) => {
+                    if let Err(e) = dm.send_message(&ctx.http, CreateMessage::default().content(content)).await {
+                        log::trace!("Failed to send message to user: {}", e);
+                    }
+                }
+                Err(e) => {
+                    log::trace!("Failed to create dm channel: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct UnlockedAchievement {
+    pub name: String,
+    pub description: String,
+    pub unlocked_at: chrono::NaiveDateTime,
+}
+
+/// the data-driven part of an achievement, stored as json in `achievements.criteria`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Criteria {
+    /// win `count` games in total, optionally only counting a single game. finishing a sliding puzzle, clearing a minesweeper board or solving the word counts as a win
+    Wins { game: Option<GameKind>, count: i64 },
+    /// win `count` multiplayer games in a row, optionally only counting a single game
+    WinStreak { game: Option<GameKind>, count: i64 },
+    /// finish a sliding puzzle of the given size and difficulty in at most `max_moves` moves
+    SlidingPuzzleMoves { size: i32, difficulty: i32, max_moves: i32 },
+    /// win a game of ultimate tictactoe without the opponent taking a single board
+    UltimateFlawless,
+    /// clear a minesweeper board of the given difficulty in at most `max_time` milliseconds
+    MinesweeperTime { difficulty: i32, max_time: i32 },
+    /// solve the daily word `count` days in a row
+    WordleStreak { count: i32 },
+}
+
+impl Criteria {
+    async fn is_met(&self, user: &User, result: &GameResult, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<bool> {
+        Ok(match self {
+            Self::Wins { game, count } => {
+                if !result.won() || game.map(|g| g != result.kind()).unwrap_or(false) {
+                    return Ok(false);
+                }
+                let wins = sqlx::query!(
+                    r#"
+                    SELECT
+                        (SELECT COUNT(*) FROM tictactoe WHERE user_id = $1 AND won) as "tictactoe!",
+                        (SELECT COUNT(*) FROM ultimate_tictactoe WHERE user_id = $1 AND won) as "ultimatetictactoe!",
+                        (SELECT COUNT(*) FROM connectfour WHERE user_id = $1 AND won) as "connectfour!",
+                        (SELECT COUNT(*) FROM minesweeper WHERE user_id = $1) as "minesweeper!",
+                        (SELECT COUNT(*) FROM wordle WHERE user_id = $1 AND solved) as "wordle!",
+                        (SELECT COUNT(*) FROM slidingpuzzle WHERE user_id = $1) as "slidingpuzzle!"
+                    "#,
+                    user.id as i32
+                )
+                .fetch_one(tx.acquire().await?)
+                .await?;
+                let total = match game {
+                    Some(GameKind::TicTacToe) => wins.tictactoe,
+                    Some(GameKind::UltimateTicTacToe) => wins.ultimatetictactoe,
+                    Some(GameKind::SlidingPuzzle) => wins.slidingpuzzle,
+                    Some(GameKind::ConnectFour) => wins.connectfour,
+                    Some(GameKind::Minesweeper) => wins.minesweeper,
+                    Some(GameKind::Wordle) => wins.wordle,
+                    None => wins.tictactoe + wins.ultimatetictactoe + wins.slidingpuzzle + wins.connectfour + wins.minesweeper + wins.wordle,
+                };
+                total >= *count
+            }
+            Self::WinStreak { game, count } => {
+                if !result.won() || !result.kind().is_multiplayer() || game.map(|g| g != result.kind()).unwrap_or(false) {
+                    return Ok(false);
+                }
+                let recent = sqlx::query_scalar!(
+                    r#"
+                    SELECT won as "won!" FROM (
+                        SELECT won, created_at, 'tictactoe' as game FROM tictactoe WHERE user_id = $1
+                        UNION ALL
+                        SELECT won, created_at, 'ultimatetictactoe' as game FROM ultimate_tictactoe WHERE user_id = $1
+                        UNION ALL
+                        SELECT won, created_at, 'connectfour' as game FROM connectfour WHERE user_id = $1
+                    ) results
+                    WHERE ($2::text IS NULL OR game = $2)
+                    ORDER BY created_at DESC
+                    LIMIT $3
+                    "#,
+                    user.id as i32,
+                    game.map(|g| g.name()),
+                    count
+                )
+                .fetch_all(tx.acquire().await?)
+                .await?;
+                recent.len() as i64 >= *count && recent.iter().all(|won| *won)
+            }
+            Self::SlidingPuzzleMoves { size, difficulty, max_moves } => match result {
+                GameResult::SlidingPuzzle { size: s, difficulty: d, moves, .. } => s == size && d == difficulty && moves <= max_moves,
+                _ => false,
+            },
+            Self::UltimateFlawless => matches!(result, GameResult::UltimateTicTacToe { won: true, boards_lost: 0 }),
+            Self::MinesweeperTime { difficulty, max_time } => match result {
+                GameResult::Minesweeper { difficulty: d, time } => d == difficulty && time <= max_time,
+                _ => false,
+            },
+            Self::WordleStreak { count } => match result {
+                GameResult::Wordle { streak, .. } => streak >= count,
+                _ => false,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GameKind {
+    TicTacToe,
+    UltimateTicTacToe,
+    SlidingPuzzle,
+    ConnectFour,
+    Minesweeper,
+    Wordle,
+}
+
+impl GameKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TicTacToe => "tictactoe",
+            Self::UltimateTicTacToe => "ultimatetictactoe",
+            Self::SlidingPuzzle => "slidingpuzzle",
+            Self::ConnectFour => "connectfour",
+            Self::Minesweeper => "minesweeper",
+            Self::Wordle => "wordle",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tictactoe" => Some(Self::TicTacToe),
+            "ultimatetictactoe" => Some(Self::UltimateTicTacToe),
+            "slidingpuzzle" => Some(Self::SlidingPuzzle),
+            "connectfour" => Some(Self::ConnectFour),
+            "minesweeper" => Some(Self::Minesweeper),
+            "wordle" => Some(Self::Wordle),
+            _ => None,
+        }
+    }
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::TicTacToe => "Tic Tac Toe",
+            Self::UltimateTicTacToe => "Ultimate Tic Tac Toe",
+            Self::SlidingPuzzle => "Sliding Puzzle",
+            Self::ConnectFour => "Connect Four",
+            Self::Minesweeper => "Minesweeper",
+            Self::Wordle => "Wordle",
+        }
+    }
+    /// win streaks only count games against someone
+    pub fn is_multiplayer(&self) -> bool {
+        matches!(self, Self::TicTacToe | Self::UltimateTicTacToe | Self::ConnectFour)
+    }
+}
+
+/// what just happened, from the point of view of the player being evaluated
+#[derive(Debug, Clone, Copy)]
+pub enum GameResult {
+    TicTacToe { won: bool },
+    UltimateTicTacToe { won: bool, boards_lost: usize },
+    SlidingPuzzle { size: i32, difficulty: i32, moves: i32, time: i32 }, // time is in milliseconds
+    ConnectFour { won: bool },
+    Minesweeper { difficulty: i32, time: i32 },         // only cleared boards, time is in milliseconds
+    Wordle { solved: bool, guesses: i32, streak: i32 }, // streak is the days solved in a row, including this one
+}
+
+impl GameResult {
+    pub fn kind(&self) -> GameKind {
+        match self {
+            Self::TicTacToe { .. } => GameKind::TicTacToe,
+            Self::UltimateTicTacToe { .. } => GameKind::UltimateTicTacToe,
+            Self::SlidingPuzzle { .. } => GameKind::SlidingPuzzle,
+            Self::ConnectFour { .. } => GameKind::ConnectFour,
+            Self::Minesweeper { .. } => GameKind::Minesweeper,
+            Self::Wordle { .. } => GameKind::Wordle,
+        }
+    }
+    pub fn won(&self) -> bool {
+        match self {
+            Self::TicTacToe { won } => *won,
+            Self::UltimateTicTacToe { won, .. } => *won,
+            Self::SlidingPuzzle { .. } => true,
+            Self::ConnectFour { won } => *won,
+            Self::Minesweeper { .. } => true,
+            Self::Wordle { solved, .. } => *solved,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// CREATE TABLE IF NOT EXISTS ai_games (
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     game text NOT NULL, -- see qg_shared::db::GameKind
+//     difficulty text NOT NULL,
+//     won boolean, -- NULL is a tie
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the game was played
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::{anyhow, Result};
+
+use super::{GameKind, User};
+
+/// a game against the bot. these are unranked, so they're kept out of the game tables the leaderboards are built from
+#[derive(Debug, sqlx::FromRow)]
+pub struct AiGame {
+    id: i64,
+    pub user_id: i64,
+    game: String,
+    pub difficulty: String,
+    pub won: Option<bool>,
+    created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+}
+
+impl AiGame {
+    pub async fn create(user: &User, game: GameKind, difficulty: &str, won: Option<bool>, guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let ai_game = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO ai_games (user_id, game, difficulty, won, guild_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+            user.id as i32,
+            game.name(),
+            difficulty,
+            won,
+            guild_id
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(ai_game)
+    }
+    pub fn game(&self) -> Result<GameKind> {
+        GameKind::from_name(&self.game).ok_or(anyhow!("Unknown game `{}`", self.game))
+    }
+}
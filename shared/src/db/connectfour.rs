@@ -0,0 +1,62 @@
+// CREATE TABLE IF NOT EXISTS connectfour (
+//     -- multiplayer game, so we'll be keeping track of only if the player won or lost
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     opponent_id integer NOT NULL REFERENCES users(id), -- not really used, we double up on the entries so its easier to query
+//     won boolean NOT NULL, -- true if the user won, false if the user lost. draws aren't recorded, same as tictactoe
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the game was played, NULL means it only counts globally
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::Result;
+
+use super::{rating::RatedGame, RatingEntry, RatingFilters, User};
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ConnectFour {
+    id: i64,
+    pub user_id: i64,
+    pub opponent_id: i64,
+    pub won: bool,
+    created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+}
+
+impl ConnectFour {
+    pub async fn create(user_id: i32, opponent_id: i32, won: bool, guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let connectfour = sqlx::query_as!(
+            ConnectFour,
+            r#"
+            INSERT INTO connectfour (user_id, opponent_id, won, guild_id)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            user_id,
+            opponent_id,
+            won,
+            guild_id
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(connectfour)
+    }
+
+    /// (wins, losses) for a single user
+    pub async fn get_record(user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(i64, i64)> {
+        let record = sqlx::query!(
+            r#"SELECT COUNT(*) FILTER (WHERE won) as "wins!", COUNT(*) FILTER (WHERE NOT won) as "losses!" FROM connectfour WHERE user_id = $1"#,
+            user.id as i32
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok((record.wins, record.losses))
+    }
+
+    pub async fn get_standings(filters: RatingFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<RatingEntry>, bool)> {
+        RatedGame::ConnectFour.standings(filters, tx).await
+    }
+}
@@ -0,0 +1,56 @@
+// CREATE TABLE IF NOT EXISTS guild_settings (
+//     guild_id bigint PRIMARY KEY,
+//     queue_channel_id bigint, -- where matchmade games are posted, NULL posts them wherever the match was found
+//     game_threads boolean NOT NULL DEFAULT false -- accepted challenges are played in their own thread
+// );
+
+use sqlx::Acquire;
+
+use crate::anyhow::Result;
+
+/// per server configuration, a server without a row just uses the defaults
+#[derive(Debug, Default, sqlx::FromRow)]
+pub struct GuildSettings {
+    pub guild_id: i64,
+    pub queue_channel_id: Option<i64>,
+    pub game_threads: bool,
+}
+
+impl GuildSettings {
+    pub async fn get(guild_id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let settings = sqlx::query_as!(Self, "SELECT * FROM guild_settings WHERE guild_id = $1", guild_id)
+            .fetch_optional(tx.acquire().await?)
+            .await?;
+        Ok(settings.unwrap_or(Self { guild_id, ..Default::default() }))
+    }
+    pub async fn set_queue_channel(guild_id: i64, channel_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let settings = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO guild_settings (guild_id, queue_channel_id) VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE SET queue_channel_id = EXCLUDED.queue_channel_id
+            RETURNING *
+            "#,
+            guild_id,
+            channel_id
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+        Ok(settings)
+    }
+    pub async fn set_game_threads(guild_id: i64, game_threads: bool, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let settings = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO guild_settings (guild_id, game_threads) VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE SET game_threads = EXCLUDED.game_threads
+            RETURNING *
+            "#,
+            guild_id,
+            game_threads
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+        Ok(settings)
+    }
+}
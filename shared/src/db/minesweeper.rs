@@ -0,0 +1,240 @@
+// CREATE TABLE IF NOT EXISTS minesweeper (
+//     -- singleplayer game, only cleared boards are recorded
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     difficulty integer NOT NULL, -- 0 = easy, 1 = medium, 2 = hard
+//     time integer NOT NULL, -- milliseconds from the first reveal to clearing the board
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the game was played, NULL means it only counts globally
+// );
+
+// CREATE TABLE IF NOT EXISTS minesweeper_games (
+//     nonce bigint PRIMARY KEY, -- the game's nonce, as it's in the message
+//     revealed integer NOT NULL, -- how many spaces the newest copy of the board has revealed
+//     won boolean, -- NULL while the game is being played
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::Result;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Minesweeper {
+    id: i64,
+    user_id: i64,
+    pub difficulty: i32,
+    pub time: i32,
+    created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+}
+
+impl Minesweeper {
+    /// `time` is in milliseconds
+    pub async fn create(user_id: i32, difficulty: i32, time: i32, guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let game = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO minesweeper (user_id, difficulty, time, guild_id)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            user_id,
+            difficulty,
+            time,
+            guild_id
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(game)
+    }
+
+    /// each user's fastest clear for the filtered difficulty, one page at a time
+    pub async fn get_standings(filters: MinesweeperFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<MinesweeperWithUser>, bool)> {
+        // one extra row is fetched to know if there's another page
+        let mut rows = sqlx::query_as!(
+            MinesweeperStandingRaw,
+            r#"
+            SELECT best.*, users.name, users.discord_id, users.avatar, users.refreshed_at FROM (
+                SELECT DISTINCT ON (user_id) * FROM minesweeper
+                WHERE difficulty = $1
+                AND ($2::text IS NULL OR created_at >= date_trunc($2, LOCALTIMESTAMP))
+                AND ($3::bigint IS NULL OR guild_id = $3)
+                ORDER BY user_id, time ASC, created_at ASC
+            ) best
+            INNER JOIN users ON users.id = best.user_id
+            ORDER BY best.time ASC, best.created_at ASC
+            LIMIT $4 OFFSET $5
+            "#,
+            filters.difficulty,
+            filters.period.trunc_field(),
+            filters.guild_id,
+            filters.limit + 1,
+            filters.paginated_offset(),
+        )
+        .fetch_all(tx.acquire().await?)
+        .await?;
+
+        let more_available = rows.len() as i64 > filters.limit;
+        rows.truncate(filters.limit as usize);
+
+        Ok((rows.into_iter().map(MinesweeperWithUser::from).collect(), more_available))
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy)]
+pub struct MinesweeperFilters {
+    difficulty: i32,
+    period: super::Period,
+    guild_id: Option<i64>, // None is the global leaderboard
+    limit: i64,
+    offset: i64, // will be multiplied by limit, pagination
+}
+
+impl MinesweeperFilters {
+    pub fn easy(&mut self) -> &mut Self {
+        self.difficulty = 0;
+        self
+    }
+    pub fn medium(&mut self) -> &mut Self {
+        self.difficulty = 1;
+        self
+    }
+    pub fn hard(&mut self) -> &mut Self {
+        self.difficulty = 2;
+        self
+    }
+    pub fn difficulty_name(&self) -> &'static str {
+        match self.difficulty {
+            0 => "Easy",
+            1 => "Medium",
+            _ => "Hard",
+        }
+    }
+    pub fn period(&mut self, period: super::Period) -> &mut Self {
+        self.period = period;
+        self
+    }
+    pub fn get_period(&self) -> super::Period {
+        self.period
+    }
+    pub fn server(&mut self, guild_id: i64) -> &mut Self {
+        self.guild_id = Some(guild_id);
+        self
+    }
+    pub fn global(&mut self) -> &mut Self {
+        self.guild_id = None;
+        self
+    }
+    pub fn is_global(&self) -> bool {
+        self.guild_id.is_none()
+    }
+    pub fn limit(&mut self, limit: i64) -> &mut Self {
+        self.limit = limit;
+        self
+    }
+    pub fn increment_offset(&mut self) -> &mut Self {
+        self.offset += 1;
+        self
+    }
+    pub fn decrement_offset(&mut self) -> &mut Self {
+        self.offset = (self.offset - 1).max(0);
+        self
+    }
+    pub fn get_offset(&self) -> i64 {
+        self.offset
+    }
+    /// the number of entries before the current page
+    pub fn paginated_offset(&self) -> i64 {
+        self.offset * self.limit
+    }
+}
+
+impl Default for MinesweeperFilters {
+    fn default() -> Self {
+        Self {
+            difficulty: 0,
+            period: super::Period::AllTime,
+            guild_id: None,
+            limit: 10,
+            offset: 0,
+        }
+    }
+}
+
+pub struct MinesweeperWithUser {
+    pub game: Minesweeper,
+    pub user: super::User,
+}
+
+/// a standings row, the game joined with who played it
+#[derive(Debug, sqlx::FromRow)]
+struct MinesweeperStandingRaw {
+    id: i64,
+    user_id: i64,
+    difficulty: i32,
+    time: i32,
+    created_at: chrono::NaiveDateTime,
+    guild_id: Option<i64>,
+    name: String,
+    discord_id: i64,
+    avatar: Option<String>,
+    refreshed_at: chrono::NaiveDateTime,
+}
+
+impl From<MinesweeperStandingRaw> for MinesweeperWithUser {
+    fn from(row: MinesweeperStandingRaw) -> Self {
+        Self {
+            game: Minesweeper {
+                id: row.id,
+                user_id: row.user_id,
+                difficulty: row.difficulty,
+                time: row.time,
+                created_at: row.created_at,
+                guild_id: row.guild_id,
+            },
+            user: super::User {
+                id: row.user_id,
+                name: row.name,
+                discord_id: row.discord_id,
+                avatar: row.avatar,
+                refreshed_at: row.refreshed_at,
+            },
+        }
+    }
+}
+
+/// how far a game has really got. the message can be an older copy of the board, so this is what clicks are checked against
+#[derive(Debug, sqlx::FromRow)]
+pub struct MinesweeperGame {
+    pub nonce: i64,
+    pub revealed: i32,
+    pub won: Option<bool>,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl MinesweeperGame {
+    /// `FOR UPDATE` so a second click on the same game waits until this one is saved
+    pub async fn get(nonce: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let game = sqlx::query_as!(Self, "SELECT * FROM minesweeper_games WHERE nonce = $1 FOR UPDATE", nonce)
+            .fetch_optional(tx.acquire().await?)
+            .await?;
+        Ok(game)
+    }
+    pub async fn save(nonce: i64, revealed: i32, won: Option<bool>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO minesweeper_games (nonce, revealed, won)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (nonce) DO UPDATE SET revealed = $2, won = $3
+            "#,
+            nonce,
+            revealed,
+            won
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+        Ok(())
+    }
+}
@@ -1,20 +1,113 @@
+mod achievement;
+mod ai_game;
+mod connectfour;
+mod guild_settings;
+mod minesweeper;
+mod official_puzzle;
+mod queue;
+mod rating;
+mod series;
 mod slidingpuzzle;
+mod slidingpuzzle_race;
 mod tictactoe;
+mod tournament;
 mod ultimatetictactoe;
 mod user;
+mod wordle;
+
+pub use achievement::Achievement;
+pub use achievement::Criteria;
+pub use achievement::GameKind;
+pub use achievement::GameResult;
+pub use achievement::UnlockedAchievement;
+
+pub use ai_game::AiGame;
+
+pub use connectfour::ConnectFour;
+
+pub use guild_settings::GuildSettings;
+
+pub use minesweeper::Minesweeper;
+pub use minesweeper::MinesweeperFilters;
+pub use minesweeper::MinesweeperGame;
+pub use minesweeper::MinesweeperWithUser;
+
+pub use official_puzzle::OfficialPuzzle;
+
+pub use queue::QueueEntry;
+pub use queue::QUEUE_TIMEOUT_MINUTES;
+
+pub use rating::RatingEntry;
+pub use rating::RatingFilters;
+
+pub use series::SeriesResult;
 
 pub use slidingpuzzle::SlidingPuzzle;
 pub use slidingpuzzle::SlidingPuzzleFilters;
+pub use slidingpuzzle::SlidingPuzzleMove;
 pub use slidingpuzzle::SlidingPuzzleWithUser;
 
-pub use tictactoe::TTTLeaderboardEntry;
+pub use slidingpuzzle_race::SlidingPuzzleRace;
+pub use slidingpuzzle_race::SlidingPuzzleRaceSeed;
+
 pub use tictactoe::TicTacToe;
+pub use tictactoe::TicTacToeVariant;
+
+pub use tournament::Advancement;
+pub use tournament::Tournament;
+pub use tournament::TournamentFormat;
+pub use tournament::TournamentMatch;
 
-pub use ultimatetictactoe::UTTTLeaderboardEntry;
 pub use ultimatetictactoe::UltimateTicTacToe;
 
 pub use user::User;
 
+pub use wordle::Wordle;
+pub use wordle::WordleDay;
+pub use wordle::WordleGuess;
+pub use wordle::WordleStreak;
+
+/// the window of time a leaderboard covers. windows line up with the calendar, so a weekly board resets every monday
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+    #[default]
+    AllTime,
+}
+
+impl Period {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Daily => "Daily",
+            Self::Weekly => "Weekly",
+            Self::Monthly => "Monthly",
+            Self::AllTime => "All Time",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            "alltime" => Some(Self::AllTime),
+            _ => None,
+        }
+    }
+
+    /// the `date_trunc` field for the start of the current window, None means all of history
+    fn trunc_field(&self) -> Option<&'static str> {
+        match self {
+            Self::Daily => Some("day"),
+            Self::Weekly => Some("week"),
+            Self::Monthly => Some("month"),
+            Self::AllTime => None,
+        }
+    }
+}
+
 const POINTS_PER_WIN: f64 = 17.0;
 const POINTS_PER_LOSS: f64 = -13.0;
 
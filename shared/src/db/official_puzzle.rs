@@ -0,0 +1,50 @@
+// CREATE TABLE IF NOT EXISTS official_puzzles (
+//     id SERIAL PRIMARY KEY,
+//     guild_id bigint NOT NULL,
+//     image_hash bigint NOT NULL, -- see qg_shared::db::OfficialPuzzle::hash
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     UNIQUE (guild_id, image_hash)
+// );
+
+use sha2::{Digest as _, Sha256};
+use sqlx::Acquire;
+
+use crate::anyhow::Result;
+
+/// a picture a server's admins have picked for sliding puzzles. games with any other picture are unranked,
+/// since a picture that's easy to recognise makes the puzzle easier
+pub struct OfficialPuzzle;
+
+impl OfficialPuzzle {
+    /// the first 8 bytes of the sha-256 of the uploaded file, so a different picture can't be made to match an official one
+    pub fn hash(bytes: &[u8]) -> i64 {
+        let digest = Sha256::digest(bytes);
+        i64::from_be_bytes(digest[..8].try_into().expect("sha-256 is 32 bytes"))
+    }
+    pub async fn is_official(guild_id: i64, image_hash: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<bool> {
+        let official = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM official_puzzles WHERE guild_id = $1 AND image_hash = $2) as "exists!""#,
+            guild_id,
+            image_hash
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+        Ok(official)
+    }
+    pub async fn set(guild_id: i64, image_hash: i64, official: bool, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        if official {
+            sqlx::query!(
+                "INSERT INTO official_puzzles (guild_id, image_hash) VALUES ($1, $2) ON CONFLICT (guild_id, image_hash) DO NOTHING",
+                guild_id,
+                image_hash
+            )
+            .execute(tx.acquire().await?)
+            .await?;
+        } else {
+            sqlx::query!("DELETE FROM official_puzzles WHERE guild_id = $1 AND image_hash = $2", guild_id, image_hash)
+                .execute(tx.acquire().await?)
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+// CREATE TABLE IF NOT EXISTS queue (
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     game text NOT NULL, -- see qg_shared::db::GameKind
+//     guild_id bigint, -- the server's queue, NULL is the global queue whose games are played over dms
+//     channel_id bigint NOT NULL, -- where they joined from
+//     rating float8 NOT NULL, -- their rating when they joined, used to find them a fair opponent
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     expires_at timestamp NOT NULL,
+//     UNIQUE (user_id, game)
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::{anyhow, Result};
+
+use super::{ConnectFour, GameKind, TicTacToe, UltimateTicTacToe, User};
+
+/// how long someone waits in the queue before giving up on them
+pub const QUEUE_TIMEOUT_MINUTES: i64 = 10;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct QueueEntry {
+    pub id: i64,
+    pub user_id: i64,
+    game: String,
+    pub guild_id: Option<i64>,
+    pub channel_id: i64,
+    pub rating: f64,
+    created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+impl QueueEntry {
+    /// puts the user in the queue for a game, or pairs them with whoever is waiting with the closest rating.
+    /// returns the opponent's entry when a match was found, they're taken out of the queue
+    pub async fn join(user: &User, game: GameKind, guild_id: Option<i64>, channel_id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        Self::remove_expired(tx).await?;
+
+        let rating = Self::rating(user, game, tx).await?;
+
+        let opponent = sqlx::query_as!(
+            Self,
+            r#"
+            SELECT * FROM queue
+            WHERE game = $1
+            AND guild_id IS NOT DISTINCT FROM $2
+            AND user_id <> $3
+            ORDER BY ABS(rating - $4) ASC, created_at ASC
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+            game.name(),
+            guild_id,
+            user.id as i32,
+            rating
+        )
+        .fetch_optional(tx.acquire().await?)
+        .await?;
+
+        if let Some(opponent) = opponent {
+            sqlx::query!("DELETE FROM queue WHERE id = $1 OR (user_id = $2 AND game = $3)", opponent.id as i32, user.id as i32, game.name())
+                .execute(tx.acquire().await?)
+                .await?;
+            return Ok(Some(opponent));
+        }
+
+        // rejoining just moves them to the new queue and resets their timer
+        sqlx::query!(
+            r#"
+            INSERT INTO queue (user_id, game, guild_id, channel_id, rating, expires_at)
+            VALUES ($1, $2, $3, $4, $5, (now() AT TIME ZONE 'utc') + make_interval(mins => $6))
+            ON CONFLICT (user_id, game) DO UPDATE SET guild_id = EXCLUDED.guild_id, channel_id = EXCLUDED.channel_id, rating = EXCLUDED.rating, expires_at = EXCLUDED.expires_at
+            "#,
+            user.id as i32,
+            game.name(),
+            guild_id,
+            channel_id,
+            rating,
+            QUEUE_TIMEOUT_MINUTES as i32
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+
+        Ok(None)
+    }
+    /// takes the user out of every queue they're in, returning how many they left
+    pub async fn leave(user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<u64> {
+        Self::remove_expired(tx).await?;
+        let left = sqlx::query!("DELETE FROM queue WHERE user_id = $1", user.id as i32).execute(tx.acquire().await?).await?;
+        Ok(left.rows_affected())
+    }
+    pub fn game(&self) -> Result<GameKind> {
+        GameKind::from_name(&self.game).ok_or(anyhow!("Unknown game `{}`", self.game))
+    }
+    async fn remove_expired(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!("DELETE FROM queue WHERE expires_at < $1", chrono::Utc::now().naive_utc())
+            .execute(tx.acquire().await?)
+            .await?;
+        Ok(())
+    }
+    async fn rating(user: &User, game: GameKind, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<f64> {
+        let (wins, losses) = match game {
+            GameKind::TicTacToe => TicTacToe::get_record(user, tx).await?,
+            GameKind::UltimateTicTacToe => UltimateTicTacToe::get_record(user, tx).await?,
+            GameKind::ConnectFour => ConnectFour::get_record(user, tx).await?,
+            GameKind::SlidingPuzzle => return Err(anyhow!("Sliding puzzles are singleplayer")),
+            GameKind::Minesweeper => return Err(anyhow!("Minesweeper is singleplayer")),
+            GameKind::Wordle => return Err(anyhow!("Wordle is singleplayer")),
+        };
+        Ok(super::calculate_rating(wins, losses))
+    }
+}
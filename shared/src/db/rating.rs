@@ -0,0 +1,153 @@
+// CREATE VIEW rated_games AS
+//     -- every game rated by wins and losses, tagged with which one it is. size and win_length are NULL outside tictactoe
+//     SELECT 'tictactoe' AS game, user_id, won, created_at, guild_id, size, win_length FROM tictactoe
+//     UNION ALL SELECT 'ultimatetictactoe', ... FROM ultimate_tictactoe
+//     UNION ALL SELECT 'connectfour', ... FROM connectfour
+//     UNION ALL SELECT 'slidingpuzzle_race', ... FROM slidingpuzzle_races;
+
+use sqlx::Acquire;
+
+use crate::anyhow::Result;
+
+use super::{Period, TicTacToeVariant, User};
+
+/// the games in `rated_games`, each has its own standings
+#[derive(Debug, Clone, Copy)]
+pub(super) enum RatedGame {
+    TicTacToe(TicTacToeVariant),
+    UltimateTicTacToe,
+    ConnectFour,
+    SlidingPuzzleRace,
+}
+
+impl RatedGame {
+    /// the `game` column in `rated_games`
+    fn name(&self) -> &'static str {
+        match self {
+            Self::TicTacToe(_) => "tictactoe",
+            Self::UltimateTicTacToe => "ultimatetictactoe",
+            Self::ConnectFour => "connectfour",
+            Self::SlidingPuzzleRace => "slidingpuzzle_race",
+        }
+    }
+    fn variant(&self) -> Option<TicTacToeVariant> {
+        match self {
+            Self::TicTacToe(variant) => Some(*variant),
+            _ => None,
+        }
+    }
+
+    /// a page of the game's standings, highest rating first, and whether there's another page after it
+    pub(super) async fn standings(&self, filters: RatingFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<RatingEntry>, bool)> {
+        let variant = self.variant();
+        // ordered by the same rating calculate_rating gives, so pages line up with what's shown.
+        // one extra row is fetched to know if there's another page
+        let mut leaderboard = sqlx::query!(
+            r#"
+            SELECT user_id as "user_id!", SUM(won::integer) as "wins!", SUM((NOT won)::integer) as "losses!" FROM rated_games
+            WHERE game = $1
+            AND ($2::text IS NULL OR created_at >= date_trunc($2, LOCALTIMESTAMP))
+            AND ($3::bigint IS NULL OR guild_id = $3)
+            AND ($4::integer IS NULL OR (size = $4 AND win_length = $5))
+            GROUP BY user_id
+            ORDER BY SUM(CASE WHEN won THEN $6::float8 ELSE $7::float8 END) DESC, SUM(won::integer) DESC, user_id ASC
+            LIMIT $8 OFFSET $9
+            "#,
+            self.name(),
+            filters.period.trunc_field(),
+            filters.guild_id,
+            variant.map(|variant| variant.size as i32),
+            variant.map(|variant| variant.win_length as i32),
+            super::POINTS_PER_WIN,
+            super::POINTS_PER_LOSS,
+            filters.limit + 1,
+            filters.paginated_offset(),
+        )
+        .fetch_all(tx.acquire().await?)
+        .await?;
+
+        let more_available = leaderboard.len() as i64 > filters.limit;
+        leaderboard.truncate(filters.limit as usize);
+
+        let mut entries = Vec::new();
+        for entry in leaderboard {
+            let user = User::get_by_id(entry.user_id as i64, tx).await?.ok_or(anyhow::anyhow!("No user found"))?;
+            let (wins, losses) = (entry.wins, entry.losses);
+            entries.push(RatingEntry {
+                user,
+                wins,
+                losses,
+                rating: super::calculate_rating(wins, losses),
+            });
+        }
+
+        Ok((entries, more_available))
+    }
+}
+
+pub struct RatingEntry {
+    pub user: User,
+    pub wins: i64,
+    pub losses: i64,
+    pub rating: f64,
+}
+
+/// filters for the win/loss standings, shared by tictactoe, ultimate tictactoe, connect four and sliding puzzle races
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy)]
+pub struct RatingFilters {
+    pub(super) period: Period,
+    pub(super) guild_id: Option<i64>, // None is the global leaderboard
+    pub(super) limit: i64,
+    offset: i64, // will be multiplied by limit, pagination
+}
+
+impl RatingFilters {
+    pub fn period(&mut self, period: Period) -> &mut Self {
+        self.period = period;
+        self
+    }
+    pub fn get_period(&self) -> Period {
+        self.period
+    }
+    pub fn server(&mut self, guild_id: i64) -> &mut Self {
+        self.guild_id = Some(guild_id);
+        self
+    }
+    pub fn global(&mut self) -> &mut Self {
+        self.guild_id = None;
+        self
+    }
+    pub fn is_global(&self) -> bool {
+        self.guild_id.is_none()
+    }
+    pub fn limit(&mut self, limit: i64) -> &mut Self {
+        self.limit = limit;
+        self
+    }
+    pub fn increment_offset(&mut self) -> &mut Self {
+        self.offset += 1;
+        self
+    }
+    pub fn decrement_offset(&mut self) -> &mut Self {
+        self.offset = (self.offset - 1).max(0);
+        self
+    }
+    pub fn get_offset(&self) -> i64 {
+        self.offset
+    }
+    /// the number of entries before the current page
+    pub fn paginated_offset(&self) -> i64 {
+        self.offset * self.limit
+    }
+}
+
+impl Default for RatingFilters {
+    fn default() -> Self {
+        Self {
+            period: Period::AllTime,
+            guild_id: None,
+            limit: 10,
+            offset: 0,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// CREATE TABLE IF NOT EXISTS series_results (
+//     id SERIAL PRIMARY KEY,
+//     game text NOT NULL, -- see qg_shared::db::GameKind
+//     winner_id integer NOT NULL REFERENCES users(id),
+//     loser_id integer NOT NULL REFERENCES users(id),
+//     best_of integer NOT NULL,
+//     winner_wins integer NOT NULL,
+//     loser_wins integer NOT NULL,
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the series was played, NULL means it only counts globally
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::{anyhow, Result};
+
+use super::GameKind;
+
+/// a decided best of n series, the games themselves are still recorded one by one in their own tables
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeriesResult {
+    id: i64,
+    game: String,
+    pub winner_id: i64,
+    pub loser_id: i64,
+    pub best_of: i32,
+    pub winner_wins: i32,
+    pub loser_wins: i32,
+    created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+}
+
+impl SeriesResult {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(game: GameKind, winner_id: i64, loser_id: i64, best_of: u8, score: (u8, u8), guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let result = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO series_results (game, winner_id, loser_id, best_of, winner_wins, loser_wins, guild_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+            game.name(),
+            winner_id as i32,
+            loser_id as i32,
+            best_of as i32,
+            score.0 as i32,
+            score.1 as i32,
+            guild_id
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(result)
+    }
+    pub fn game(&self) -> Result<GameKind> {
+        GameKind::from_name(&self.game).ok_or(anyhow!("Unknown game `{}`", self.game))
+    }
+}
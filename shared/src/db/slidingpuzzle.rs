@@ -3,14 +3,30 @@
 //     id SERIAL PRIMARY KEY,
 //     user_id integer NOT NULL REFERENCES users(id),
 //     difficulty integer NOT NULL, -- 1 = easy, 2 = medium, 3 = hard
-//     size integer NOT NULL, -- 3 = 3x3, 4 = 4x4, 5 = 5x5
+//     size integer NOT NULL, -- 3 = 3x3, 4 = 4x4, 5 = 5x5, 6 = 6x6, 7 = 7x7, 8 = 8x8
 //     score integer[] NOT NULL, -- The users list of scores for this size
-//     time integer[] NOT NULL, -- The users list of times for this size
+//     time integer[] NOT NULL, -- The users list of times for this size, in milliseconds
 //     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
-//     updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+//     updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint,
+//     optimal integer, -- the fewest moves the scramble could be solved in, NULL if the solver gave up
+//     efficiency float8, -- optimal / score, 1 is a perfect solve
+//     hinted boolean NOT NULL DEFAULT false,
+//     message_id bigint, -- the game's message, its moves are logged under it in slidingpuzzle_moves
+//     seed bigint, -- regenerates the scramble, NULL for games from before replays were checked
+//     solution integer[] -- the number on each tile moved, in order, replayed from the scramble before the row is saved
 // );
 
-use serenity::futures::TryStreamExt;
+// CREATE TABLE IF NOT EXISTS slidingpuzzle_moves (
+//     id SERIAL PRIMARY KEY,
+//     message_id bigint NOT NULL, -- there's no result row until the puzzle is solved, so moves are keyed by the game's message
+//     number integer NOT NULL, -- 1 is the first move
+//     tile integer NOT NULL, -- the number on the tile that slid into the empty space
+//     elapsed integer NOT NULL, -- milliseconds on the clock when the move was made, paused time isn't counted
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+// CREATE UNIQUE INDEX IF NOT EXISTS slidingpuzzle_moves_message_id_number_idx ON slidingpuzzle_moves (message_id, number);
+
 use sqlx::{types::chrono, Acquire};
 
 use crate::anyhow::Result;
@@ -24,22 +40,53 @@ pub struct SlidingPuzzle {
     pub score: i32,
     pub time: i32,
     created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+    pub optimal: Option<i32>,
+    pub efficiency: Option<f64>,
+    pub hinted: bool,
+    pub message_id: Option<i64>,
+    pub seed: Option<i64>,
+    pub solution: Option<Vec<i32>>,
 }
 
 impl SlidingPuzzle {
-    pub async fn create(user_id: i32, difficulty: i32, size: i32, score: i32, time: i32, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+    /// `time` is in milliseconds. `optimal` is the shortest solution for the scramble when the solver found one, the efficiency is worked out from it.
+    /// `solution` should already have been replayed from the scramble `seed` makes, it's only kept here for auditing
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        user_id: i32,
+        difficulty: i32,
+        size: i32,
+        score: i32,
+        time: i32,
+        optimal: Option<i32>,
+        hinted: bool,
+        guild_id: Option<i64>,
+        message_id: Option<i64>,
+        seed: i64,
+        solution: &[i32],
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Self> {
+        let efficiency = optimal.map(|optimal| optimal as f64 / score.max(1) as f64);
         let puzzle = sqlx::query_as!(
             Self,
             r#"
-            INSERT INTO slidingpuzzle (user_id, difficulty, size, score, time)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO slidingpuzzle (user_id, difficulty, size, score, time, guild_id, optimal, efficiency, hinted, message_id, seed, solution)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING *
             "#,
             user_id,
             difficulty,
             size,
             score,
-            time
+            time,
+            guild_id,
+            optimal,
+            efficiency,
+            hinted,
+            message_id,
+            seed,
+            solution
         )
         .fetch_one(tx.acquire().await?)
         .await?;
@@ -47,82 +94,78 @@ impl SlidingPuzzle {
         Ok(puzzle)
     }
 
-    pub async fn get_standings(filters: SlidingPuzzleFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<SlidingPuzzleWithUser>, bool)> {
-        let order = match filters.sort_by {
-            SlidingPuzzleSortBy::Score => "score",
-            SlidingPuzzleSortBy::Time => "time",
-        };
+    pub async fn count_for_user(user: &super::User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<i64> {
+        let count = sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM slidingpuzzle WHERE user_id = $1"#, user.id as i32)
+            .fetch_one(tx.acquire().await?)
+            .await?;
+
+        Ok(count)
+    }
 
-        let mut puzzles = Vec::new();
-
-        {
-            let mut rows = sqlx::query_as!(
-                Self,
-                r#"
-                SELECT * FROM slidingpuzzle
-                WHERE size = $1
-                and difficulty = $2
-                ORDER BY $3 ASC
-                "#,
-                filters.filter_by.size,
-                filters.filter_by.difficulty,
-                order
-            )
-            .fetch(tx.acquire().await?);
-
-            let mut users = std::collections::HashMap::new();
-
-            while let Some::<SlidingPuzzle>(puzzle) = rows.try_next().await? {
-                let score = puzzle.score;
-                // if user is in hashmap, check if the score is lower than the current score
-                // if it is, replace the score with the new score and update the puzzle in the puzzles vector, one per user
-                if let Some(otherscore) = users.get_mut(&puzzle.user_id) {
-                    if score < *otherscore {
-                        *otherscore = score;
-                        puzzles.retain(|p: &SlidingPuzzle| p.user_id != puzzle.user_id);
-                        puzzles.push(puzzle);
-                    }
-                } else {
-                    users.insert(puzzle.user_id, score);
-                    puzzles.push(puzzle);
-                }
+    /// each user's best run for the filtered size and difficulty, one page at a time.
+    /// "best" follows the sort, so sorting by time ranks everyone by their fastest solve
+    pub async fn get_standings(filters: SlidingPuzzleFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<SlidingPuzzleWithUser>, bool)> {
+        // the column order can't be a bind parameter, so each sort gets its own query.
+        // one extra row is fetched to know if there's another page
+        let mut rows = match filters.sort_by {
+            SlidingPuzzleSortBy::Score => {
+                sqlx::query_as!(
+                    SlidingPuzzleStandingRaw,
+                    r#"
+                    SELECT best.*, users.name, users.discord_id, users.avatar, users.refreshed_at FROM (
+                        SELECT DISTINCT ON (user_id) * FROM slidingpuzzle
+                        WHERE size = $1
+                        AND difficulty = $2
+                        AND ($3::text IS NULL OR created_at >= date_trunc($3, LOCALTIMESTAMP))
+                        AND ($4::bigint IS NULL OR guild_id = $4)
+                        ORDER BY user_id, score ASC, time ASC, created_at ASC
+                    ) best
+                    INNER JOIN users ON users.id = best.user_id
+                    ORDER BY best.score ASC, best.time ASC, best.created_at ASC
+                    LIMIT $5 OFFSET $6
+                    "#,
+                    filters.filter_by.size,
+                    filters.filter_by.difficulty,
+                    filters.period.trunc_field(),
+                    filters.guild_id,
+                    filters.limit + 1,
+                    filters.paginated_offset(),
+                )
+                .fetch_all(tx.acquire().await?)
+                .await?
             }
-            match filters.sort_by {
-                SlidingPuzzleSortBy::Score => puzzles.sort_by(|a, b| {
-                    // sort by score, if score is equal, sort by time
-                    // if time is equal sort by date
-                    match (a.score == b.score, a.time == b.time) {
-                        (true, true) => a.created_at.cmp(&b.created_at),
-                        (true, _) => a.time.cmp(&b.time),
-                        (_, _) => a.score.cmp(&b.score),
-                    }
-                }),
-                SlidingPuzzleSortBy::Time => puzzles.sort_by(|a, b| {
-                    // sort by time, if time is equal, sort by score
-                    // if score is equal sort by date
-                    match (a.time == b.time, a.score == b.score) {
-                        (true, true) => a.created_at.cmp(&b.created_at),
-                        (true, _) => a.score.cmp(&b.score),
-                        (_, _) => a.time.cmp(&b.time),
-                    }
-                }),
+            SlidingPuzzleSortBy::Time => {
+                sqlx::query_as!(
+                    SlidingPuzzleStandingRaw,
+                    r#"
+                    SELECT best.*, users.name, users.discord_id, users.avatar, users.refreshed_at FROM (
+                        SELECT DISTINCT ON (user_id) * FROM slidingpuzzle
+                        WHERE size = $1
+                        AND difficulty = $2
+                        AND ($3::text IS NULL OR created_at >= date_trunc($3, LOCALTIMESTAMP))
+                        AND ($4::bigint IS NULL OR guild_id = $4)
+                        ORDER BY user_id, time ASC, score ASC, created_at ASC
+                    ) best
+                    INNER JOIN users ON users.id = best.user_id
+                    ORDER BY best.time ASC, best.score ASC, best.created_at ASC
+                    LIMIT $5 OFFSET $6
+                    "#,
+                    filters.filter_by.size,
+                    filters.filter_by.difficulty,
+                    filters.period.trunc_field(),
+                    filters.guild_id,
+                    filters.limit + 1,
+                    filters.paginated_offset(),
+                )
+                .fetch_all(tx.acquire().await?)
+                .await?
             }
-        }
-
-        let more_available = puzzles.len() > filters.limit as usize;
-
-        if more_available {
-            puzzles.truncate(filters.limit as usize);
-        }
-
-        let mut results = Vec::new();
+        };
 
-        for puzzle in puzzles {
-            let user = super::User::get_by_id(puzzle.user_id, tx).await?.ok_or(anyhow::anyhow!("User not found"))?;
-            results.push(SlidingPuzzleWithUser { puzzle, user });
-        }
+        let more_available = rows.len() as i64 > filters.limit;
+        rows.truncate(filters.limit as usize);
 
-        Ok((results, more_available))
+        Ok((rows.into_iter().map(SlidingPuzzleWithUser::from).collect(), more_available))
     }
 }
 
@@ -130,6 +173,8 @@ impl SlidingPuzzle {
 pub struct SlidingPuzzleFilters {
     sort_by: SlidingPuzzleSortBy,
     filter_by: SlidingPuzzleFilterBy,
+    period: super::Period,
+    guild_id: Option<i64>, // None is the global leaderboard
     limit: i64,
     offset: i64, // will be multiplied by limit, pagination
 }
@@ -179,6 +224,36 @@ impl SlidingPuzzleFilters {
         self.filter_by.size = 2;
         self
     }
+    pub fn sixbysix(&mut self) -> &mut Self {
+        self.filter_by.size = 3;
+        self
+    }
+    pub fn sevenbyseven(&mut self) -> &mut Self {
+        self.filter_by.size = 4;
+        self
+    }
+    pub fn eightbyeight(&mut self) -> &mut Self {
+        self.filter_by.size = 5;
+        self
+    }
+    pub fn period(&mut self, period: super::Period) -> &mut Self {
+        self.period = period;
+        self
+    }
+    pub fn get_period(&self) -> super::Period {
+        self.period
+    }
+    pub fn server(&mut self, guild_id: i64) -> &mut Self {
+        self.guild_id = Some(guild_id);
+        self
+    }
+    pub fn global(&mut self) -> &mut Self {
+        self.guild_id = None;
+        self
+    }
+    pub fn is_global(&self) -> bool {
+        self.guild_id.is_none()
+    }
     pub fn limit(&mut self, limit: i64) -> &mut Self {
         self.limit = limit;
         self
@@ -192,10 +267,14 @@ impl SlidingPuzzleFilters {
         self
     }
     pub fn decrement_offset(&mut self) -> &mut Self {
-        self.offset -= 1;
+        self.offset = (self.offset - 1).max(0);
         self
     }
-    fn paginated_offset(&self) -> i64 {
+    pub fn get_offset(&self) -> i64 {
+        self.offset
+    }
+    /// the number of entries before the current page
+    pub fn paginated_offset(&self) -> i64 {
         self.offset * self.limit
     }
 }
@@ -205,6 +284,8 @@ impl Default for SlidingPuzzleFilters {
         Self {
             sort_by: SlidingPuzzleSortBy::Score,
             filter_by: SlidingPuzzleFilterBy { difficulty: 0, size: 0 },
+            period: super::Period::AllTime,
+            guild_id: None,
             limit: 10,
             offset: 0,
         }
@@ -215,3 +296,112 @@ pub struct SlidingPuzzleWithUser {
     pub puzzle: SlidingPuzzle,
     pub user: super::User,
 }
+
+/// a standings row, the puzzle joined with who played it
+#[derive(Debug, sqlx::FromRow)]
+struct SlidingPuzzleStandingRaw {
+    id: i64,
+    user_id: i64,
+    difficulty: i32,
+    size: i32,
+    score: i32,
+    time: i32,
+    created_at: chrono::NaiveDateTime,
+    guild_id: Option<i64>,
+    optimal: Option<i32>,
+    efficiency: Option<f64>,
+    hinted: bool,
+    message_id: Option<i64>,
+    seed: Option<i64>,
+    solution: Option<Vec<i32>>,
+    name: String,
+    discord_id: i64,
+    avatar: Option<String>,
+    refreshed_at: chrono::NaiveDateTime,
+}
+
+impl From<SlidingPuzzleStandingRaw> for SlidingPuzzleWithUser {
+    fn from(row: SlidingPuzzleStandingRaw) -> Self {
+        Self {
+            puzzle: SlidingPuzzle {
+                id: row.id,
+                user_id: row.user_id,
+                difficulty: row.difficulty,
+                size: row.size,
+                score: row.score,
+                time: row.time,
+                created_at: row.created_at,
+                guild_id: row.guild_id,
+                optimal: row.optimal,
+                efficiency: row.efficiency,
+                hinted: row.hinted,
+                message_id: row.message_id,
+                seed: row.seed,
+                solution: row.solution,
+            },
+            user: super::User {
+                id: row.user_id,
+                name: row.name,
+                discord_id: row.discord_id,
+                avatar: row.avatar,
+                refreshed_at: row.refreshed_at,
+            },
+        }
+    }
+}
+
+/// one move of a game, logged as it's played
+#[derive(Debug, sqlx::FromRow)]
+pub struct SlidingPuzzleMove {
+    id: i64,
+    pub message_id: i64,
+    pub number: i32,
+    pub tile: i32,
+    pub elapsed: i32,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl SlidingPuzzleMove {
+    /// a move that hasn't been logged, for checking a replay without the database
+    pub fn new(message_id: i64, number: i32, tile: i32, elapsed: i32) -> Self {
+        Self {
+            id: 0,
+            message_id,
+            number,
+            tile,
+            elapsed,
+            created_at: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    /// logs move `number`, dropping anything logged from it onwards first. a click on an older copy of the message
+    /// continues from that copy's state, so the moves after it never happened as far as the board is concerned
+    pub async fn log(message_id: i64, number: i32, tile: i32, elapsed: i32, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!("DELETE FROM slidingpuzzle_moves WHERE message_id = $1 AND number >= $2", message_id, number)
+            .execute(tx.acquire().await?)
+            .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO slidingpuzzle_moves (message_id, number, tile, elapsed)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            message_id,
+            number,
+            tile,
+            elapsed
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// every move of a game in the order they were played
+    pub async fn for_game(message_id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<Self>> {
+        let moves = sqlx::query_as!(Self, "SELECT * FROM slidingpuzzle_moves WHERE message_id = $1 ORDER BY number ASC", message_id)
+            .fetch_all(tx.acquire().await?)
+            .await?;
+
+        Ok(moves)
+    }
+}
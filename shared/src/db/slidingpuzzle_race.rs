@@ -0,0 +1,132 @@
+// CREATE TABLE IF NOT EXISTS slidingpuzzle_races (
+//     -- like tictactoe there's a row for each player, so standings only have to look at user_id
+//     id SERIAL PRIMARY KEY,
+//     race_id bigint NOT NULL, -- the scoreboard message, both players' rows share it
+//     user_id integer NOT NULL REFERENCES users(id),
+//     opponent_id integer NOT NULL REFERENCES users(id),
+//     won boolean NOT NULL,
+//     size integer NOT NULL,
+//     difficulty integer NOT NULL,
+//     seed bigint NOT NULL, -- both players were given the scramble this makes
+//     time integer, -- milliseconds the winner took, NULL for the player who didn't finish first
+//     moves integer, -- NULL for the player who didn't finish first
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the race was played, NULL means it only counts globally
+// );
+// CREATE UNIQUE INDEX IF NOT EXISTS slidingpuzzle_races_winner_idx ON slidingpuzzle_races (race_id) WHERE won;
+
+// CREATE TABLE IF NOT EXISTS slidingpuzzle_race_seeds (
+//     -- kept out of the scoreboard, which everyone can read, so nobody can work out the scramble before pressing Start
+//     race_id bigint PRIMARY KEY, -- the scoreboard message
+//     seed bigint NOT NULL,
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::Result;
+
+use super::{rating::RatedGame, RatingEntry, RatingFilters, User};
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct SlidingPuzzleRace {
+    id: i64,
+    pub race_id: i64,
+    pub user_id: i64,
+    pub opponent_id: i64,
+    pub won: bool,
+    pub size: i32,
+    pub difficulty: i32,
+    pub seed: i64,
+    pub time: Option<i32>,
+    pub moves: Option<i32>,
+    created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+}
+
+impl SlidingPuzzleRace {
+    /// claims the race for `winner` and records the loss for `loser`.
+    /// `false` if the other player's solve was recorded first, in which case nothing is written
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record(
+        race_id: i64,
+        winner: &User,
+        loser: &User,
+        size: i32,
+        difficulty: i32,
+        seed: i64,
+        time: i32,
+        moves: i32,
+        guild_id: Option<i64>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool> {
+        let claimed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO slidingpuzzle_races (race_id, user_id, opponent_id, won, size, difficulty, seed, time, moves, guild_id)
+            VALUES ($1, $2, $3, true, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (race_id) WHERE won DO NOTHING
+            RETURNING id
+            "#,
+            race_id,
+            winner.id as i32,
+            loser.id as i32,
+            size,
+            difficulty,
+            seed,
+            time,
+            moves,
+            guild_id
+        )
+        .fetch_optional(tx.acquire().await?)
+        .await?;
+
+        if claimed.is_none() {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO slidingpuzzle_races (race_id, user_id, opponent_id, won, size, difficulty, seed, guild_id)
+            VALUES ($1, $2, $3, false, $4, $5, $6, $7)
+            "#,
+            race_id,
+            loser.id as i32,
+            winner.id as i32,
+            size,
+            difficulty,
+            seed,
+            guild_id
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+
+        Ok(true)
+    }
+
+    /// rated the same way as tictactoe, so a win is worth more than a loss costs
+    pub async fn get_standings(filters: RatingFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<RatingEntry>, bool)> {
+        RatedGame::SlidingPuzzleRace.standings(filters, tx).await
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct SlidingPuzzleRaceSeed {
+    pub race_id: i64,
+    pub seed: i64,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl SlidingPuzzleRaceSeed {
+    pub async fn create(race_id: i64, seed: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let race_seed = sqlx::query_as!(Self, "INSERT INTO slidingpuzzle_race_seeds (race_id, seed) VALUES ($1, $2) RETURNING *", race_id, seed)
+            .fetch_one(tx.acquire().await?)
+            .await?;
+        Ok(race_seed)
+    }
+    pub async fn get(race_id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let race_seed = sqlx::query_as!(Self, "SELECT * FROM slidingpuzzle_race_seeds WHERE race_id = $1", race_id)
+            .fetch_optional(tx.acquire().await?)
+            .await?;
+        Ok(race_seed)
+    }
+}
@@ -4,14 +4,17 @@
 //     user_id integer NOT NULL REFERENCES users(id),
 //     opponent_id integer NOT NULL REFERENCES users(id), -- not really used, we double up on the entries so its easier to query
 //     won boolean NOT NULL, -- true if the user won, false if the user lost. we dont keep track of draws, draws are lame
-//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint, -- where the game was played, NULL means it only counts globally
+//     size integer NOT NULL DEFAULT 3,
+//     win_length integer NOT NULL DEFAULT 3 -- how many in a row it took to win, each size and length has its own standings
 // );
 
 use sqlx::{types::chrono, Acquire};
 
 use crate::anyhow::Result;
 
-use super::User;
+use super::{rating::RatedGame, RatingEntry, RatingFilters, User};
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct TicTacToe {
@@ -20,78 +23,78 @@ pub struct TicTacToe {
     pub opponent_id: i64,
     pub won: bool,
     created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+    pub size: i32,
+    pub win_length: i32,
 }
 
 impl TicTacToe {
-    pub async fn create(user_id: i32, opponent_id: i32, won: bool, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+    pub async fn create(user_id: i32, opponent_id: i32, won: bool, variant: TicTacToeVariant, guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
         let tictactoe = sqlx::query_as!(
             TicTacToe,
             r#"
-            INSERT INTO tictactoe (user_id, opponent_id, won)
-            VALUES ($1, $2, $3)
+            INSERT INTO tictactoe (user_id, opponent_id, won, guild_id, size, win_length)
+            VALUES ($1, $2, $3, $4, $5, $6)
             RETURNING *
             "#,
             user_id,
             opponent_id,
-            won
+            won,
+            guild_id,
+            variant.size as i32,
+            variant.win_length as i32
         )
         .fetch_one(tx.acquire().await?)
         .await?;
 
         Ok(tictactoe)
     }
-    pub async fn get_standings(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<TTTLeaderboardEntry>, bool)> {
-        let (leaderboard, more) = TTTLeaderboardEntryRaw::get_all_sorted(tx).await?;
-
-        let mut entries = Vec::new();
-
-        for entry in leaderboard {
-            let user = User::get_by_id(entry.user_id, tx).await?.ok_or(anyhow::anyhow!("No user found"))?;
-
-            let ratio = entry.ratio.ok_or(anyhow::anyhow!("No ratio found"))?;
-            let wins = entry.total.unwrap_or(0);
-
-            // ratio is wins - losses
-            let losses = wins - ratio;
-
-            // now that we've reconstructed the wins and losses, we can calculate the players rating.
-            let rating = super::calculate_rating(wins, losses);
-
-            entries.push(TTTLeaderboardEntry { user, wins, losses, rating });
-        }
+    /// (wins, losses) for a single user
+    pub async fn get_record(user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(i64, i64)> {
+        let record = sqlx::query!(
+            r#"SELECT COUNT(*) FILTER (WHERE won) as "wins!", COUNT(*) FILTER (WHERE NOT won) as "losses!" FROM tictactoe WHERE user_id = $1"#,
+            user.id as i32
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
 
-        // sort entried by rating, highest to lowest
-        entries.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap_or(std::cmp::Ordering::Equal));
+        Ok((record.wins, record.losses))
+    }
 
-        Ok((entries, more))
+    pub async fn get_standings(variant: TicTacToeVariant, filters: RatingFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<RatingEntry>, bool)> {
+        RatedGame::TicTacToe(variant).standings(filters, tx).await
     }
 }
 
-#[derive(Debug, sqlx::FromRow)]
-struct TTTLeaderboardEntryRaw {
-    user_id: i64,
-    ratio: Option<i64>,
-    total: Option<i64>,
+/// the board size and how many in a row it takes to win. every variant plays differently enough to get its own standings
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TicTacToeVariant {
+    pub size: usize,
+    pub win_length: usize,
 }
 
-impl TTTLeaderboardEntryRaw {
-    async fn get_all_sorted(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<Self>, bool)> {
-        let leaderboard = sqlx::query_as!(
-            Self,
-            r#"
-            SELECT user_id, SUM((won::integer * 2) - 1) as ratio, SUM(won::integer) as total FROM tictactoe GROUP BY user_id ORDER BY ratio DESC
-            "#,
-        )
-        .fetch_all(tx.acquire().await?)
-        .await?;
+impl TicTacToeVariant {
+    pub const SIZES: std::ops::RangeInclusive<usize> = 3..=5;
 
-        Ok((leaderboard, false))
+    pub fn new(size: usize, win_length: usize) -> Result<Self> {
+        if !Self::SIZES.contains(&size) {
+            return Err(anyhow::anyhow!("The board has to be between 3 and 5 spaces across"));
+        }
+        if !Self::SIZES.contains(&win_length) || win_length > size {
+            return Err(anyhow::anyhow!("The line to win has to be between 3 and the size of the board"));
+        }
+        Ok(Self { size, win_length })
+    }
+    pub fn is_classic(&self) -> bool {
+        *self == Self::default()
+    }
+    pub fn name(&self) -> String {
+        format!("{}x{}, {} in a row", self.size, self.size, self.win_length)
     }
 }
 
-pub struct TTTLeaderboardEntry {
-    pub user: User,
-    pub wins: i64,
-    pub losses: i64,
-    pub rating: f64,
+impl Default for TicTacToeVariant {
+    fn default() -> Self {
+        Self { size: 3, win_length: 3 }
+    }
 }
@@ -0,0 +1,482 @@
+// CREATE TABLE IF NOT EXISTS tournaments (
+//     id SERIAL PRIMARY KEY,
+//     guild_id bigint NOT NULL,
+//     channel_id bigint NOT NULL, -- where matches are opened and the champion is announced
+//     game text NOT NULL, -- see qg_shared::db::GameKind
+//     format text NOT NULL, -- 'single_elimination' or 'round_robin'
+//     status text NOT NULL DEFAULT 'signup', -- 'signup', 'running' or 'finished'
+//     created_by bigint NOT NULL, -- discord id of the organizer
+//     signup_closes_at timestamp NOT NULL,
+//     champion_id integer REFERENCES users(id),
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+// CREATE TABLE IF NOT EXISTS tournament_players (
+//     id SERIAL PRIMARY KEY,
+//     tournament_id integer NOT NULL REFERENCES tournaments(id),
+//     user_id integer NOT NULL REFERENCES users(id),
+//     joined_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     UNIQUE (tournament_id, user_id)
+// );
+
+// CREATE TABLE IF NOT EXISTS tournament_matches (
+//     id SERIAL PRIMARY KEY,
+//     tournament_id integer NOT NULL REFERENCES tournaments(id),
+//     round integer NOT NULL,
+//     player_one integer NOT NULL REFERENCES users(id),
+//     player_two integer REFERENCES users(id), -- NULL is a bye, player_one goes through without playing
+//     winner_id integer REFERENCES users(id), -- NULL until finished, and for round robin draws
+//     finished boolean NOT NULL DEFAULT false,
+//     message_id bigint, -- the message the game is being played in
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+use std::{cell::RefCell, future::Future};
+
+use rand::seq::SliceRandom;
+use serenity::all::*;
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::{anyhow, Result};
+
+use super::{GameKind, User};
+
+tokio::task_local! {
+    /// matches that became playable while handling the current interaction. their games aren't posted until its transaction has committed,
+    /// otherwise a rollback would leave games in the channel whose matches don't exist
+    static OPENING: RefCell<Vec<TournamentMatch>>;
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Tournament {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    game: String,
+    format: String,
+    status: String,
+    pub created_by: i64,
+    pub signup_closes_at: chrono::NaiveDateTime,
+    pub champion_id: Option<i32>,
+    created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TournamentFormat {
+    SingleElimination,
+    RoundRobin,
+}
+
+impl TournamentFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SingleElimination => "single_elimination",
+            Self::RoundRobin => "round_robin",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "single_elimination" => Some(Self::SingleElimination),
+            "round_robin" => Some(Self::RoundRobin),
+            _ => None,
+        }
+    }
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::SingleElimination => "Single Elimination",
+            Self::RoundRobin => "Round Robin",
+        }
+    }
+}
+
+impl Tournament {
+    pub async fn create(
+        guild_id: i64,
+        channel_id: i64,
+        game: GameKind,
+        format: TournamentFormat,
+        created_by: i64,
+        signup_closes_at: chrono::NaiveDateTime,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Self> {
+        let tournament = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO tournaments (guild_id, channel_id, game, format, created_by, signup_closes_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+            guild_id,
+            channel_id,
+            game.name(),
+            format.name(),
+            created_by,
+            signup_closes_at
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(tournament)
+    }
+    pub async fn get(id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let tournament = sqlx::query_as!(Self, "SELECT * FROM tournaments WHERE id = $1", id as i32).fetch_optional(tx.acquire().await?).await?;
+        Ok(tournament)
+    }
+    /// the tournament a server is currently signing up for or playing, only one runs at a time
+    pub async fn get_active(guild_id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let tournament = sqlx::query_as!(
+            Self,
+            "SELECT * FROM tournaments WHERE guild_id = $1 AND status <> 'finished' ORDER BY created_at DESC LIMIT 1",
+            guild_id
+        )
+        .fetch_optional(tx.acquire().await?)
+        .await?;
+        Ok(tournament)
+    }
+    /// the most recent tournament in a server, finished or not
+    pub async fn get_latest(guild_id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let tournament = sqlx::query_as!(Self, "SELECT * FROM tournaments WHERE guild_id = $1 ORDER BY created_at DESC LIMIT 1", guild_id)
+            .fetch_optional(tx.acquire().await?)
+            .await?;
+        Ok(tournament)
+    }
+
+    pub fn game(&self) -> Result<GameKind> {
+        GameKind::from_name(&self.game).ok_or(anyhow!("Unknown game `{}`", self.game))
+    }
+    pub fn format(&self) -> Result<TournamentFormat> {
+        TournamentFormat::from_name(&self.format).ok_or(anyhow!("Unknown format `{}`", self.format))
+    }
+    pub fn is_signing_up(&self) -> bool {
+        self.status == "signup"
+    }
+    pub fn is_finished(&self) -> bool {
+        self.status == "finished"
+    }
+    pub fn signup_open(&self) -> bool {
+        self.is_signing_up() && chrono::Utc::now().naive_utc() < self.signup_closes_at
+    }
+
+    pub async fn join(&self, user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        if !self.signup_open() {
+            return Err(anyhow!("Sign-ups for this tournament are closed"));
+        }
+        let joined = sqlx::query!(
+            "INSERT INTO tournament_players (tournament_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            self.id as i32,
+            user.id as i32
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+        if joined.rows_affected() == 0 {
+            return Err(anyhow!("You've already signed up"));
+        }
+        Ok(())
+    }
+    pub async fn get_players(&self, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<User>> {
+        let players = sqlx::query_as!(
+            User,
+            r#"
+            SELECT users.* FROM tournament_players
+            INNER JOIN users ON users.id = tournament_players.user_id
+            WHERE tournament_players.tournament_id = $1
+            ORDER BY tournament_players.joined_at ASC
+            "#,
+            self.id as i32
+        )
+        .fetch_all(tx.acquire().await?)
+        .await?;
+        Ok(players)
+    }
+    pub async fn get_matches(&self, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<TournamentMatch>> {
+        let matches = sqlx::query_as!(TournamentMatch, "SELECT * FROM tournament_matches WHERE tournament_id = $1 ORDER BY round ASC, id ASC", self.id as i32)
+            .fetch_all(tx.acquire().await?)
+            .await?;
+        Ok(matches)
+    }
+
+    /// closes sign-ups and generates the pairings, returning the matches that need opening.
+    /// single elimination only pairs the first round, later rounds are paired from the winners as they come in.
+    /// round robin pairs every round up front (circle method) and opens them one round at a time
+    pub async fn start(&mut self, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<TournamentMatch>> {
+        if !self.is_signing_up() {
+            return Err(anyhow!("This tournament has already started"));
+        }
+
+        let mut players = self.get_players(tx).await?.into_iter().map(|user| user.id).collect::<Vec<i64>>();
+        if players.len() < 2 {
+            return Err(anyhow!("At least 2 players need to sign up before starting"));
+        }
+        players.shuffle(&mut rand::thread_rng());
+
+        match self.format()? {
+            TournamentFormat::SingleElimination => {
+                self.pair_round(1, &players, tx).await?;
+            }
+            TournamentFormat::RoundRobin => {
+                let mut seats = players.into_iter().map(Some).collect::<Vec<Option<i64>>>();
+                if seats.len() % 2 == 1 {
+                    // whoever is across from the empty seat sits the round out
+                    seats.push(None);
+                }
+                for round in 1..seats.len() {
+                    for i in 0..seats.len() / 2 {
+                        if let (Some(one), Some(two)) = (seats[i], seats[seats.len() - 1 - i]) {
+                            TournamentMatch::create(self.id, round as i32, one, Some(two), tx).await?;
+                        }
+                    }
+                    // the first seat stays put, everyone else rotates one seat over
+                    seats[1..].rotate_right(1);
+                }
+            }
+        }
+
+        sqlx::query!("UPDATE tournaments SET status = 'running' WHERE id = $1", self.id as i32)
+            .execute(tx.acquire().await?)
+            .await?;
+        self.status = String::from("running");
+
+        TournamentMatch::get_playable(self.id, 1, tx).await
+    }
+
+    /// pairs players off in order, an odd player out gets a bye
+    async fn pair_round(&self, round: i32, players: &[i64], tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        for pair in players.chunks(2) {
+            match pair {
+                [one, two] => {
+                    TournamentMatch::create(self.id, round, *one, Some(*two), tx).await?;
+                }
+                [one] => {
+                    let bye = TournamentMatch::create(self.id, round, *one, None, tx).await?;
+                    sqlx::query!("UPDATE tournament_matches SET winner_id = $1, finished = true WHERE id = $2", *one as i32, bye.id as i32)
+                        .execute(tx.acquire().await?)
+                        .await?;
+                }
+                _ => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// records how a match ended and moves the tournament along. winner is the users id, None for a tie.
+    /// single elimination can't have draws, so a tie is replayed between the same players
+    pub async fn record_result(match_id: i64, winner: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Advancement> {
+        let finished = sqlx::query_as!(
+            TournamentMatch,
+            "UPDATE tournament_matches SET winner_id = $1, finished = true WHERE id = $2 AND NOT finished RETURNING *",
+            winner.map(|w| w as i32),
+            match_id as i32
+        )
+        .fetch_optional(tx.acquire().await?)
+        .await?;
+
+        // already recorded, nothing to do
+        let finished = match finished {
+            Some(finished) => finished,
+            None => return Ok(Advancement::default()),
+        };
+
+        let mut tournament = Self::get(finished.tournament_id, tx).await?.ok_or(anyhow!("Tournament not found"))?;
+        let format = tournament.format()?;
+
+        if winner.is_none() && format == TournamentFormat::SingleElimination {
+            if let Some(player_two) = finished.player_two {
+                let replay = TournamentMatch::create(tournament.id, finished.round, finished.player_one, Some(player_two as i64), tx).await?;
+                return Ok(Advancement {
+                    opened: vec![replay],
+                    champion: None,
+                    tournament: Some(tournament),
+                });
+            }
+        }
+
+        let remaining = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM tournament_matches WHERE tournament_id = $1 AND round = $2 AND NOT finished"#,
+            tournament.id as i32,
+            finished.round
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        if remaining > 0 {
+            return Ok(Advancement {
+                opened: vec![],
+                champion: None,
+                tournament: Some(tournament),
+            });
+        }
+
+        let next_round = finished.round + 1;
+
+        let champion = match format {
+            TournamentFormat::SingleElimination => {
+                let winners = sqlx::query_scalar!(
+                    r#"SELECT winner_id as "winner_id!" FROM tournament_matches WHERE tournament_id = $1 AND round = $2 AND winner_id IS NOT NULL ORDER BY id ASC"#,
+                    tournament.id as i32,
+                    finished.round
+                )
+                .fetch_all(tx.acquire().await?)
+                .await?
+                .into_iter()
+                .map(|w| w as i64)
+                .collect::<Vec<i64>>();
+
+                if winners.len() > 1 {
+                    tournament.pair_round(next_round, &winners, tx).await?;
+                    None
+                } else {
+                    winners.first().copied()
+                }
+            }
+            TournamentFormat::RoundRobin => {
+                if TournamentMatch::get_playable(tournament.id, next_round, tx).await?.is_empty() {
+                    // most wins takes it, earliest sign-up breaks ties
+                    let champion = sqlx::query_scalar!(
+                        r#"
+                        SELECT tournament_players.user_id FROM tournament_players
+                        LEFT JOIN tournament_matches ON tournament_matches.tournament_id = tournament_players.tournament_id
+                        AND tournament_matches.winner_id = tournament_players.user_id
+                        WHERE tournament_players.tournament_id = $1
+                        GROUP BY tournament_players.user_id, tournament_players.joined_at
+                        ORDER BY COUNT(tournament_matches.id) DESC, tournament_players.joined_at ASC
+                        LIMIT 1
+                        "#,
+                        tournament.id as i32
+                    )
+                    .fetch_optional(tx.acquire().await?)
+                    .await?;
+                    champion.map(|c| c as i64)
+                } else {
+                    None
+                }
+            }
+        };
+
+        match champion {
+            Some(champion) => {
+                sqlx::query!("UPDATE tournaments SET status = 'finished', champion_id = $1 WHERE id = $2", champion as i32, tournament.id as i32)
+                    .execute(tx.acquire().await?)
+                    .await?;
+                tournament.status = String::from("finished");
+                tournament.champion_id = Some(champion as i32);
+                Ok(Advancement {
+                    opened: vec![],
+                    champion: User::get_by_id(champion, tx).await?,
+                    tournament: Some(tournament),
+                })
+            }
+            None => Ok(Advancement {
+                opened: TournamentMatch::get_playable(tournament.id, next_round, tx).await?,
+                champion: None,
+                tournament: Some(tournament),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct TournamentMatch {
+    pub id: i64,
+    pub tournament_id: i64,
+    pub round: i32,
+    pub player_one: i64,
+    pub player_two: Option<i32>,
+    pub winner_id: Option<i32>,
+    pub finished: bool,
+    pub message_id: Option<i64>,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl TournamentMatch {
+    async fn create(tournament_id: i64, round: i32, player_one: i64, player_two: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let tournament_match = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO tournament_matches (tournament_id, round, player_one, player_two)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            tournament_id as i32,
+            round,
+            player_one as i32,
+            player_two.map(|p| p as i32)
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(tournament_match)
+    }
+    /// the unfinished matches of a round that actually need playing, byes are left out
+    async fn get_playable(tournament_id: i64, round: i32, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<Self>> {
+        let matches = sqlx::query_as!(
+            Self,
+            "SELECT * FROM tournament_matches WHERE tournament_id = $1 AND round = $2 AND NOT finished AND player_two IS NOT NULL ORDER BY id ASC",
+            tournament_id as i32,
+            round
+        )
+        .fetch_all(tx.acquire().await?)
+        .await?;
+        Ok(matches)
+    }
+    /// the discord ids of both players
+    pub async fn discord_ids(&self, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(UserId, UserId)> {
+        let player_one = User::get_by_id(self.player_one, tx).await?.ok_or(anyhow!("User not found"))?;
+        let player_two = User::get_by_id(self.player_two.ok_or(anyhow!("A bye has no opponent"))? as i64, tx)
+            .await?
+            .ok_or(anyhow!("User not found"))?;
+        Ok((UserId::new(player_one.discord_id as u64), UserId::new(player_two.discord_id as u64)))
+    }
+    /// runs an interaction's handler, returning the matches it made playable along with its result. open them once the transaction has committed
+    pub async fn collect<F: Future>(handler: F) -> (F::Output, Vec<Self>) {
+        OPENING
+            .scope(RefCell::new(Vec::new()), async {
+                let output = handler.await;
+                (output, OPENING.with(|opening| opening.take()))
+            })
+            .await
+    }
+
+    /// holds on to matches until the interaction is done, see `collect`
+    pub fn open_later(matches: Vec<Self>) {
+        if matches.is_empty() {
+            return;
+        }
+        if OPENING.try_with(|opening| opening.borrow_mut().extend(matches)).is_err() {
+            log::warn!("Tournament matches were made playable outside of an interaction, they won't be opened");
+        }
+    }
+
+    pub async fn set_message(&mut self, message_id: MessageId, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!("UPDATE tournament_matches SET message_id = $1 WHERE id = $2", message_id.get() as i64, self.id as i32)
+            .execute(tx.acquire().await?)
+            .await?;
+        self.message_id = Some(message_id.get() as i64);
+        Ok(())
+    }
+}
+
+/// what changed after a match was recorded
+#[derive(Debug, Default)]
+pub struct Advancement {
+    pub tournament: Option<Tournament>,
+    /// matches that are ready to be played, replays and the next round
+    pub opened: Vec<TournamentMatch>,
+    pub champion: Option<User>,
+}
+
+impl Advancement {
+    /// let the server know who won. failing to post shouldn't fail the match, so errors are only logged
+    pub async fn announce_champion(&self, ctx: &Context) {
+        let (tournament, champion) = match (&self.tournament, &self.champion) {
+            (Some(tournament), Some(champion)) => (tournament, champion),
+            _ => return,
+        };
+
+        let game = tournament.game().map(|game| game.display_name()).unwrap_or("the");
+        let content = format!("🏆 {} is the champion of the {} tournament! Congratulations!", UserId::new(champion.discord_id as u64).mention(), game);
+
+        if let Err(e) = ChannelId::new(tournament.channel_id as u64).send_message(&ctx.http, CreateMessage::default().content(content)).await {
+            log::trace!("Failed to announce champion: {}", e);
+        }
+    }
+}
@@ -4,14 +4,15 @@
 //     user_id integer NOT NULL REFERENCES users(id),
 //     opponent_id integer NOT NULL REFERENCES users(id), -- not really used, we double up on the entries so its easier to query
 //     won boolean NOT NULL, -- true if the user won, false if the user lost. we dont keep track of draws, draws are lame
-//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the game was played, NULL means it only counts globally
 // );
 
 use sqlx::{types::chrono, Acquire};
 
 use crate::anyhow::Result;
 
-use super::User;
+use super::{rating::RatedGame, RatingEntry, RatingFilters, User};
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct UltimateTicTacToe {
@@ -20,20 +21,22 @@ pub struct UltimateTicTacToe {
     pub opponent_id: i64,
     pub won: bool,
     created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
 }
 
 impl UltimateTicTacToe {
-    pub async fn create(user_id: i32, opponent_id: i32, won: bool, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+    pub async fn create(user_id: i32, opponent_id: i32, won: bool, guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
         let tictactoe = sqlx::query_as!(
             UltimateTicTacToe,
             r#"
-            INSERT INTO ultimate_tictactoe (user_id, opponent_id, won)
-            VALUES ($1, $2, $3)
+            INSERT INTO ultimate_tictactoe (user_id, opponent_id, won, guild_id)
+            VALUES ($1, $2, $3, $4)
             RETURNING *
             "#,
             user_id,
             opponent_id,
-            won
+            won,
+            guild_id
         )
         .fetch_one(tx.acquire().await?)
         .await?;
@@ -41,58 +44,19 @@ impl UltimateTicTacToe {
         Ok(tictactoe)
     }
 
-    pub async fn get_standings(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<UTTTLeaderboardEntry>, bool)> {
-        let (leaderboard, more) = UTTTLeaderboardEntryRaw::get_all_sorted(tx).await?;
-
-        let mut entries = Vec::new();
-
-        for entry in leaderboard {
-            let user = User::get_by_id(entry.user_id, tx).await?.ok_or(anyhow::anyhow!("No user found"))?;
-
-            let ratio = entry.ratio.ok_or(anyhow::anyhow!("No ratio found"))?;
-            let wins = entry.total.unwrap_or(0);
-
-            // ratio is wins - losses
-            let losses = wins - ratio;
-
-            // now that we've reconstructed the wins and losses, we can calculate the players rating.
-            let rating = super::calculate_rating(wins, losses);
-
-            entries.push(UTTTLeaderboardEntry { user, wins, losses, rating });
-        }
-
-        // sort entried by rating, highest to lowest
-        entries.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap_or(std::cmp::Ordering::Equal));
-
-        Ok((entries, more))
-    }
-}
-
-#[derive(Debug, sqlx::FromRow)]
-struct UTTTLeaderboardEntryRaw {
-    user_id: i64,
-    ratio: Option<i64>,
-    total: Option<i64>,
-}
-
-impl UTTTLeaderboardEntryRaw {
-    async fn get_all_sorted(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<Self>, bool)> {
-        let leaderboard = sqlx::query_as!(
-            Self,
-            r#"
-            SELECT user_id, SUM((won::integer * 2) - 1) as ratio, SUM(won::integer) as total FROM ultimate_tictactoe GROUP BY user_id ORDER BY ratio DESC
-            "#,
+    /// (wins, losses) for a single user
+    pub async fn get_record(user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(i64, i64)> {
+        let record = sqlx::query!(
+            r#"SELECT COUNT(*) FILTER (WHERE won) as "wins!", COUNT(*) FILTER (WHERE NOT won) as "losses!" FROM ultimate_tictactoe WHERE user_id = $1"#,
+            user.id as i32
         )
-        .fetch_all(tx.acquire().await?)
+        .fetch_one(tx.acquire().await?)
         .await?;
 
-        Ok((leaderboard, false))
+        Ok((record.wins, record.losses))
     }
-}
 
-pub struct UTTTLeaderboardEntry {
-    pub user: User,
-    pub wins: i64,
-    pub losses: i64,
-    pub rating: f64,
+    pub async fn get_standings(filters: RatingFilters, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(Vec<RatingEntry>, bool)> {
+        RatedGame::UltimateTicTacToe.standings(filters, tx).await
+    }
 }
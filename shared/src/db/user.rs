@@ -2,32 +2,43 @@
 //     id SERIAL PRIMARY KEY,
 //     name text NOT NULL, -- Will usually be the users global nickname, but can also be the users username. worst case scenario, it will be the users discord id
 //     discord_id bigint NOT NULL, -- The users discord id
+//     avatar text, -- last known avatar url, NULL if they don't have one (or we never found out)
+//     refreshed_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP -- when name and avatar were last checked against discord
 // );
 
-use sqlx::Acquire;
+use sqlx::{types::chrono, Acquire};
 
-use crate::anyhow::Result;
+use crate::{
+    anyhow::Result,
+    identity::{self, Identity},
+};
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct User {
     pub id: i64,
     pub name: String,
     pub discord_id: i64,
+    pub avatar: Option<String>,
+    pub refreshed_at: chrono::NaiveDateTime,
 }
 
 impl User {
+    /// looks up the user, creating them if needed. their name and avatar are kept up to date through the identity cache,
+    /// if discord can't be reached the stored ones are kept so recording a result never depends on it
     pub async fn get_or_create(ctx: &serenity::client::Context, discord_id: &serenity::model::id::UserId, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
-        let username = {
-            let user = discord_id.to_user(&ctx.http).await?;
-            match user.global_name {
-                Some(name) => name,
-                None => user.name,
-            }
-        };
         let user = match Self::get_by_discord_id(discord_id, tx).await? {
             Some(mut user) => {
-                if user.name != username {
-                    user.update_name(&username, tx).await?;
+                // only ask discord once what we've stored is old, anything the gateway has shown us recently is free
+                let stale = (chrono::Utc::now().naive_utc() - user.refreshed_at).to_std().map(|age| age > identity::TTL).unwrap_or(false);
+                let identity = match identity::get(discord_id) {
+                    Some(identity) => Some(identity),
+                    None if stale => identity::resolve(ctx, discord_id).await,
+                    None => None,
+                };
+                if let Some(identity) = identity {
+                    if stale || user.name != identity.name || user.avatar != identity.avatar {
+                        user.update_identity(&identity, tx).await?;
+                    }
                 }
                 user
             }
@@ -39,51 +50,49 @@ impl User {
         let row = sqlx::query_as!(Self, "SELECT * FROM users WHERE discord_id = $1", discord_id.to_string().parse::<i64>()?)
             .fetch_optional(tx.acquire().await?)
             .await?;
-        if let Some(row) = row {
-            Ok(Some(Self {
-                id: row.id,
-                name: row.name,
-                discord_id: row.discord_id,
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row)
     }
     pub async fn get_by_id(id: i64, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
         let row = sqlx::query_as!(Self, "SELECT * FROM users WHERE id = $1", id as i32).fetch_optional(tx.acquire().await?).await?;
-        if let Some(row) = row {
-            Ok(Some(Self {
-                id: row.id,
-                name: row.name,
-                discord_id: row.discord_id,
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row)
     }
     pub async fn create(ctx: &serenity::client::Context, discord_id: &serenity::model::id::UserId, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
-        let user = discord_id.to_user(&ctx.http).await?;
-
-        let nickname = user.global_name.unwrap_or(user.name);
+        // worst case scenario, they're stored as their discord id until we hear about them again
+        let identity = identity::resolve(ctx, discord_id).await.unwrap_or(Identity {
+            name: discord_id.to_string(),
+            avatar: None,
+        });
 
         let row = sqlx::query_as!(
             Self,
-            "INSERT INTO users (name, discord_id) VALUES ($1, $2) RETURNING *",
-            nickname,
-            discord_id.to_string().parse::<i64>()?
+            "INSERT INTO users (name, discord_id, avatar) VALUES ($1, $2, $3) RETURNING *",
+            identity.name,
+            discord_id.to_string().parse::<i64>()?,
+            identity.avatar
         )
         .fetch_one(tx.acquire().await?)
         .await?;
 
-        Ok(Self {
-            id: row.id,
-            name: row.name,
-            discord_id: row.discord_id,
+        Ok(row)
+    }
+    /// the freshest name and avatar we know of without asking discord, preferring the cache over what's stored
+    pub fn identity(&self) -> Identity {
+        identity::get(&serenity::model::id::UserId::new(self.discord_id as u64)).unwrap_or(Identity {
+            name: self.name.clone(),
+            avatar: self.avatar.clone(),
         })
     }
-    async fn update_name(&mut self, name: &str, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
-        sqlx::query!("UPDATE users SET name = $1 WHERE id = $2", name, self.id as i32).execute(tx.acquire().await?).await?;
-        self.name = name.to_string();
+    async fn update_identity(&mut self, identity: &Identity, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        let row = sqlx::query_as!(
+            Self,
+            "UPDATE users SET name = $1, avatar = $2, refreshed_at = CURRENT_TIMESTAMP WHERE id = $3 RETURNING *",
+            identity.name,
+            identity.avatar,
+            self.id as i32
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+        *self = row;
         Ok(())
     }
 }
@@ -0,0 +1,182 @@
+// CREATE TABLE IF NOT EXISTS wordle_guesses (
+//     -- guesses live here rather than in the message, so a second message for the same day can't start the word over
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     day integer NOT NULL, -- days since the unix epoch, everyone gets the same word on the same day
+//     number integer NOT NULL, -- 1 is the first guess
+//     guess text NOT NULL,
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+// CREATE TABLE IF NOT EXISTS wordle (
+//     -- one row per player per day, written when the word is solved or the guesses run out
+//     id SERIAL PRIMARY KEY,
+//     user_id integer NOT NULL REFERENCES users(id),
+//     day integer NOT NULL,
+//     solved boolean NOT NULL,
+//     guesses integer NOT NULL,
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     guild_id bigint -- where the game was played, NULL means it only counts globally
+// );
+
+// CREATE TABLE IF NOT EXISTS wordle_days (
+//     -- the word is picked the first time anyone plays that day, so it doesn't depend on anything that can change mid-day
+//     day integer PRIMARY KEY, -- days since the unix epoch
+//     word text NOT NULL,
+//     created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+// CREATE TABLE IF NOT EXISTS wordle_streaks (
+//     user_id integer PRIMARY KEY REFERENCES users(id),
+//     current integer NOT NULL, -- days solved in a row, up to last_day
+//     best integer NOT NULL,
+//     last_day integer NOT NULL -- the last day a result was recorded
+// );
+
+use sqlx::{types::chrono, Acquire};
+
+use crate::anyhow::Result;
+
+use super::User;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Wordle {
+    id: i64,
+    user_id: i64,
+    pub day: i32,
+    pub solved: bool,
+    pub guesses: i32,
+    created_at: chrono::NaiveDateTime,
+    pub guild_id: Option<i64>,
+}
+
+impl Wordle {
+    /// records the day's result and carries the streak along, returning the streak after it
+    pub async fn create(user: &User, day: i32, solved: bool, guesses: i32, guild_id: Option<i64>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<WordleStreak> {
+        sqlx::query!(
+            r#"
+            INSERT INTO wordle (user_id, day, solved, guesses, guild_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            user.id as i32,
+            day,
+            solved,
+            guesses,
+            guild_id
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+
+        WordleStreak::record(user, day, solved, tx).await
+    }
+
+    pub async fn get(user: &User, day: i32, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let wordle = sqlx::query_as!(Self, "SELECT * FROM wordle WHERE user_id = $1 AND day = $2", user.id as i32, day)
+            .fetch_optional(tx.acquire().await?)
+            .await?;
+
+        Ok(wordle)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct WordleGuess {
+    id: i64,
+    user_id: i64,
+    pub day: i32,
+    pub number: i32,
+    pub guess: String,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl WordleGuess {
+    /// fails if that guess number is already taken, which is how a doubled up submit gets turned away
+    pub async fn create(user: &User, day: i32, number: i32, guess: &str, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO wordle_guesses (user_id, day, number, guess)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            user.id as i32,
+            day,
+            number,
+            guess
+        )
+        .execute(tx.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// the user's guesses for the day in the order they were made
+    pub async fn for_day(user: &User, day: i32, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Vec<Self>> {
+        let guesses = sqlx::query_as!(Self, "SELECT * FROM wordle_guesses WHERE user_id = $1 AND day = $2 ORDER BY number ASC", user.id as i32, day)
+            .fetch_all(tx.acquire().await?)
+            .await?;
+
+        Ok(guesses)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct WordleStreak {
+    user_id: i64,
+    pub current: i32,
+    pub best: i32,
+    pub last_day: i32,
+}
+
+impl WordleStreak {
+    pub async fn get(user: &User, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Option<Self>> {
+        let streak = sqlx::query_as!(Self, "SELECT * FROM wordle_streaks WHERE user_id = $1", user.id as i32)
+            .fetch_optional(tx.acquire().await?)
+            .await?;
+
+        Ok(streak)
+    }
+
+    /// a solve the day after the last one carries the streak on, anything else starts it over
+    async fn record(user: &User, day: i32, solved: bool, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        let current = match Self::get(user, tx).await? {
+            Some(streak) if solved && streak.last_day == day - 1 => streak.current + 1,
+            _ if solved => 1,
+            _ => 0,
+        };
+        let streak = sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO wordle_streaks (user_id, current, best, last_day)
+            VALUES ($1, $2, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE SET current = $2, best = GREATEST(wordle_streaks.best, $2), last_day = $3
+            RETURNING *
+            "#,
+            user.id as i32,
+            current,
+            day
+        )
+        .fetch_one(tx.acquire().await?)
+        .await?;
+
+        Ok(streak)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct WordleDay {
+    pub day: i32,
+    pub word: String,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl WordleDay {
+    /// the day's word. the first player of the day sets it to `word`, everyone after gets what they set
+    pub async fn get_or_create(day: i32, word: &str, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<Self> {
+        sqlx::query!("INSERT INTO wordle_days (day, word) VALUES ($1, $2) ON CONFLICT (day) DO NOTHING", day, word)
+            .execute(tx.acquire().await?)
+            .await?;
+
+        let wordle_day = sqlx::query_as!(Self, "SELECT * FROM wordle_days WHERE day = $1", day).fetch_one(tx.acquire().await?).await?;
+
+        Ok(wordle_day)
+    }
+}
@@ -0,0 +1,78 @@
+// in-memory cache of what discord users are called and what they look like.
+// anything we see through the gateway (interactions, member updates) is dropped in here for free,
+// so recording a result or rendering a leaderboard rarely has to ask discord about anyone.
+// entries expire so renames eventually make it back into the users table.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use serenity::all::*;
+
+/// how long an entry is trusted before it's looked up again
+pub const TTL: Duration = Duration::from_secs(60 * 30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub avatar: Option<String>,
+}
+
+impl From<&User> for Identity {
+    fn from(user: &User) -> Self {
+        Self {
+            name: user.global_name.clone().unwrap_or(user.name.clone()),
+            avatar: user.avatar_url(),
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<UserId, (Identity, Instant)>> {
+    static CACHE: OnceLock<Mutex<HashMap<UserId, (Identity, Instant)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// the cached identity, if it's still fresh
+pub fn get(discord_id: &UserId) -> Option<Identity> {
+    let cache = cache().lock().ok()?;
+    cache.get(discord_id).filter(|(_, seen)| seen.elapsed() < TTL).map(|(identity, _)| identity.clone())
+}
+
+pub fn insert(discord_id: UserId, identity: Identity) {
+    if let Ok(mut cache) = cache().lock() {
+        // drop anything stale while we're here so the map doesn't grow forever
+        if cache.len() > 10_000 {
+            cache.retain(|_, (_, seen)| seen.elapsed() < TTL);
+        }
+        cache.insert(discord_id, (identity, Instant::now()));
+    }
+}
+
+/// remember a user we were handed by the gateway
+pub fn observe(user: &User) {
+    if user.bot {
+        return;
+    }
+    insert(user.id, Identity::from(user));
+}
+
+/// the cached identity, falling back to asking discord. returns None if discord can't be reached (rate limits, outages),
+/// callers should fall back to whatever they have stored instead of failing
+pub async fn resolve(ctx: &Context, discord_id: &UserId) -> Option<Identity> {
+    if let Some(identity) = get(discord_id) {
+        return Some(identity);
+    }
+    match discord_id.to_user(&ctx.http).await {
+        Ok(user) => {
+            let identity = Identity::from(&user);
+            insert(*discord_id, identity.clone());
+            Some(identity)
+        }
+        Err(e) => {
+            log::trace!("Failed to resolve user {}: {}", discord_id, e);
+            None
+        }
+    }
+}
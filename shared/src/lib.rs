@@ -15,6 +15,7 @@ pub use serenity;
 pub use sqlx;
 
 pub mod db;
+pub mod identity;
 
 pub type OptTrans<'a> = Option<sqlx::Transaction<'a, sqlx::Postgres>>;
 
@@ -35,7 +36,7 @@ where
         let mut b = CreateCommand::new(info.name);
         b = b.description(info.description);
         for option in info.options.0 {
-            b = b.add_option(CreateCommandOption::new(option.option_type.into(), option.name, option.description).required(option.required));
+            b = b.add_option(option.into());
         }
         b
     }
@@ -195,6 +196,28 @@ impl From<serenity::model::application::CommandOption> for CommandOption {
     }
 }
 
+impl From<CommandOption> for CreateCommandOption {
+    fn from(option: CommandOption) -> Self {
+        let (kind, sub_options) = match option.option_type {
+            CommandOptionType::SubCommand(sub_options) => (serenity::model::application::CommandOptionType::SubCommand, sub_options.0),
+            CommandOptionType::SubCommandGroup(sub_options) => (serenity::model::application::CommandOptionType::SubCommandGroup, sub_options.0),
+            option_type => (option_type.into(), Vec::new()),
+        };
+        let mut b = CreateCommandOption::new(kind, option.name, option.description).required(option.required);
+        for choice in option.choices.0 {
+            b = match kind {
+                serenity::model::application::CommandOptionType::Integer => b.add_int_choice(choice.name, choice.value.parse().unwrap_or_default()),
+                serenity::model::application::CommandOptionType::Number => b.add_number_choice(choice.name, choice.value.parse().unwrap_or_default()),
+                _ => b.add_string_choice(choice.name, choice.value),
+            };
+        }
+        for sub_option in sub_options {
+            b = b.add_sub_option(sub_option.into());
+        }
+        b
+    }
+}
+
 impl CommandOption {
     pub fn populate_subcommands(&mut self, command_option: &serenity::model::application::CommandOption) {
         match &mut self.option_type {
@@ -330,6 +353,191 @@ impl<T> CycleVec<T> {
     pub fn all(&self) -> impl Iterator<Item = &T> {
         self.vec.iter()
     }
+    pub fn push(&mut self, item: T) {
+        self.vec.push(item);
+    }
+    /// reshuffles the order, starting again from whoever ends up first
+    pub fn shuffle(&mut self) {
+        self.vec.shuffle(&mut rand::thread_rng());
+        self.index = 0;
+    }
+    /// the same order moved along by one, so whoever went second goes first
+    pub fn rotated(&self) -> Self
+    where
+        T: Clone,
+    {
+        let mut vec = self.vec.clone();
+        vec.rotate_left(1);
+        Self { vec, index: 0 }
+    }
+}
+
+/// a best of n series between the same players, carried along from game to game
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Series {
+    best_of: u8,       // 0 or 1 is a single game
+    wins: Vec<UserId>, // who won each decided game, ties aren't counted
+    played: u8,        // games played so far, ties included
+}
+
+impl Series {
+    pub fn new(best_of: u8) -> Self {
+        Self { best_of, ..Default::default() }
+    }
+    pub fn best_of(&self) -> u8 {
+        self.best_of.max(1)
+    }
+    pub fn is_series(&self) -> bool {
+        self.best_of > 1
+    }
+    pub fn played(&self) -> u8 {
+        self.played
+    }
+    pub fn wins(&self, player: UserId) -> u8 {
+        self.wins.iter().filter(|id| **id == player).count() as u8
+    }
+    /// records a finished game, `None` being a tie
+    pub fn record(&mut self, winner: Option<UserId>) {
+        self.played += 1;
+        if let Some(winner) = winner {
+            self.wins.push(winner);
+        }
+    }
+    /// whoever has won more than half of the games, once someone has. a single game is never a series
+    pub fn winner(&self) -> Option<UserId> {
+        if !self.is_series() {
+            return None;
+        }
+        self.wins.iter().find(|id| self.wins(**id) > self.best_of() / 2).copied()
+    }
+    /// the series the next game belongs to, a fresh one once this one is decided
+    pub fn next(&self) -> Self {
+        if !self.is_series() || self.winner().is_some() {
+            return Self::new(self.best_of);
+        }
+        self.clone()
+    }
+    /// the score so far, like `@a 2 - @b 1`. players are sorted so the order doesn't flip between games
+    pub fn score(&self, players: impl Iterator<Item = UserId>) -> String {
+        let mut players = players.collect::<Vec<UserId>>();
+        players.sort();
+        players.iter().map(|player| format!("{} {}", player.mention(), self.wins(*player))).collect::<Vec<String>>().join(" - ")
+    }
+}
+
+/// a game being played in its own thread, the challenge message it started from stays behind in the parent channel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct GameThread {
+    pub id: ChannelId,
+    pub parent: ChannelId,
+    pub origin: MessageId,        // the challenge message, it gets the result once the game is over
+    last_ping: Option<MessageId>, // the last turn ping, replaced every turn so the thread doesn't fill up
+}
+
+impl GameThread {
+    /// starts a thread on the challenge message with a summary for anyone watching. threads archive themselves after an hour without moves
+    pub async fn open(ctx: &Context, parent: ChannelId, origin: MessageId, name: String, summary: String) -> Result<Self> {
+        let thread = parent
+            .create_thread_from_message(&ctx.http, origin, CreateThread::new(name).auto_archive_duration(AutoArchiveDuration::OneHour))
+            .await?;
+        thread.id.send_message(&ctx.http, CreateMessage::default().content(summary)).await?;
+        Ok(Self {
+            id: thread.id,
+            parent,
+            origin,
+            last_ping: None,
+        })
+    }
+    /// pings whoever's turn it is. failing to ping shouldn't stop the move, so errors are only logged
+    pub async fn ping(&mut self, ctx: &Context, player: UserId) {
+        if let Some(last_ping) = self.last_ping.take() {
+            if let Err(e) = self.id.delete_message(&ctx.http, last_ping).await {
+                log::trace!("Failed to delete turn ping: {}", e);
+            }
+        }
+        match self.id.send_message(&ctx.http, CreateMessage::default().content(format!("{} it's your turn", player.mention()))).await {
+            Ok(message) => self.last_ping = Some(message.id),
+            Err(e) => log::trace!("Failed to send turn ping: {}", e),
+        }
+    }
+    /// mirrors the result onto the challenge message and archives the thread. the game is already over by now, so errors are only logged
+    pub async fn close(&self, ctx: &Context, result: String) {
+        if let Some(last_ping) = self.last_ping {
+            if let Err(e) = self.id.delete_message(&ctx.http, last_ping).await {
+                log::trace!("Failed to delete turn ping: {}", e);
+            }
+        }
+        if let Err(e) = self.parent.edit_message(&ctx.http, self.origin, EditMessage::new().content(result).components(vec![])).await {
+            log::error!("Failed to mirror result to the parent channel: {}", e);
+        }
+        if let Err(e) = self.id.edit_thread(&ctx.http, EditThread::new().archived(true)).await {
+            log::error!("Failed to archive game thread: {}", e);
+        }
+    }
+}
+
+/// a game played over dms, for players who might not share a server. each player gets their own copy of the board and every update is copied to the other
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct DmGame {
+    copies: [(UserId, ChannelId, MessageId); 2],
+}
+
+impl DmGame {
+    /// posts a placeholder to each player. the copies need each other's ids in their state, so fill them in with `update` once this returns
+    pub async fn open(ctx: &Context, players: (UserId, UserId)) -> Result<Self> {
+        let mut copies = vec![];
+        for player in [players.0, players.1] {
+            let dm = player.create_dm_channel(&ctx.http).await?;
+            let message = dm.id.send_message(&ctx.http, CreateMessage::default().content("Setting up your game...")).await?;
+            copies.push((player, dm.id, message.id));
+        }
+        Ok(Self { copies: [copies[0], copies[1]] })
+    }
+    /// shows the game on both copies
+    pub async fn update(&self, ctx: &Context, content: String, components: Vec<CreateActionRow>) -> Result<()> {
+        for (_, channel_id, message_id) in self.copies {
+            channel_id
+                .edit_message(&ctx.http, message_id, EditMessage::new().content(content.clone()).components(components.clone()))
+                .await?;
+        }
+        Ok(())
+    }
+    /// copies an update to the copy that wasn't clicked. the move is already saved in the clicked one, so errors are only logged
+    pub async fn sync(&self, ctx: &Context, clicked: MessageId, content: String, components: Vec<CreateActionRow>) {
+        for (_, channel_id, message_id) in self.copies.into_iter().filter(|(_, _, message_id)| *message_id != clicked) {
+            if let Err(e) = channel_id
+                .edit_message(&ctx.http, message_id, EditMessage::new().content(content.clone()).components(components.clone()))
+                .await
+            {
+                log::error!("Failed to copy the game to the other player's dms: {}", e);
+            }
+        }
+    }
+    /// the player's own copy of the game
+    pub fn link(&self, player: UserId) -> Option<String> {
+        self.copies
+            .iter()
+            .find(|(id, _, _)| *id == player)
+            .map(|(_, channel_id, message_id)| format!("https://discord.com/channels/@me/{}/{}", channel_id, message_id))
+    }
+}
+
+/// whether someone is allowed to play against themselves, handy for testing
+pub fn allow_self_play() -> bool {
+    std::env::var("ALLOW_SELF_PLAY").ok().and_then(|s| s.parse::<bool>().ok()).unwrap_or(false)
+}
+
+/// the key for game state that can't go in the message where players can read it, like where the mines are.
+/// without STATE_SECRET set a random one is made at startup, so those games can't be carried on after a restart
+pub fn state_secret() -> &'static [u8] {
+    static SECRET: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+    SECRET.get_or_init(|| match std::env::var("STATE_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => {
+            log::warn!("STATE_SECRET not set, hidden game state won't survive a restart");
+            rand::random::<[u8; 32]>().to_vec()
+        }
+    })
 }
 
 pub fn current_time() -> Result<u64> {
@@ -363,6 +571,25 @@ pub fn format_duration(t: u64) -> String {
     s.trim().to_string()
 }
 
+/// `format_duration` down to the millisecond, for timed puzzles
+pub fn format_duration_ms(ms: u64) -> String {
+    let seconds = ms / 1000;
+    let fraction = format!("{}.{:03}s", seconds % 60, ms % 1000);
+    if seconds < 60 {
+        fraction
+    } else {
+        // minutes and up are whole, so format_duration handles them
+        format!("{} {}", format_duration(seconds - seconds % 60), fraction)
+    }
+}
+
+/// when a discord id was created, in milliseconds since the unix epoch.
+/// interaction ids are made when the user clicks, so they time the click itself rather than when we got around to it
+pub fn snowflake_millis(id: u64) -> u64 {
+    const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+    (id >> 22) + DISCORD_EPOCH
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct CommandOptionChoice {
     pub name: String,